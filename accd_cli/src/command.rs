@@ -0,0 +1,215 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Focus(i32),
+    Camera(String, String),
+    Hud(String),
+    Replay(f32),
+    Profiles,
+    Profile(String),
+    Connect,
+    Disconnect,
+    /// Records the datagrams to a file, `None` stops recording.
+    Record(Option<String>),
+    /// Exports laps, events and car updates to a directory, `None` stops exporting.
+    Export(Option<String>),
+    Help,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    Empty,
+    Unknown(String),
+    MissingArgument(&'static str),
+    InvalidArgument(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "empty command"),
+            CommandError::Unknown(cmd) => write!(f, "unknown command \"{}\", try \"help\"", cmd),
+            CommandError::MissingArgument(usage) => write!(f, "missing argument, usage: {}", usage),
+            CommandError::InvalidArgument(arg) => write!(f, "invalid argument \"{}\"", arg),
+        }
+    }
+}
+
+pub const HELP: &str = "\
+focus <race#>        focus the car with the given race number
+camera <set> <cam>   switch to a camera of the given camera set
+hud <page>           change the HUD page
+replay <secs>        instant replay of the last <secs> seconds
+profiles             list the connection profiles
+profile <name>       disconnect and connect with the given profile
+connect              connect again with the current profile
+disconnect           disconnect from ACC without exiting
+record <file>|off    record what ACC sends to a file, to play it back later
+export <dir>|off     export laps, events and car updates of every session as CSV and JSONL
+help                 show this help
+quit                 disconnect and exit";
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, CommandError> {
+        let line = line.trim();
+        let (cmd, args) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+
+        match cmd {
+            "" => Err(CommandError::Empty),
+            "focus" => {
+                let race_number = first_arg(args, "focus <race#>")?;
+                match race_number.trim_start_matches('#').parse::<i32>() {
+                    Ok(race_number) => Ok(Command::Focus(race_number)),
+                    Err(_) => Err(CommandError::InvalidArgument(race_number.to_string())),
+                }
+            }
+            "camera" => {
+                let mut splits = args.split_whitespace();
+                match (splits.next(), splits.next()) {
+                    (Some(camera_set), Some(camera)) => {
+                        Ok(Command::Camera(camera_set.to_string(), camera.to_string()))
+                    }
+                    _ => Err(CommandError::MissingArgument("camera <set> <cam>")),
+                }
+            }
+            "hud" => {
+                // HUD pages can contain spaces (e.g. "Basic HUD"), take the whole line.
+                if args.is_empty() {
+                    Err(CommandError::MissingArgument("hud <page>"))
+                } else {
+                    Ok(Command::Hud(args.to_string()))
+                }
+            }
+            "replay" => {
+                let seconds = first_arg(args, "replay <secs>")?;
+                match seconds.parse::<f32>() {
+                    Ok(seconds) if seconds > 0.0 => Ok(Command::Replay(seconds)),
+                    _ => Err(CommandError::InvalidArgument(seconds.to_string())),
+                }
+            }
+            "profiles" => Ok(Command::Profiles),
+            "profile" => {
+                let name = first_arg(args, "profile <name>")?;
+                Ok(Command::Profile(name.to_string()))
+            }
+            "connect" => Ok(Command::Connect),
+            "disconnect" => Ok(Command::Disconnect),
+            "record" => match first_arg(args, "record <file>|off")? {
+                "off" => Ok(Command::Record(None)),
+                path => Ok(Command::Record(Some(path.to_string()))),
+            },
+            "export" => match first_arg(args, "export <dir>|off")? {
+                "off" => Ok(Command::Export(None)),
+                path => Ok(Command::Export(Some(path.to_string()))),
+            },
+            "help" => Ok(Command::Help),
+            "quit" | "exit" => Ok(Command::Quit),
+            _ => Err(CommandError::Unknown(cmd.to_string())),
+        }
+    }
+}
+
+fn first_arg<'a>(args: &'a str, usage: &'static str) -> Result<&'a str, CommandError> {
+    match args.split_whitespace().next() {
+        Some(arg) => Ok(arg),
+        None => Err(CommandError::MissingArgument(usage)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_command() {
+        let cases = vec![
+            ("focus 7", Command::Focus(7)),
+            ("focus #99", Command::Focus(99)),
+            (
+                "camera set1 Onboard",
+                Command::Camera("set1".to_string(), "Onboard".to_string()),
+            ),
+            ("hud Basic HUD", Command::Hud("Basic HUD".to_string())),
+            ("replay 12.5", Command::Replay(12.5)),
+            ("profiles", Command::Profiles),
+            ("profile server2", Command::Profile("server2".to_string())),
+            ("connect", Command::Connect),
+            ("disconnect", Command::Disconnect),
+            (
+                "record race.accd",
+                Command::Record(Some("race.accd".to_string())),
+            ),
+            ("record off", Command::Record(None)),
+            ("export out", Command::Export(Some("out".to_string()))),
+            ("export off", Command::Export(None)),
+            ("help", Command::Help),
+            ("quit", Command::Quit),
+            ("exit", Command::Quit),
+            ("  focus   3  ", Command::Focus(3)),
+        ];
+        for (line, command) in cases {
+            assert_eq!(Command::parse(line), Ok(command), "{}", line);
+        }
+    }
+
+    #[test]
+    fn missing_arguments_show_the_usage() {
+        for (line, usage) in [
+            ("focus", "focus <race#>"),
+            ("camera set1", "camera <set> <cam>"),
+            ("hud", "hud <page>"),
+            ("replay", "replay <secs>"),
+            ("profile", "profile <name>"),
+            ("record", "record <file>|off"),
+            ("export  ", "export <dir>|off"),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Command::parse(line),
+                Err(CommandError::MissingArgument(usage)),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        for (line, arg) in [
+            ("focus seven", "seven"),
+            ("focus #", "#"),
+            ("replay soon", "soon"),
+            ("replay 0", "0"),
+            ("replay -5", "-5"),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Command::parse(line),
+                Err(CommandError::InvalidArgument(arg.to_string())),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_and_empty_input() {
+        assert_eq!(Command::parse(""), Err(CommandError::Empty));
+        assert_eq!(Command::parse("   "), Err(CommandError::Empty));
+        assert_eq!(
+            Command::parse("jump 3"),
+            Err(CommandError::Unknown("jump".to_string()))
+        );
+        assert_eq!(
+            Command::parse("Focus 3"),
+            Err(CommandError::Unknown("Focus".to_string()))
+        );
+    }
+}
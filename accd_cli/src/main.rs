@@ -0,0 +1,466 @@
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use accd_core::accd_broadcasting_event::ACCDBroadcastingEvent;
+use accd_core::accd_car_info::ACCDCarInfo;
+use accd_core::accd_config::ACCDConfig;
+use accd_core::accd_config_loader::{ACCDConfigLoader, USAGE};
+use accd_core::accd_export::ACCDExporter;
+use accd_core::accd_grid::ACCDGridEvent;
+use accd_core::accd_pit_strategy::ACCDPitEvent;
+use accd_core::accd_protocol::{ACCDProtocol, ListenResult};
+use accd_core::accd_realtime_update::ACCDRealtimeUpdate;
+use accd_core::accd_recording::ACCDRecordingPlayer;
+use accd_core::accd_session_tracker::ACCDSessionEvent;
+use accd_core::accd_speed_trap::{ACCDSpeedEvent, ACCDSpeedRecordKind};
+
+mod command;
+use crate::command::{Command, HELP};
+
+#[derive(Default)]
+struct CliState {
+    entry_list_cars: Vec<ACCDCarInfo>,
+    realtime_update: ACCDRealtimeUpdate,
+    exporter: Option<ACCDExporter>,
+}
+
+fn main() {
+    let loader = match ACCDConfigLoader::from_env() {
+        Ok(loader) => loader,
+        Err(e) => {
+            println!("ERROR: {}\n{}", e, USAGE);
+            process::exit(1);
+        }
+    };
+    // accd_cli play <recording> [export dir]
+    if loader.args.first().map(String::as_str) == Some("play") {
+        match loader.args.get(1) {
+            Some(recording) => play(Path::new(recording), loader.args.get(2).map(Path::new)),
+            None => {
+                println!("ERROR: missing recording, usage: accd_cli play <recording> [export dir]")
+            }
+        }
+        return;
+    }
+    if let Some(arg) = loader.args.first() {
+        println!("ERROR: unexpected argument {}\n{}", arg, USAGE);
+        process::exit(1);
+    }
+
+    let config = match loader.load() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if loader.print_config {
+        print!("{}", config.to_toml(true));
+        return;
+    }
+
+    let mut accdp = ACCDProtocol::new(config);
+    print_connecting(&accdp.config);
+    if let Err(e) = accdp.connect() {
+        println!("ERROR({}): {}", accdp.config.bind_addr, e);
+        process::exit(1);
+    }
+    println!("Type \"help\" for the list of commands");
+
+    let accdp = Arc::new(Mutex::new(accdp));
+    let state = Arc::new(Mutex::new(CliState::default()));
+
+    let c_accdp = Arc::clone(&accdp);
+    let c_state = Arc::clone(&state);
+    thread::spawn(move || loop {
+        // The protocol stays free for the commands while waiting for ACC.
+        let listen_result = ACCDProtocol::listen_shared(&c_accdp);
+        export_listen_result(&listen_result, &mut c_state.lock().unwrap());
+        print_listen_result(listen_result, &c_state);
+    });
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                println!("ERROR: {}", e);
+                break;
+            }
+        };
+
+        match Command::parse(&line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => run_command(command, &accdp, &state),
+            Err(command::CommandError::Empty) => {}
+            Err(e) => println!("ERROR: {}", e),
+        }
+    }
+
+    // The listener thread keeps running until the process exits, write what's buffered.
+    state.lock().unwrap().exporter = None;
+    let mut accdp = accdp.lock().unwrap();
+    accdp.stop_recording();
+    accdp.disconnect();
+}
+
+/// Prints a recording as if it was received live, or exports it to `export_dir`.
+fn play(recording: &Path, export_dir: Option<&Path>) {
+    let player = match ACCDRecordingPlayer::open(recording) {
+        Ok(player) => player,
+        Err(e) => {
+            println!("ERROR({}): {}", recording.display(), e);
+            process::exit(1);
+        }
+    };
+
+    let state = Arc::new(Mutex::new(CliState {
+        exporter: export_dir.map(ACCDExporter::new),
+        ..CliState::default()
+    }));
+    for (_, listen_result) in player {
+        if export_dir.is_none() {
+            print_listen_result(listen_result, &state);
+            continue;
+        }
+
+        let mut state = state.lock().unwrap();
+        export_listen_result(&listen_result, &mut state);
+        if state.exporter.is_none() {
+            return;
+        }
+    }
+
+    if let Some(dir) = export_dir {
+        println!("Exported to {}", dir.display());
+    }
+}
+
+fn export_listen_result(listen_result: &ListenResult, state: &mut CliState) {
+    if let Some(exporter) = &mut state.exporter {
+        if let Err(e) = exporter.update(listen_result) {
+            println!("ERROR({}): {}, export stopped", exporter.dir.display(), e);
+            state.exporter = None;
+        }
+    }
+}
+
+fn print_connecting(config: &ACCDConfig) {
+    match &config.active_profile {
+        Some(name) => println!(
+            "Connecting to {} (profile {})",
+            config.destination_addr, name
+        ),
+        None => println!("Connecting to {}", config.destination_addr),
+    }
+}
+
+/// Drops what was received from the previous ACC instance and connects with `config`.
+fn reconnect(accdp: &Arc<Mutex<ACCDProtocol>>, state: &Arc<Mutex<CliState>>, config: ACCDConfig) {
+    let mut accdp = accdp.lock().unwrap();
+    let mut state = state.lock().unwrap();
+    *state = CliState {
+        exporter: state.exporter.take(),
+        ..CliState::default()
+    };
+
+    print_connecting(&config);
+    let bind_addr = config.bind_addr;
+    if let Err(e) = accdp.reconnect(config) {
+        println!("ERROR({}): {}", bind_addr, e);
+    }
+}
+
+fn run_command(command: Command, accdp: &Arc<Mutex<ACCDProtocol>>, state: &Arc<Mutex<CliState>>) {
+    match command {
+        Command::Focus(race_number) => {
+            let car_index = state
+                .lock()
+                .unwrap()
+                .entry_list_cars
+                .iter()
+                .find(|car_info| car_info.race_number == race_number)
+                .map(|car_info| car_info.car_index);
+
+            match car_index {
+                Some(car_index) => {
+                    accdp.lock().unwrap().set_focus(
+                        Some(car_index),
+                        "".to_string(),
+                        "".to_string(),
+                    );
+                }
+                None => println!("ERROR: no car with race number #{}", race_number),
+            }
+        }
+        Command::Camera(camera_set, camera) => {
+            accdp.lock().unwrap().set_camera(camera_set, camera);
+        }
+        Command::Hud(hud_page) => {
+            accdp.lock().unwrap().request_hud_page(hud_page);
+        }
+        Command::Replay(seconds) => {
+            let realtime_update = state.lock().unwrap().realtime_update.clone();
+            let start_time = realtime_update.session_time.as_millis() as f32;
+
+            accdp.lock().unwrap().request_instant_replay(
+                start_time - (seconds * 1000.0),
+                seconds * 1000.0,
+                realtime_update.focused_car_index,
+                realtime_update.active_camera_set,
+                realtime_update.active_camera,
+            );
+        }
+        Command::Profiles => {
+            let accdp = accdp.lock().unwrap();
+            let names = accdp.config.profile_names();
+            if names.is_empty() {
+                println!("No profiles in the config file");
+            }
+            for name in names {
+                let marker = if accdp.config.active_profile.as_deref() == Some(name) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{} {}", marker, name);
+            }
+        }
+        Command::Profile(name) => {
+            let mut config = accdp.lock().unwrap().config.clone();
+            match config.activate_profile(&name) {
+                Ok(()) => reconnect(accdp, state, config),
+                Err(e) => println!("ERROR: {}", e),
+            }
+        }
+        Command::Connect => {
+            let config = accdp.lock().unwrap().config.clone();
+            reconnect(accdp, state, config);
+        }
+        Command::Disconnect => {
+            let mut accdp = accdp.lock().unwrap();
+            if accdp.is_connected() {
+                accdp.disconnect();
+            } else {
+                println!("ERROR: not connected");
+            }
+        }
+        Command::Record(path) => {
+            let mut accdp = accdp.lock().unwrap();
+            match path {
+                Some(path) => match accdp.start_recording(Path::new(&path)) {
+                    Ok(()) => println!("Recording to {}", path),
+                    Err(e) => println!("ERROR({}): {}", path, e),
+                },
+                None if accdp.stop_recording() => println!("Recording stopped"),
+                None => println!("ERROR: not recording"),
+            }
+        }
+        Command::Export(dir) => {
+            let mut state = state.lock().unwrap();
+            match dir {
+                Some(dir) => {
+                    state.exporter = Some(ACCDExporter::new(Path::new(&dir)));
+                    println!("Exporting to {}", dir);
+                }
+                None if state.exporter.take().is_some() => println!("Export stopped"),
+                None => println!("ERROR: not exporting"),
+            }
+        }
+        Command::Help => println!("{}", HELP),
+        Command::Quit => {}
+    }
+}
+
+fn print_listen_result(listen_result: ListenResult, state: &Arc<Mutex<CliState>>) {
+    match listen_result {
+        ListenResult::RegistrationResult(reg_result) => {
+            if reg_result.connection_success > 0 {
+                println!(
+                    "Connected | id: {} | read only: {}",
+                    reg_result.connection_id,
+                    reg_result.is_read_only > 0
+                );
+            } else {
+                println!(
+                    "Connection refused: {}",
+                    reg_result.err_msg.trim_matches(char::from(0))
+                );
+            }
+        }
+        ListenResult::TrackData(trk_data) => {
+            println!(
+                "Track: {} ({} meters)",
+                trk_data.track_name, trk_data.track_meters
+            );
+
+            let mut camera_sets: Vec<_> = trk_data.camera_sets.iter().collect();
+            camera_sets.sort();
+            for (camera_set, cameras) in camera_sets {
+                println!("  cameras {}: {}", camera_set, cameras.join(", "));
+            }
+            println!("  hud pages: {}", trk_data.hud_pages.join(", "));
+        }
+        ListenResult::EntryList(entry_list_cars) => {
+            println!("Entry list: {} cars", entry_list_cars.len());
+            state.lock().unwrap().entry_list_cars = entry_list_cars;
+        }
+        ListenResult::EntryListCar(car_info) => {
+            let driver_name = match car_info.drivers.get(car_info.current_driver_index as usize) {
+                Some(driver) => format!("{} {}", driver.first_name, driver.last_name),
+                None => "".to_string(),
+            };
+            println!(
+                "  #{} | {} | {}",
+                car_info.race_number, car_info.team_name, driver_name
+            );
+
+            if let Some(car) = state
+                .lock()
+                .unwrap()
+                .entry_list_cars
+                .iter_mut()
+                .find(|car| car.car_index == car_info.car_index)
+            {
+                *car = car_info;
+            }
+        }
+        ListenResult::RealTimeUpdate(rtu) => {
+            let mut state = state.lock().unwrap();
+            if rtu.session_type != state.realtime_update.session_type
+                || rtu.phase != state.realtime_update.phase
+            {
+                println!(
+                    "Session: {:?} | phase: {:?} | time: {} | end time: {} | air: {} | track: {}",
+                    rtu.session_type,
+                    rtu.phase,
+                    rtu.session_time,
+                    rtu.session_end_time,
+                    rtu.ambient_temp,
+                    rtu.track_temp
+                );
+            }
+            state.realtime_update = rtu;
+        }
+        ListenResult::BroadcastingEvent(broadcasting_event) => {
+            print_broadcasting_event(&broadcasting_event);
+        }
+        ListenResult::Session(ACCDSessionEvent::SessionStarted(session)) => {
+            println!("Session started: {}", session);
+        }
+        ListenResult::Session(ACCDSessionEvent::SessionEnded(session)) => {
+            println!("Session ended: {}", session);
+        }
+        ListenResult::Session(ACCDSessionEvent::EventChanged { event_index, .. }) => {
+            println!("Event changed: {}", event_index);
+        }
+        ListenResult::Grid(ACCDGridEvent::GridCaptured(grid)) => {
+            let slots: Vec<String> = grid
+                .slots
+                .iter()
+                .map(|slot| format!("{}. #{}", slot.position, slot.race_number))
+                .collect();
+            println!("Grid: {}", slots.join(" | "));
+        }
+        ListenResult::Grid(ACCDGridEvent::FirstLapMover(change)) => {
+            println!(
+                "First lap: #{} | P{} -> P{} ({:+}) | {:?} P{} -> P{} ({:+})",
+                change.race_number,
+                change.grid_position,
+                change.position,
+                change.gained,
+                change.car_class,
+                change.class_grid_position,
+                change.class_position,
+                change.class_gained
+            );
+        }
+        ListenResult::Pit(ACCDPitEvent::PitExit { car_index, stop }) => {
+            println!(
+                "Pit stop: #{} | lap: {} | pit lane: {}",
+                race_number(state, car_index),
+                stop.lap + 1,
+                stop.pit_lane_time()
+            );
+        }
+        ListenResult::Pit(ACCDPitEvent::PitLoss {
+            car_index,
+            loss,
+            track_pit_loss,
+        }) => {
+            println!(
+                "Pit loss: #{} | {} | track: {}",
+                race_number(state, car_index),
+                loss,
+                track_pit_loss
+            );
+        }
+        ListenResult::Pit(ACCDPitEvent::PitCycle(pit_cycle)) => {
+            println!(
+                "{:?}: #{} ahead of #{} | P{}",
+                pit_cycle.result,
+                race_number(state, pit_cycle.car_index),
+                race_number(state, pit_cycle.other_car_index),
+                pit_cycle.position_after
+            );
+        }
+        ListenResult::Conditions(event) => {
+            println!("Conditions: {:?}", event);
+        }
+        ListenResult::SpeedTrap(ACCDSpeedEvent::Record(record)) => {
+            let kind = match record.kind {
+                ACCDSpeedRecordKind::Trap { trap, .. } => format!("trap {}", trap + 1),
+                ACCDSpeedRecordKind::TopSpeed => "top speed".to_string(),
+            };
+            println!(
+                "Speed record: #{} | {} | {} km/h ({:+}) | {}",
+                race_number(state, record.car_index),
+                kind,
+                record.kmh,
+                record.kmh - record.previous_kmh,
+                if record.overall {
+                    "overall".to_string()
+                } else {
+                    format!("{:?}", record.car_class)
+                }
+            );
+        }
+        // Phase changes are printed with the realtime update,
+        // the protocol already prints the entry list sync events.
+        ListenResult::Session(ACCDSessionEvent::SessionPhaseChanged { .. })
+        | ListenResult::RealTimeCarUpdate(_)
+        | ListenResult::Pit(ACCDPitEvent::PitEntry { .. })
+        | ListenResult::SpeedTrap(ACCDSpeedEvent::TrapSpeed(_))
+        | ListenResult::EntryListSync(_)
+        | ListenResult::Error => {}
+    }
+}
+
+/// Race number of a car of the entry list, car index while the entry list is incomplete.
+fn race_number(state: &Arc<Mutex<CliState>>, car_index: u16) -> i32 {
+    state
+        .lock()
+        .unwrap()
+        .entry_list_cars
+        .iter()
+        .find(|car_info| car_info.car_index == car_index)
+        .map_or(car_index as i32, |car_info| car_info.race_number)
+}
+
+fn print_broadcasting_event(broadcasting_event: &ACCDBroadcastingEvent) {
+    let race_number = match &broadcasting_event.event_car_data {
+        Some(car_info) => format!("#{}", car_info.race_number),
+        None => "-".to_string(),
+    };
+    println!(
+        "[{:?}] {} | {} | time: {}",
+        broadcasting_event.event_type,
+        broadcasting_event.event_msg,
+        race_number,
+        broadcasting_event.event_time
+    );
+}
@@ -1,13 +1,16 @@
+use std::convert::TryFrom;
 use std::fmt;
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 use byteorder::{NativeEndian, ReadBytesExt};
 
 use crate::accd_car_info::ACCDCarInfo;
 use crate::accd_protocol::ACCDProtocol;
+use crate::accd_session_time::SessionTime;
 use crate::accd_utils::read_string;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BroadcastingCarEventType {
     None = 0,
     GreenFlag = 1,
@@ -37,37 +40,39 @@ impl From<u8> for BroadcastingCarEventType {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDBroadcastingEvent {
     pub event_type: BroadcastingCarEventType,
     pub event_msg: String,
-    pub event_time_ms: i32,
-    pub event_car_id: i32,
-    pub event_car_data: ACCDCarInfo,
+    /// Session time of the event.
+    pub event_time: SessionTime,
+    /// `None` for events without a car.
+    pub event_car_id: Option<u16>,
+    /// `None` for events without a car, or of a car missing from the entry list.
+    pub event_car_data: Option<ACCDCarInfo>,
 }
 
 impl ACCDBroadcastingEvent {
-    pub fn new(cur: &mut Cursor<&Vec<u8>>, accd_conn: &ACCDProtocol) -> Self {
-        let event_type = BroadcastingCarEventType::from(cur.read_u8().unwrap());
-        let event_msg = read_string(cur);
-        let event_time_ms = cur.read_i32::<NativeEndian>().unwrap();
-        let event_car_id = cur.read_i32::<NativeEndian>().unwrap();
-        let event_car_data = match accd_conn
-            .entry_list_cars
-            .iter()
-            .find(|car_info| car_info.car_index == event_car_id as u16)
-        {
-            Some(car_info) => car_info.clone(),
+    pub fn new(cur: &mut Cursor<&Vec<u8>>, accd_conn: &ACCDProtocol) -> io::Result<Self> {
+        let event_type = BroadcastingCarEventType::from(cur.read_u8()?);
+        let event_msg = read_string(cur)?;
+        let event_time = SessionTime::from_millis(cur.read_i32::<NativeEndian>()? as f64);
+        let event_car_id = u16::try_from(cur.read_i32::<NativeEndian>()?).ok();
+        let event_car_data = event_car_id.and_then(|car_index| {
+            accd_conn
+                .entry_list_cars
+                .iter()
+                .find(|car_info| car_info.car_index == car_index)
+                .cloned()
+        });
 
-            None => ACCDCarInfo::default().clone(),
-        };
-
-        ACCDBroadcastingEvent {
+        Ok(ACCDBroadcastingEvent {
             event_type,
             event_msg,
-            event_time_ms,
+            event_time,
             event_car_id,
             event_car_data,
-        }
+        })
     }
 }
 
@@ -76,9 +81,9 @@ impl Default for ACCDBroadcastingEvent {
         ACCDBroadcastingEvent {
             event_type: BroadcastingCarEventType::Error,
             event_msg: "".to_string(),
-            event_time_ms: -1,
-            event_car_id: -1,
-            event_car_data: ACCDCarInfo::default(),
+            event_time: SessionTime::ZERO,
+            event_car_id: None,
+            event_car_data: None,
         }
     }
 }
@@ -88,8 +93,10 @@ impl fmt::Display for ACCDBroadcastingEvent {
         writeln!(f, "/== Broadcasting Event ==/")?;
         writeln!(f, "{:#?}", self.event_type)?;
         writeln!(f, "{}", self.event_msg)?;
-        writeln!(f, "{}", self.event_time_ms)?;
-        writeln!(f, "\n{}", self.event_car_data)?;
+        writeln!(f, "{}", self.event_time)?;
+        if let Some(car_info) = &self.event_car_data {
+            writeln!(f, "\n{}", car_info)?;
+        }
         writeln!(f, "/------------------------/")
     }
 }
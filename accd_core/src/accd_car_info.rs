@@ -3,7 +3,102 @@ use std::fmt;
 use crate::accd_driver_info::ACCDDriverInfo;
 use crate::accd_enums::NationalityEnum;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CarClass {
+    GT3,
+    GT4,
+    GT2,
+    /// Porsche Carrera Cup.
+    Cup,
+    /// Lamborghini Super Trofeo.
+    ST,
+    /// Ferrari Challenge.
+    CHL,
+    /// BMW M2 CS Racing.
+    TCX,
+    Unknown,
+}
+
+impl From<u8> for CarClass {
+    /// Class of an ACC car model.
+    fn from(car_model_type: u8) -> Self {
+        match car_model_type {
+            9 | 28 => CarClass::Cup,
+            18 | 29 => CarClass::ST,
+            26 => CarClass::CHL,
+            27 => CarClass::TCX,
+            0..=36 => CarClass::GT3,
+            50..=61 => CarClass::GT4,
+            80..=86 => CarClass::GT2,
+            _ => CarClass::Unknown,
+        }
+    }
+}
+
+/// Name of an ACC car model.
+pub fn car_model_name(car_model_type: u8) -> &'static str {
+    match car_model_type {
+        0 => "Porsche 991 GT3 R",
+        1 => "Mercedes-AMG GT3",
+        2 => "Ferrari 488 GT3",
+        3 => "Audi R8 LMS",
+        4 => "Lamborghini Huracan GT3",
+        5 => "McLaren 650S GT3",
+        6 => "Nissan GT-R Nismo GT3 2018",
+        7 => "BMW M6 GT3",
+        8 => "Bentley Continental GT3 2018",
+        9 => "Porsche 991 II GT3 Cup",
+        10 => "Nissan GT-R Nismo GT3 2017",
+        11 => "Bentley Continental GT3 2016",
+        12 => "Aston Martin V12 Vantage GT3",
+        13 => "Lamborghini Gallardo R-EX",
+        14 => "Jaguar G3",
+        15 => "Lexus RC F GT3",
+        16 => "Lamborghini Huracan GT3 Evo",
+        17 => "Honda NSX GT3",
+        18 => "Lamborghini Huracan Super Trofeo",
+        19 => "Audi R8 LMS Evo",
+        20 => "Aston Martin V8 Vantage GT3",
+        21 => "Honda NSX GT3 Evo",
+        22 => "McLaren 720S GT3",
+        23 => "Porsche 991 II GT3 R",
+        24 => "Ferrari 488 GT3 Evo",
+        25 => "Mercedes-AMG GT3 2020",
+        26 => "Ferrari 488 Challenge Evo",
+        27 => "BMW M2 CS Racing",
+        28 => "Porsche 992 GT3 Cup",
+        29 => "Lamborghini Huracan Super Trofeo EVO2",
+        30 => "BMW M4 GT3",
+        31 => "Audi R8 LMS GT3 Evo II",
+        32 => "Ferrari 296 GT3",
+        33 => "Lamborghini Huracan GT3 Evo2",
+        34 => "Porsche 992 GT3 R",
+        35 => "McLaren 720S GT3 Evo",
+        36 => "Ford Mustang GT3",
+        50 => "Alpine A110 GT4",
+        51 => "Aston Martin V8 Vantage GT4",
+        52 => "Audi R8 LMS GT4",
+        53 => "BMW M4 GT4",
+        55 => "Chevrolet Camaro GT4",
+        56 => "Ginetta G55 GT4",
+        57 => "KTM X-Bow GT4",
+        58 => "Maserati MC GT4",
+        59 => "McLaren 570S GT4",
+        60 => "Mercedes-AMG GT4",
+        61 => "Porsche 718 Cayman GT4",
+        80 => "Audi R8 LMS GT2",
+        82 => "KTM X-Bow GT2",
+        83 => "Maserati MC20 GT2",
+        84 => "Mercedes-AMG GT2",
+        85 => "Porsche 911 GT2 RS CS Evo",
+        86 => "Porsche 935",
+        _ => "Unknown",
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDCarInfo {
     pub car_index: u16,
     pub car_model_type: u8,
@@ -22,6 +117,14 @@ impl ACCDCarInfo {
             ..ACCDCarInfo::default()
         }
     }
+
+    pub fn car_class(&self) -> CarClass {
+        CarClass::from(self.car_model_type)
+    }
+
+    pub fn car_model_name(&self) -> &'static str {
+        car_model_name(self.car_model_type)
+    }
 }
 
 impl Default for ACCDCarInfo {
@@ -0,0 +1,661 @@
+use std::collections::HashMap;
+
+use crate::accd_pace::is_pace_lap;
+use crate::accd_realtime_car_update::ACCDRealtimeCarUpdate;
+use crate::accd_realtime_update::ACCDRealtimeUpdate;
+use crate::accd_session_time::SessionTime;
+use crate::accd_session_tracker::ACCDSessionInfo;
+
+/// Laps needed for a correlation.
+const MIN_CORRELATION_LAPS: usize = 3;
+
+/// Weather and track conditions at a moment of the session.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDConditions {
+    pub session_time: SessionTime,
+    pub time_of_day: SessionTime,
+    /// Degrees Celsius.
+    pub ambient_temp: u8,
+    pub track_temp: u8,
+    /// Between 0 and 1, like the rain level and the wetness.
+    pub clouds: f32,
+    pub rain_level: f32,
+    pub wetness: f32,
+}
+
+impl ACCDConditions {
+    pub fn new(realtime_update: &ACCDRealtimeUpdate) -> Self {
+        ACCDConditions {
+            session_time: realtime_update.session_time,
+            time_of_day: realtime_update.time_of_day,
+            ambient_temp: realtime_update.ambient_temp,
+            track_temp: realtime_update.track_temp,
+            clouds: realtime_update.clouds,
+            rain_level: realtime_update.rain_level,
+            wetness: realtime_update.wetness,
+        }
+    }
+}
+
+/// Condition changes reported by `ACCDConditionTracker`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDConditionEvent {
+    RainStarted {
+        session_time: SessionTime,
+        rain_level: f32,
+    },
+    RainStopped {
+        session_time: SessionTime,
+    },
+    TrackWetter {
+        session_time: SessionTime,
+        wetness: f32,
+    },
+    /// The wetness went down, the track is dry again at 0.
+    TrackDrying {
+        session_time: SessionTime,
+        wetness: f32,
+    },
+    /// Track temperature moved by at least `temperature_swing` degrees since `since`.
+    /// Only the track temperature counts, the ambient temperatures are there to show
+    /// what the air did meanwhile.
+    TemperatureSwing {
+        session_time: SessionTime,
+        since: SessionTime,
+        previous_track_temp: u8,
+        track_temp: u8,
+        previous_ambient_temp: u8,
+        ambient_temp: u8,
+    },
+}
+
+impl ACCDConditionEvent {
+    pub fn session_time(&self) -> SessionTime {
+        match self {
+            ACCDConditionEvent::RainStarted { session_time, .. }
+            | ACCDConditionEvent::RainStopped { session_time }
+            | ACCDConditionEvent::TrackWetter { session_time, .. }
+            | ACCDConditionEvent::TrackDrying { session_time, .. }
+            | ACCDConditionEvent::TemperatureSwing { session_time, .. } => *session_time,
+        }
+    }
+}
+
+/// Compares the conditions of the realtime updates with the ones of the last change
+/// and reports rain, wetness and temperature changes.
+#[derive(Debug, Clone)]
+pub struct ACCDConditionTracker {
+    /// Degrees the track temperature has to move to be reported, the ambient
+    /// temperature alone never reports a swing.
+    pub temperature_swing: u8,
+    /// Wetness change reported, ACC sends it in steps of 0.1.
+    pub wetness_step: f32,
+    session: Option<ACCDSessionInfo>,
+    raining: bool,
+    wetness: f32,
+    /// Conditions the temperatures are compared with.
+    temperatures: Option<ACCDConditions>,
+}
+
+impl Default for ACCDConditionTracker {
+    fn default() -> Self {
+        ACCDConditionTracker {
+            temperature_swing: 3,
+            wetness_step: 0.1,
+            session: None,
+            raining: false,
+            wetness: 0.0,
+            temperatures: None,
+        }
+    }
+}
+
+impl ACCDConditionTracker {
+    pub fn new() -> Self {
+        ACCDConditionTracker::default()
+    }
+
+    /// Forgets the session, e.g. after connecting to another ACC instance.
+    pub fn reset(&mut self) {
+        *self = ACCDConditionTracker {
+            temperature_swing: self.temperature_swing,
+            wetness_step: self.wetness_step,
+            ..ACCDConditionTracker::default()
+        };
+    }
+
+    /// Events caused by the realtime update, none for the first update of a session.
+    pub fn realtime_update(
+        &mut self,
+        realtime_update: &ACCDRealtimeUpdate,
+    ) -> Vec<ACCDConditionEvent> {
+        let conditions = ACCDConditions::new(realtime_update);
+        let session_time = conditions.session_time;
+        let session = ACCDSessionInfo {
+            event_index: realtime_update.event_index(),
+            session_index: realtime_update.session_index(),
+            session_type: realtime_update.session_type.clone(),
+        };
+        if self.session.as_ref() != Some(&session) {
+            self.session = Some(session);
+            self.raining = conditions.rain_level > 0.0;
+            self.wetness = conditions.wetness;
+            self.temperatures = Some(conditions);
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+
+        let raining = conditions.rain_level > 0.0;
+        if raining != self.raining {
+            self.raining = raining;
+            events.push(if raining {
+                ACCDConditionEvent::RainStarted {
+                    session_time,
+                    rain_level: conditions.rain_level,
+                }
+            } else {
+                ACCDConditionEvent::RainStopped { session_time }
+            });
+        }
+
+        // The wetness is a tenth of a byte, a small margin keeps the steps exact.
+        let wetness_change = conditions.wetness - self.wetness;
+        if wetness_change.abs() >= self.wetness_step - 0.001 {
+            self.wetness = conditions.wetness;
+            events.push(if wetness_change > 0.0 {
+                ACCDConditionEvent::TrackWetter {
+                    session_time,
+                    wetness: conditions.wetness,
+                }
+            } else {
+                ACCDConditionEvent::TrackDrying {
+                    session_time,
+                    wetness: conditions.wetness,
+                }
+            });
+        }
+
+        if let Some(previous) = &self.temperatures {
+            let swing = (conditions.track_temp as i32 - previous.track_temp as i32).abs();
+            if swing >= self.temperature_swing as i32 {
+                events.push(ACCDConditionEvent::TemperatureSwing {
+                    session_time,
+                    since: previous.session_time,
+                    previous_track_temp: previous.track_temp,
+                    track_temp: conditions.track_temp,
+                    previous_ambient_temp: previous.ambient_temp,
+                    ambient_temp: conditions.ambient_temp,
+                });
+                self.temperatures = Some(conditions);
+            }
+        }
+
+        events
+    }
+}
+
+/// A pace lap with the conditions it was completed in.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDConditionLap {
+    pub car_index: u16,
+    pub lap_time_ms: i32,
+    pub conditions: ACCDConditions,
+}
+
+/// How much a condition goes with the lap times.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDConditionEffect {
+    /// Pearson correlation between the condition and the lap times, between -1 and 1.
+    pub correlation: f64,
+    /// Lap time gained (negative) or lost per unit of the condition:
+    /// a degree for the temperatures, the whole range from 0 to 1 for the others.
+    pub lap_time_per_unit: SessionTime,
+}
+
+/// Correlation of the conditions with the lap times of every car compared to its usual pace.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDConditionCorrelation {
+    pub laps: usize,
+    /// `None` when the condition didn't change or there are too few laps.
+    pub track_temp: Option<ACCDConditionEffect>,
+    pub ambient_temp: Option<ACCDConditionEffect>,
+    pub rain_level: Option<ACCDConditionEffect>,
+    pub wetness: Option<ACCDConditionEffect>,
+}
+
+/// Part of the session between two condition changes.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDConditionPeriod {
+    pub start: SessionTime,
+    /// `None` for the period still running.
+    pub end: Option<SessionTime>,
+    /// The change the period started with, `None` for the first one.
+    pub event: Option<ACCDConditionEvent>,
+    pub laps: usize,
+    /// Mean of the lap times of the period, every car compared to its usual pace.
+    pub pace_delta: Option<SessionTime>,
+}
+
+/// Conditions through the session: sampled at regular intervals of session time,
+/// with their changes and the pace laps completed in them.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDConditionHistory {
+    /// Session time between two samples.
+    pub sample_interval: SessionTime,
+    /// Oldest first.
+    pub samples: Vec<ACCDConditions>,
+    pub events: Vec<ACCDConditionEvent>,
+    pub laps: Vec<ACCDConditionLap>,
+    current: Option<ACCDConditions>,
+    car_laps: HashMap<u16, i32>,
+}
+
+impl Default for ACCDConditionHistory {
+    fn default() -> Self {
+        ACCDConditionHistory {
+            sample_interval: SessionTime::from_secs(30.0),
+            samples: Vec::new(),
+            events: Vec::new(),
+            laps: Vec::new(),
+            current: None,
+            car_laps: HashMap::new(),
+        }
+    }
+}
+
+impl ACCDConditionHistory {
+    pub fn new() -> Self {
+        ACCDConditionHistory::default()
+    }
+
+    /// Latest conditions, `None` before the first realtime update.
+    pub fn current(&self) -> Option<&ACCDConditions> {
+        self.current.as_ref()
+    }
+
+    pub fn realtime_update(&mut self, realtime_update: &ACCDRealtimeUpdate) {
+        let conditions = ACCDConditions::new(realtime_update);
+        let due = match self.samples.last() {
+            Some(last) => conditions.session_time - last.session_time >= self.sample_interval,
+            None => true,
+        };
+        if due {
+            self.samples.push(conditions.clone());
+        }
+        self.current = Some(conditions);
+    }
+
+    pub fn condition_event(&mut self, event: &ACCDConditionEvent) {
+        self.events.push(event.clone());
+    }
+
+    /// Keeps the pace laps with the conditions they ended in.
+    pub fn car_update(&mut self, car_update: &ACCDRealtimeCarUpdate) {
+        let car_index = car_update.car_index as u16;
+        let previous_laps = self.car_laps.insert(car_index, car_update.laps);
+        let lap_completed = previous_laps.is_some_and(|laps| car_update.laps > laps);
+        if !lap_completed || !is_pace_lap(&car_update.last_lap) {
+            return;
+        }
+        if let (Some(conditions), Some(lap_time_ms)) =
+            (&self.current, car_update.last_lap.lap_time_ms)
+        {
+            self.laps.push(ACCDConditionLap {
+                car_index,
+                lap_time_ms,
+                conditions: conditions.clone(),
+            });
+        }
+    }
+
+    /// Lap time of every lap minus the median lap time of its car, so fast and slow
+    /// cars can be put together.
+    fn lap_deltas(&self) -> Vec<(&ACCDConditionLap, f64)> {
+        let mut car_times: HashMap<u16, Vec<f64>> = HashMap::new();
+        for lap in self.laps.iter() {
+            car_times
+                .entry(lap.car_index)
+                .or_default()
+                .push(lap.lap_time_ms as f64);
+        }
+        let medians: HashMap<u16, f64> = car_times
+            .into_iter()
+            .map(|(car_index, mut times)| {
+                times.sort_by(|a, b| a.total_cmp(b));
+                (car_index, times[times.len() / 2])
+            })
+            .collect();
+
+        self.laps
+            .iter()
+            .map(|lap| (lap, lap.lap_time_ms as f64 - medians[&lap.car_index]))
+            .collect()
+    }
+
+    pub fn correlation(&self) -> ACCDConditionCorrelation {
+        let deltas = self.lap_deltas();
+        let effect_of = |condition: fn(&ACCDConditions) -> f64| {
+            let points: Vec<(f64, f64)> = deltas
+                .iter()
+                .map(|(lap, delta)| (condition(&lap.conditions), *delta))
+                .collect();
+            effect(&points)
+        };
+
+        ACCDConditionCorrelation {
+            laps: deltas.len(),
+            track_temp: effect_of(|conditions| conditions.track_temp as f64),
+            ambient_temp: effect_of(|conditions| conditions.ambient_temp as f64),
+            rain_level: effect_of(|conditions| conditions.rain_level as f64),
+            wetness: effect_of(|conditions| conditions.wetness as f64),
+        }
+    }
+
+    /// The session split at every condition change, with the pace of each part.
+    pub fn periods(&self) -> Vec<ACCDConditionPeriod> {
+        let deltas = self.lap_deltas();
+        let mut starts: Vec<(SessionTime, Option<&ACCDConditionEvent>)> =
+            vec![(SessionTime::ZERO, None)];
+        starts.extend(
+            self.events
+                .iter()
+                .map(|event| (event.session_time(), Some(event))),
+        );
+
+        starts
+            .iter()
+            .enumerate()
+            .map(|(i, (start, event))| {
+                let end = starts.get(i + 1).map(|(end, _)| *end);
+                let period_deltas: Vec<f64> = deltas
+                    .iter()
+                    .filter(|(lap, _)| lap.conditions.session_time >= *start)
+                    .filter(|(lap, _)| end.is_none_or(|end| lap.conditions.session_time < end))
+                    .map(|(_, delta)| *delta)
+                    .collect();
+                let pace_delta = match period_deltas.len() {
+                    0 => None,
+                    len => Some(SessionTime::from_millis(
+                        period_deltas.iter().sum::<f64>() / len as f64,
+                    )),
+                };
+                ACCDConditionPeriod {
+                    start: *start,
+                    end,
+                    event: event.cloned(),
+                    laps: period_deltas.len(),
+                    pace_delta,
+                }
+            })
+            .collect()
+    }
+
+    /// Forgets everything but `sample_interval`, e.g. when a new session starts.
+    pub fn reset(&mut self) {
+        *self = ACCDConditionHistory {
+            sample_interval: self.sample_interval,
+            ..ACCDConditionHistory::default()
+        };
+    }
+}
+
+/// Correlation and least squares slope of lap time deltas against a condition.
+fn effect(points: &[(f64, f64)]) -> Option<ACCDConditionEffect> {
+    if points.len() < MIN_CORRELATION_LAPS {
+        return None;
+    }
+    let len = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / len;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / len;
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return None;
+    }
+
+    Some(ACCDConditionEffect {
+        correlation: covariance / (variance_x * variance_y).sqrt(),
+        lap_time_per_unit: SessionTime::from_millis(covariance / variance_x),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn realtime_update(
+        session_secs: f64,
+        rain_level: f32,
+        wetness: f32,
+        track_temp: u8,
+        ambient_temp: u8,
+    ) -> ACCDRealtimeUpdate {
+        ACCDRealtimeUpdate {
+            session_time: SessionTime::from_secs(session_secs),
+            rain_level,
+            wetness,
+            track_temp,
+            ambient_temp,
+            ..ACCDRealtimeUpdate::default()
+        }
+    }
+
+    fn conditions(session_secs: f64) -> ACCDConditions {
+        ACCDConditions::new(&realtime_update(session_secs, 0.0, 0.0, 25, 20))
+    }
+
+    fn lap(session_secs: f64, lap_time_ms: i32) -> ACCDConditionLap {
+        ACCDConditionLap {
+            car_index: 0,
+            lap_time_ms,
+            conditions: conditions(session_secs),
+        }
+    }
+
+    #[test]
+    fn no_events_on_the_first_update_of_a_session() {
+        let mut tracker = ACCDConditionTracker::new();
+        assert!(tracker
+            .realtime_update(&realtime_update(0.0, 0.5, 0.5, 40, 30))
+            .is_empty());
+
+        let mut next_session = realtime_update(10.0, 0.0, 0.0, 20, 15);
+        next_session.session_index = 1;
+        assert!(tracker.realtime_update(&next_session).is_empty());
+
+        // The first update of the new session is what the next ones are compared with.
+        let mut rain = realtime_update(20.0, 0.2, 0.0, 20, 15);
+        rain.session_index = 1;
+        assert_eq!(
+            tracker.realtime_update(&rain),
+            vec![ACCDConditionEvent::RainStarted {
+                session_time: SessionTime::from_secs(20.0),
+                rain_level: 0.2,
+            }]
+        );
+    }
+
+    #[test]
+    fn rain_starts_and_stops() {
+        let mut tracker = ACCDConditionTracker::new();
+        tracker.realtime_update(&realtime_update(0.0, 0.0, 0.0, 25, 20));
+        assert_eq!(
+            tracker.realtime_update(&realtime_update(10.0, 0.1, 0.0, 25, 20)),
+            vec![ACCDConditionEvent::RainStarted {
+                session_time: SessionTime::from_secs(10.0),
+                rain_level: 0.1,
+            }]
+        );
+        // More rain is no new event.
+        assert!(tracker
+            .realtime_update(&realtime_update(20.0, 0.6, 0.0, 25, 20))
+            .is_empty());
+        assert_eq!(
+            tracker.realtime_update(&realtime_update(30.0, 0.0, 0.0, 25, 20)),
+            vec![ACCDConditionEvent::RainStopped {
+                session_time: SessionTime::from_secs(30.0),
+            }]
+        );
+        assert!(tracker
+            .realtime_update(&realtime_update(40.0, 0.0, 0.0, 25, 20))
+            .is_empty());
+    }
+
+    #[test]
+    fn wetness_is_reported_in_steps() {
+        let mut tracker = ACCDConditionTracker::new();
+        tracker.realtime_update(&realtime_update(0.0, 0.0, 0.0, 25, 20));
+        assert!(tracker
+            .realtime_update(&realtime_update(10.0, 0.0, 0.05, 25, 20))
+            .is_empty());
+        // Within the 0.001 margin of a step.
+        assert_eq!(
+            tracker.realtime_update(&realtime_update(20.0, 0.0, 0.0995, 25, 20)),
+            vec![ACCDConditionEvent::TrackWetter {
+                session_time: SessionTime::from_secs(20.0),
+                wetness: 0.0995,
+            }]
+        );
+        // Just outside of it, compared with the wetness of the last event.
+        assert!(tracker
+            .realtime_update(&realtime_update(30.0, 0.0, 0.1975, 25, 20))
+            .is_empty());
+        assert_eq!(
+            tracker.realtime_update(&realtime_update(40.0, 0.0, 0.2, 25, 20)),
+            vec![ACCDConditionEvent::TrackWetter {
+                session_time: SessionTime::from_secs(40.0),
+                wetness: 0.2,
+            }]
+        );
+        assert_eq!(
+            tracker.realtime_update(&realtime_update(50.0, 0.0, 0.0, 25, 20)),
+            vec![ACCDConditionEvent::TrackDrying {
+                session_time: SessionTime::from_secs(50.0),
+                wetness: 0.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn temperature_swings_of_the_track() {
+        let mut tracker = ACCDConditionTracker::new();
+        tracker.realtime_update(&realtime_update(0.0, 0.0, 0.0, 25, 20));
+        assert!(tracker
+            .realtime_update(&realtime_update(10.0, 0.0, 0.0, 27, 21))
+            .is_empty());
+        // Compared with the first update, not with the last one.
+        assert_eq!(
+            tracker.realtime_update(&realtime_update(20.0, 0.0, 0.0, 28, 22)),
+            vec![ACCDConditionEvent::TemperatureSwing {
+                session_time: SessionTime::from_secs(20.0),
+                since: SessionTime::ZERO,
+                previous_track_temp: 25,
+                track_temp: 28,
+                previous_ambient_temp: 20,
+                ambient_temp: 22,
+            }]
+        );
+        assert!(tracker
+            .realtime_update(&realtime_update(30.0, 0.0, 0.0, 26, 22))
+            .is_empty());
+        // The ambient temperature alone is no swing.
+        assert!(tracker
+            .realtime_update(&realtime_update(40.0, 0.0, 0.0, 26, 12))
+            .is_empty());
+        assert_eq!(
+            tracker.realtime_update(&realtime_update(50.0, 0.0, 0.0, 25, 12)),
+            vec![ACCDConditionEvent::TemperatureSwing {
+                session_time: SessionTime::from_secs(50.0),
+                since: SessionTime::from_secs(20.0),
+                previous_track_temp: 28,
+                track_temp: 25,
+                previous_ambient_temp: 22,
+                ambient_temp: 12,
+            }]
+        );
+    }
+
+    #[test]
+    fn periods_split_at_the_events() {
+        let mut history = ACCDConditionHistory::new();
+        let rain = ACCDConditionEvent::RainStarted {
+            session_time: SessionTime::from_secs(60.0),
+            rain_level: 0.3,
+        };
+        let wetter = ACCDConditionEvent::TrackWetter {
+            session_time: SessionTime::from_secs(120.0),
+            wetness: 0.1,
+        };
+        let dry = ACCDConditionEvent::RainStopped {
+            session_time: SessionTime::from_secs(200.0),
+        };
+        history.condition_event(&rain);
+        history.condition_event(&wetter);
+        history.condition_event(&dry);
+        // The median lap is 101 s.
+        history.laps = vec![
+            lap(59.999, 100_000),
+            lap(60.0, 101_000),
+            lap(119.0, 101_000),
+            lap(120.0, 102_000),
+        ];
+
+        let periods = history.periods();
+        assert_eq!(periods.len(), 4);
+        assert_eq!(periods[0].start, SessionTime::ZERO);
+        assert_eq!(periods[0].end, Some(SessionTime::from_secs(60.0)));
+        assert_eq!(periods[0].event, None);
+        assert_eq!(periods[0].laps, 1);
+        assert_eq!(
+            periods[0].pace_delta,
+            Some(SessionTime::from_millis(-1000.0))
+        );
+
+        // A lap ending at the start of a period belongs to it.
+        assert_eq!(periods[1].event, Some(rain));
+        assert_eq!(periods[1].laps, 2);
+        assert_eq!(periods[1].pace_delta, Some(SessionTime::ZERO));
+
+        assert_eq!(periods[2].start, SessionTime::from_secs(120.0));
+        assert_eq!(periods[2].end, Some(SessionTime::from_secs(200.0)));
+        assert_eq!(periods[2].laps, 1);
+        assert_eq!(
+            periods[2].pace_delta,
+            Some(SessionTime::from_millis(1000.0))
+        );
+
+        assert_eq!(periods[3].event, Some(dry));
+        assert_eq!(periods[3].end, None);
+        assert_eq!(periods[3].laps, 0);
+        assert_eq!(periods[3].pace_delta, None);
+    }
+
+    #[test]
+    fn effect_on_linear_data() {
+        let rising: Vec<(f64, f64)> = (0..5).map(|x| (x as f64, 2.0 * x as f64 + 5.0)).collect();
+        let rising = effect(&rising).unwrap();
+        assert!((rising.correlation - 1.0).abs() < 1e-9);
+        assert!((rising.lap_time_per_unit.as_millis() - 2.0).abs() < 1e-9);
+
+        let falling: Vec<(f64, f64)> = (0..5).map(|x| (x as f64, -300.0 * x as f64)).collect();
+        let falling = effect(&falling).unwrap();
+        assert!((falling.correlation + 1.0).abs() < 1e-9);
+        assert!((falling.lap_time_per_unit.as_millis() + 300.0).abs() < 1e-9);
+
+        assert_eq!(effect(&[(0.0, 0.0), (1.0, 1.0)]), None);
+        assert_eq!(effect(&[(1.0, 0.0), (1.0, 1.0), (1.0, 2.0)]), None);
+        assert_eq!(effect(&[(0.0, 1.0), (1.0, 1.0), (2.0, 1.0)]), None);
+    }
+}
@@ -1,9 +1,13 @@
+use std::collections::BTreeMap;
+use std::error::Error;
 use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
 use std::net::SocketAddr;
 
 #[derive(Debug, Clone)]
 pub struct ACCDConfig {
-    pub debug_sleep_time: u64, //this need to go
     pub protocol_version: u8,
     pub display_name: String,
     pub connection_psw: String,
@@ -11,12 +15,65 @@ pub struct ACCDConfig {
     pub command_psw: String,
     pub bind_addr: SocketAddr,
     pub destination_addr: SocketAddr,
+    /// Length of lap-limited races, used for the laps remaining.
+    pub race_laps: Option<i32>,
+    /// Spline positions of the speed traps by track name, as sent in the track data.
+    pub speed_traps: BTreeMap<String, Vec<f32>>,
+    /// Named connection profiles, each one already merged with the top level keys.
+    pub profiles: BTreeMap<String, ACCDConfig>,
+    /// Profile activated at startup when none is given on the command line.
+    pub default_profile: Option<String>,
+    pub active_profile: Option<String>,
 }
 
+#[derive(Debug)]
+pub enum ACCDConfigError {
+    Io(String, io::Error),
+    Syntax(Option<usize>, String),
+    UnknownKey(String, Option<usize>),
+    InvalidValue(String, Option<usize>, String),
+    /// An error of the given config file.
+    File(String, Box<ACCDConfigError>),
+    /// A malformed command line.
+    Argument(String),
+    UnknownProfile(String),
+    /// No `--config` and none of these default paths exists.
+    NoConfigFile(Vec<String>),
+}
+
+impl fmt::Display for ACCDConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ACCDConfigError::Io(path, e) => write!(f, "cannot read {}: {}", path, e),
+            ACCDConfigError::File(path, e) => write!(f, "{}: {}", path, e),
+            ACCDConfigError::Argument(msg) => write!(f, "{}", msg),
+            ACCDConfigError::UnknownProfile(name) => write!(f, "unknown profile \"{}\"", name),
+            ACCDConfigError::NoConfigFile(paths) => write!(
+                f,
+                "no config file, give one with --config <path> or create one of: {}",
+                paths.join(", ")
+            ),
+            // toml already reports the line and column in its messages.
+            ACCDConfigError::Syntax(_, msg) => write!(f, "{}", msg),
+            ACCDConfigError::UnknownKey(key, Some(line)) => {
+                write!(f, "line {}: unknown key \"{}\"", line, key)
+            }
+            ACCDConfigError::UnknownKey(key, None) => write!(f, "unknown key \"{}\"", key),
+            ACCDConfigError::InvalidValue(key, Some(line), msg) => {
+                write!(f, "line {}: invalid value for \"{}\": {}", line, key, msg)
+            }
+            ACCDConfigError::InvalidValue(key, None, msg) => {
+                write!(f, "invalid value for \"{}\": {}", key, msg)
+            }
+        }
+    }
+}
+
+impl Error for ACCDConfigError {}
+
 impl Default for ACCDConfig {
     fn default() -> Self {
         ACCDConfig {
-            debug_sleep_time: 300,
             protocol_version: 4,
             display_name: String::from("Your name"),
             connection_psw: String::from("asd"),
@@ -24,6 +81,11 @@ impl Default for ACCDConfig {
             command_psw: String::from(""),
             bind_addr: "0.0.0.0:3400".parse::<SocketAddr>().unwrap(),
             destination_addr: "127.0.0.1:9000".parse::<SocketAddr>().unwrap(),
+            race_laps: None,
+            speed_traps: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            default_profile: None,
+            active_profile: None,
         }
     }
 }
@@ -48,7 +110,6 @@ impl ACCDConfig {
         destination_addr: SocketAddr,
     ) -> ACCDConfig {
         ACCDConfig {
-            debug_sleep_time: 300,
             protocol_version, //: 4,
             display_name,     //: String::from("ZioYuri78"),
             connection_psw,   //: String::from("asd"),
@@ -56,6 +117,610 @@ impl ACCDConfig {
             command_psw,      //: String::from(""),
             bind_addr,
             destination_addr, //: "127.0.0.1:9000".parse::<SocketAddr>().unwrap(),
+            ..ACCDConfig::default()
+        }
+    }
+
+    /// Reads a TOML config file, keys missing from the file keep their default value.
+    pub fn from_file(file_path: &str) -> Result<ACCDConfig, ACCDConfigError> {
+        let source = fs::read_to_string(file_path)
+            .map_err(|e| ACCDConfigError::Io(file_path.to_string(), e))?;
+
+        ACCDConfig::from_toml(&source)
+    }
+
+    pub fn from_toml(source: &str) -> Result<ACCDConfig, ACCDConfigError> {
+        let mut config = ACCDConfig::default();
+        config.apply_toml(source)?;
+        Ok(config)
+    }
+
+    /// Reads the `broadcasting.json` written by ACC in
+    /// `Documents/Assetto Corsa Competizione/Config`, keys it doesn't set keep their default value.
+    pub fn from_broadcasting_json(file_path: &str) -> Result<ACCDConfig, ACCDConfigError> {
+        let mut config = ACCDConfig::default();
+        config.apply_broadcasting_json_file(file_path)?;
+        Ok(config)
+    }
+
+    pub fn apply_broadcasting_json_file(&mut self, file_path: &str) -> Result<(), ACCDConfigError> {
+        let data =
+            fs::read(file_path).map_err(|e| ACCDConfigError::Io(file_path.to_string(), e))?;
+
+        self.apply_broadcasting_json(&data)
+    }
+
+    /// Takes the destination port and the passwords from a broadcasting.json,
+    /// the destination ip is left untouched.
+    pub fn apply_broadcasting_json(&mut self, data: &[u8]) -> Result<(), ACCDConfigError> {
+        let source = decode_text(data)?;
+        let json = match serde_json::from_str::<serde_json::Value>(&source) {
+            Ok(serde_json::Value::Object(json)) => json,
+            Ok(_) => {
+                return Err(ACCDConfigError::Syntax(
+                    None,
+                    "expected a JSON object".to_string(),
+                ))
+            }
+            Err(e) => return Err(ACCDConfigError::Syntax(Some(e.line()), e.to_string())),
+        };
+
+        let invalid = |key: &str, msg: &str| {
+            ACCDConfigError::InvalidValue(key.to_string(), None, msg.to_string())
+        };
+
+        // ACC disables the broadcasting interface when the port is 0.
+        match json.get("updListenerPort").map(|port| port.as_u64()) {
+            Some(Some(port)) if port > 0 && port <= u16::MAX as u64 => {
+                self.destination_addr.set_port(port as u16);
+            }
+            Some(Some(0)) => {
+                return Err(invalid(
+                    "updListenerPort",
+                    "broadcasting is disabled in ACC, set a port different from 0",
+                ))
+            }
+            Some(_) => return Err(invalid("updListenerPort", "expected a port number")),
+            None => return Err(invalid("updListenerPort", "missing")),
+        }
+
+        if let Some(connection_psw) = json.get("connectionPassword") {
+            self.connection_psw = connection_psw
+                .as_str()
+                .ok_or_else(|| invalid("connectionPassword", "expected a string"))?
+                .to_string();
+        }
+
+        if let Some(command_psw) = json.get("commandPassword") {
+            self.command_psw = command_psw
+                .as_str()
+                .ok_or_else(|| invalid("commandPassword", "expected a string"))?
+                .to_string();
+        }
+
+        Ok(())
+    }
+
+    /// Overrides only the keys present in `source`.
+    pub fn apply_toml(&mut self, source: &str) -> Result<(), ACCDConfigError> {
+        self.apply_toml_with_layer(source, |_| Ok(()))
+    }
+
+    /// Like `apply_toml`, `layer` changes the top level values before the profiles are
+    /// built on them, so the keys set by a profile win over the ones set by `layer`.
+    pub fn apply_toml_with_layer<F>(
+        &mut self,
+        source: &str,
+        layer: F,
+    ) -> Result<(), ACCDConfigError>
+    where
+        F: FnOnce(&mut ACCDConfig) -> Result<(), ACCDConfigError>,
+    {
+        let table = match source.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => {
+                return Err(ACCDConfigError::Syntax(
+                    None,
+                    "expected a table".to_string(),
+                ))
+            }
+            Err(e) => {
+                let line = e.line_col().map(|(line, _)| line + 1);
+                return Err(ACCDConfigError::Syntax(line, e.to_string()));
+            }
+        };
+
+        let mut profiles = None;
+        for (key, value) in table.iter() {
+            match key.as_str() {
+                // Profiles are resolved once all the top level keys are known.
+                "profiles" => profiles = Some(value),
+                "default_profile" => {
+                    self.default_profile = match value.as_str() {
+                        Some(name) => Some(name.to_string()),
+                        None => {
+                            return Err(ACCDConfigError::InvalidValue(
+                                key.clone(),
+                                key_line(source, None, key),
+                                "expected a profile name".to_string(),
+                            ))
+                        }
+                    };
+                }
+                _ => self
+                    .set_value(key, value)
+                    .map_err(|msg| toml_error(key, key_line(source, None, key), msg))?,
+            }
+        }
+
+        layer(self)?;
+
+        if let Some(profiles) = profiles {
+            let profiles = profiles.as_table().ok_or_else(|| {
+                ACCDConfigError::InvalidValue(
+                    "profiles".to_string(),
+                    key_line(source, None, "profiles"),
+                    "expected a table of profiles".to_string(),
+                )
+            })?;
+
+            for (name, profile_table) in profiles.iter() {
+                let section = format!("profiles.{}", name);
+                let profile_table = profile_table.as_table().ok_or_else(|| {
+                    ACCDConfigError::InvalidValue(
+                        section.clone(),
+                        None,
+                        "expected a table".to_string(),
+                    )
+                })?;
+
+                let mut profile = self.clone();
+                profile.profiles.clear();
+                profile.default_profile = None;
+                for (key, value) in profile_table.iter() {
+                    profile.set_value(key, value).map_err(|msg| {
+                        toml_error(
+                            &format!("{}.{}", section, key),
+                            key_line(source, Some(&section), key),
+                            msg,
+                        )
+                    })?;
+                }
+
+                self.profiles.insert(name.clone(), profile);
+            }
+        }
+
+        if let Some(name) = &self.default_profile {
+            if !self.profiles.contains_key(name) {
+                return Err(ACCDConfigError::UnknownProfile(name.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Names of the profiles, sorted.
+    pub fn profile_names(&self) -> Vec<&str> {
+        self.profiles.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Replaces the connection settings with the ones of the named profile,
+    /// the list of profiles is kept so that another one can be activated later.
+    pub fn activate_profile(&mut self, name: &str) -> Result<(), ACCDConfigError> {
+        let mut profile = self
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ACCDConfigError::UnknownProfile(name.to_string()))?;
+
+        profile.profiles = mem::take(&mut self.profiles);
+        profile.default_profile = self.default_profile.take();
+        profile.active_profile = Some(name.to_string());
+        *self = profile;
+
+        Ok(())
+    }
+
+    /// Sets a single key from its textual value, as given by an environment variable or a flag.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = match key {
+            "protocol_version" | "update_interval" | "race_laps" => value
+                .trim()
+                .parse::<i64>()
+                .map(toml::Value::Integer)
+                .unwrap_or_else(|_| toml::Value::String(value.to_string())),
+            // An inline table, e.g. `{ monza = [0.08, 0.61] }`.
+            "speed_traps" => format!("value = {}", value)
+                .parse::<toml::Value>()
+                .ok()
+                .and_then(|table| table.get("value").cloned())
+                .unwrap_or_else(|| toml::Value::String(value.to_string())),
+            _ => toml::Value::String(value.to_string()),
+        };
+
+        self.set_value(key, &value)
+            .map_err(|msg| msg.unwrap_or_else(|| format!("unknown key \"{}\"", key)))
+    }
+
+    /// Validates and sets a key, the error is `None` when the key is unknown.
+    fn set_value(&mut self, key: &str, value: &toml::Value) -> Result<(), Option<String>> {
+        let invalid = |msg: &str| Some(msg.to_string());
+
+        match key {
+            "protocol_version" => {
+                self.protocol_version = match value.as_integer() {
+                    Some(version) if version > 0 && version <= 255 => version as u8,
+                    _ => return Err(invalid("expected an integer between 1 and 255")),
+                };
+            }
+            "display_name" => {
+                self.display_name = match value.as_str() {
+                    Some(name) if !name.trim().is_empty() => name.to_string(),
+                    _ => return Err(invalid("expected a non empty string")),
+                };
+            }
+            "connection_password" => {
+                self.connection_psw = value
+                    .as_str()
+                    .ok_or_else(|| invalid("expected a string"))?
+                    .to_string();
+            }
+            "update_interval" => {
+                self.update_interval = match value.as_integer() {
+                    Some(interval) if interval > 0 && interval <= i32::MAX as i64 => {
+                        interval as i32
+                    }
+                    _ => return Err(invalid("expected a positive number of milliseconds")),
+                };
+            }
+            "command_password" => {
+                self.command_psw = value
+                    .as_str()
+                    .ok_or_else(|| invalid("expected a string"))?
+                    .to_string();
+            }
+            "bind_address" => {
+                self.bind_addr = parse_addr(value).map_err(Some)?;
+            }
+            "destination_address" => {
+                self.destination_addr = parse_addr(value).map_err(Some)?;
+            }
+            "race_laps" => {
+                self.race_laps = match value.as_integer() {
+                    Some(0) => None,
+                    Some(laps) if laps > 0 && laps <= i32::MAX as i64 => Some(laps as i32),
+                    _ => return Err(invalid("expected a number of laps, 0 for timed races")),
+                };
+            }
+            "speed_traps" => {
+                let tracks = value
+                    .as_table()
+                    .ok_or_else(|| invalid("expected a table of tracks"))?;
+                let mut speed_traps = BTreeMap::new();
+                for (track_name, traps) in tracks.iter() {
+                    let traps = traps
+                        .as_array()
+                        .and_then(|traps| {
+                            traps
+                                .iter()
+                                .map(|trap| {
+                                    let trap = match trap {
+                                        toml::Value::Float(trap) => *trap,
+                                        toml::Value::Integer(trap) => *trap as f64,
+                                        _ => return None,
+                                    };
+                                    Some(trap as f32).filter(|_| (0.0..1.0).contains(&trap))
+                                })
+                                .collect::<Option<Vec<f32>>>()
+                        })
+                        .ok_or_else(|| {
+                            Some(format!(
+                                "expected a list of spline positions from 0 to 1 for \"{}\"",
+                                track_name
+                            ))
+                        })?;
+                    speed_traps.insert(track_name.clone(), traps);
+                }
+                self.speed_traps = speed_traps;
+            }
+            _ => return Err(None),
+        }
+
+        Ok(())
+    }
+
+    /// The config in the same TOML format of the config file, profiles included,
+    /// set passwords are replaced by `<redacted>` when `redact_passwords` is true.
+    pub fn to_toml(&self, redact_passwords: bool) -> String {
+        let mut result = String::new();
+        if let Some(name) = self
+            .active_profile
+            .as_ref()
+            .or(self.default_profile.as_ref())
+        {
+            result += &format!("default_profile = {}\n", toml::Value::String(name.clone()));
+        }
+        result += &self.settings_toml(redact_passwords);
+
+        for (name, profile) in self.profiles.iter() {
+            // Names that aren't bare TOML keys need quotes.
+            let bare = name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            let name = if bare && !name.is_empty() {
+                name.clone()
+            } else {
+                toml::Value::String(name.clone()).to_string()
+            };
+            result += &format!("\n[profiles.{}]\n", name);
+            result += &profile.settings_toml(redact_passwords);
+        }
+
+        result
+    }
+
+    fn settings_toml(&self, redact_passwords: bool) -> String {
+        let password = |psw: &str| {
+            let psw = if redact_passwords && !psw.is_empty() {
+                "<redacted>"
+            } else {
+                psw
+            };
+            toml::Value::String(psw.to_string())
+        };
+
+        let mut result = format!(
+            "protocol_version = {}\n\
+             display_name = {}\n\
+             connection_password = {}\n\
+             update_interval = {}\n\
+             command_password = {}\n\
+             bind_address = \"{}\"\n\
+             destination_address = \"{}\"\n",
+            self.protocol_version,
+            toml::Value::String(self.display_name.clone()),
+            password(&self.connection_psw),
+            self.update_interval,
+            password(&self.command_psw),
+            self.bind_addr,
+            self.destination_addr,
+        );
+        if let Some(race_laps) = self.race_laps {
+            result += &format!("race_laps = {}\n", race_laps);
+        }
+        if !self.speed_traps.is_empty() {
+            let tracks: Vec<String> = self
+                .speed_traps
+                .iter()
+                .map(|(track_name, traps)| {
+                    let traps: Vec<String> =
+                        traps.iter().map(|trap| format!("{:?}", trap)).collect();
+                    format!(
+                        "{} = [{}]",
+                        toml::Value::String(track_name.clone()),
+                        traps.join(", ")
+                    )
+                })
+                .collect();
+            result += &format!("speed_traps = {{ {} }}\n", tracks.join(", "));
+        }
+
+        result
+    }
+}
+
+fn toml_error(key: &str, line: Option<usize>, msg: Option<String>) -> ACCDConfigError {
+    match msg {
+        Some(msg) => ACCDConfigError::InvalidValue(key.to_string(), line, msg),
+        None => ACCDConfigError::UnknownKey(key.to_string(), line),
+    }
+}
+
+/// ACC writes its json files as UTF-16 LE with a BOM, hand edited ones are usually UTF-8.
+fn decode_text(data: &[u8]) -> Result<String, ACCDConfigError> {
+    let utf16 = if data.starts_with(&[0xFF, 0xFE]) {
+        Some(&data[2..])
+    } else if data.len() >= 2 && data[0] != 0 && data[1] == 0 {
+        // UTF-16 LE without BOM, the first character is ASCII.
+        Some(data)
+    } else {
+        None
+    };
+
+    match utf16 {
+        Some(data) => {
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .collect();
+            String::from_utf16(&units)
+                .map_err(|_| ACCDConfigError::Syntax(None, "invalid UTF-16 text".to_string()))
         }
+        None => {
+            let data = data.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(data);
+            String::from_utf8(data.to_vec())
+                .map_err(|_| ACCDConfigError::Syntax(None, "invalid UTF-8 text".to_string()))
+        }
+    }
+}
+
+fn parse_addr(value: &toml::Value) -> Result<SocketAddr, String> {
+    match value.as_str() {
+        Some(addr) => addr
+            .parse::<SocketAddr>()
+            .map_err(|e| format!("\"{}\" is not an ip:port address ({})", addr, e)),
+        None => Err("expected an \"ip:port\" string".to_string()),
+    }
+}
+
+/// Line number (1 based) where `key` is assigned in `source`,
+/// only the lines of the `[section]` are searched when a section is given,
+/// the lines before the first section otherwise.
+fn key_line(source: &str, section: Option<&str>, key: &str) -> Option<usize> {
+    let mut lines = source.lines().enumerate();
+    if let Some(section) = section {
+        lines.find(|(_, line)| section_name(line).as_deref() == Some(section))?;
+    }
+
+    lines
+        .take_while(|(_, line)| section_name(line).is_none())
+        .find(|(_, line)| {
+            let line = line.trim_start();
+            let name = line.split('=').next().unwrap_or("").trim();
+            line.contains('=') && name.trim_matches('"') == key
+        })
+        .map(|(line, _)| line + 1)
+}
+
+/// Name of the section a `[section]` header line opens, with the quotes of its parts removed:
+/// `[profiles."race night"]` is `profiles.race night`.
+fn section_name(line: &str) -> Option<String> {
+    let name = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let parts: Vec<&str> = name
+        .split('.')
+        .map(|part| part.trim().trim_matches('"'))
+        .collect();
+    Some(parts.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_file_is_valid() {
+        let config = ACCDConfig::from_toml(include_str!("../config/default.toml")).unwrap();
+        assert_eq!(config.to_toml(false), ACCDConfig::default().to_toml(false));
+    }
+
+    #[test]
+    fn missing_keys_keep_their_default() {
+        let config =
+            ACCDConfig::from_toml("display_name = \"Director\"\nrace_laps = 20\n").unwrap();
+        assert_eq!(config.display_name, "Director");
+        assert_eq!(config.race_laps, Some(20));
+        assert_eq!(
+            config.update_interval,
+            ACCDConfig::default().update_interval
+        );
+    }
+
+    #[test]
+    fn errors_name_the_key_and_the_line() {
+        let source = "display_name = \"Director\"\nupdate_interval = -5\n";
+        match ACCDConfig::from_toml(source) {
+            Err(ACCDConfigError::InvalidValue(key, line, _)) => {
+                assert_eq!(key, "update_interval");
+                assert_eq!(line, Some(2));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        match ACCDConfig::from_toml("\n\nunknown = 1\n") {
+            Err(ACCDConfigError::UnknownKey(key, line)) => {
+                assert_eq!(key, "unknown");
+                assert_eq!(line, Some(3));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn quoted_profile_errors_have_a_line() {
+        let source = "race_laps = 10\n\n[profiles.\"race night\"]\nrace_laps = -1\n";
+        match ACCDConfig::from_toml(source) {
+            Err(ACCDConfigError::InvalidValue(key, line, _)) => {
+                assert_eq!(key, "profiles.race night.race_laps");
+                assert_eq!(line, Some(4));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn profiles_are_merged_with_the_top_level_keys() {
+        let source = "display_name = \"Director\"\ndefault_profile = \"race\"\n\
+                      [profiles.race]\ndestination_address = \"192.168.1.11:9000\"\n";
+        let mut config = ACCDConfig::from_toml(source).unwrap();
+        assert_eq!(config.profile_names(), vec!["race"]);
+
+        config.activate_profile("race").unwrap();
+        assert_eq!(config.display_name, "Director");
+        assert_eq!(config.destination_addr.to_string(), "192.168.1.11:9000");
+        assert_eq!(config.active_profile.as_deref(), Some("race"));
+        assert!(config.activate_profile("practice").is_err());
+    }
+
+    #[test]
+    fn unknown_default_profile_is_an_error() {
+        let result = ACCDConfig::from_toml("default_profile = \"race\"\n");
+        assert!(matches!(result, Err(ACCDConfigError::UnknownProfile(_))));
+    }
+
+    #[test]
+    fn speed_traps_are_validated() {
+        let config = ACCDConfig::from_toml("speed_traps = { monza = [0.08, 0.61] }\n").unwrap();
+        assert_eq!(config.speed_traps["monza"], vec![0.08, 0.61]);
+
+        for traps in ["{ monza = [1.5] }", "{ monza = [\"a\"] }", "[0.1]"] {
+            let source = format!("speed_traps = {}\n", traps);
+            assert!(ACCDConfig::from_toml(&source).is_err(), "{}", traps);
+        }
+    }
+
+    #[test]
+    fn set_parses_the_textual_values() {
+        let mut config = ACCDConfig::default();
+        config.set("race_laps", "12").unwrap();
+        config.set("speed_traps", "{ spa = [0.27, 0.73] }").unwrap();
+        config.set("bind_address", "127.0.0.1:3401").unwrap();
+        assert_eq!(config.race_laps, Some(12));
+        assert_eq!(config.speed_traps["spa"], vec![0.27, 0.73]);
+        assert_eq!(config.bind_addr.to_string(), "127.0.0.1:3401");
+
+        assert!(config.set("race_laps", "many").is_err());
+        assert!(config.set("speed_traps", "spa").is_err());
+        assert!(config.set("unknown", "1").is_err());
+    }
+
+    #[test]
+    fn to_toml_reads_back_the_same_config() {
+        let source = "display_name = \"Director\"\nrace_laps = 20\n\
+                      speed_traps = { monza = [0.08, 0.61] }\n\
+                      [profiles.\"race night\"]\nconnection_password = \"night\"\n";
+        let config = ACCDConfig::from_toml(source).unwrap();
+        let again = ACCDConfig::from_toml(&config.to_toml(false)).unwrap();
+        assert_eq!(again.to_toml(false), config.to_toml(false));
+        assert_eq!(again.profiles["race night"].connection_psw, "night");
+
+        assert!(config.to_toml(true).contains("<redacted>"));
+        assert!(!config.to_toml(true).contains("\"night\""));
+    }
+
+    #[test]
+    fn broadcasting_json_sets_port_and_passwords() {
+        let json = "{\"updListenerPort\": 9100, \"connectionPassword\": \"c\", \"commandPassword\": \"x\"}";
+        let mut config = ACCDConfig::default();
+        config.apply_broadcasting_json(json.as_bytes()).unwrap();
+        assert_eq!(config.destination_addr.to_string(), "127.0.0.1:9100");
+        assert_eq!(config.connection_psw, "c");
+        assert_eq!(config.command_psw, "x");
+
+        // ACC writes it as UTF-16 LE with a BOM.
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend(json.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        let mut config = ACCDConfig::default();
+        config.apply_broadcasting_json(&utf16).unwrap();
+        assert_eq!(config.destination_addr.port(), 9100);
+
+        let disabled = "{\"updListenerPort\": 0}";
+        assert!(config.apply_broadcasting_json(disabled.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_errors_name_the_file() {
+        let e = ACCDConfig::from_file("/nonexistent/accd.toml").unwrap_err();
+        assert!(e.to_string().contains("/nonexistent/accd.toml"), "{}", e);
     }
 }
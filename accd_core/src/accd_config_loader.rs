@@ -0,0 +1,463 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::accd_config::{ACCDConfig, ACCDConfigError};
+
+/// Config file looked for next to the executable and in the user config directory
+/// when no other path is given.
+pub const DEFAULT_CONFIG_FILE: &str = "accd.toml";
+/// Config file of a repository checkout, found from the executables built in it.
+const CHECKOUT_CONFIG_PATH: &str = "accd_core/config/default.toml";
+
+/// Keys that can be overridden by an `ACCD_<KEY>` environment variable or a `--<key>` flag.
+const OVERRIDABLE_KEYS: [&str; 8] = [
+    "display_name",
+    "connection_password",
+    "command_password",
+    "update_interval",
+    "bind_address",
+    "destination_address",
+    "race_laps",
+    "speed_traps",
+];
+
+pub const USAGE: &str = "\
+Config options, environment variables in brackets:
+  --config <path>                 config file [ACCD_CONFIG]
+  --broadcasting-json <path>      ACC broadcasting.json [ACCD_BROADCASTING_JSON]
+  --profile <name>                connection profile of the config file [ACCD_PROFILE]
+  --connections <name,name,...>   profiles to connect at the same time [ACCD_CONNECTIONS]
+  --display-name <name>           [ACCD_DISPLAY_NAME]
+  --connection-password <psw>     [ACCD_CONNECTION_PASSWORD]
+  --command-password <psw>        [ACCD_COMMAND_PASSWORD]
+  --update-interval <ms>          [ACCD_UPDATE_INTERVAL]
+  --bind-address <ip:port>        [ACCD_BIND_ADDRESS]
+  --destination-address <ip:port> [ACCD_DESTINATION_ADDRESS]
+  --race-laps <laps>              length of lap-limited races [ACCD_RACE_LAPS]
+  --speed-traps <table>           speed traps by track, e.g. '{ monza = [0.08, 0.61] }' [ACCD_SPEED_TRAPS]
+  --print-config                  print the resulting config and exit";
+
+#[derive(Debug, Clone)]
+struct Override {
+    key: &'static str,
+    /// Variable or flag the value comes from, used in error messages.
+    origin: String,
+    value: String,
+}
+
+/// Builds an `ACCDConfig` in layers, each one overriding the previous:
+/// built-in defaults, config file, broadcasting.json, environment variables, command line flags.
+/// broadcasting.json changes the top level values, keys set by a profile win over it.
+/// Environment variables and flags apply to every profile, the selected one is activated last.
+#[derive(Debug, Clone, Default)]
+pub struct ACCDConfigLoader {
+    pub config_path: Option<String>,
+    pub broadcasting_json: Option<String>,
+    pub profile: Option<String>,
+    /// Profiles to connect at the same time, for applications that support several connections.
+    pub connections: Vec<String>,
+    pub print_config: bool,
+    /// Command line arguments that are not config options, left to the application.
+    pub args: Vec<String>,
+    /// Where the config file is looked for without `--config`, first found is read.
+    pub default_config_paths: Vec<PathBuf>,
+    env_overrides: Vec<Override>,
+    flag_overrides: Vec<Override>,
+}
+
+impl ACCDConfigLoader {
+    /// Loader for the arguments and the environment of the running process.
+    pub fn from_env() -> Result<ACCDConfigLoader, ACCDConfigError> {
+        ACCDConfigLoader::new(env::args().skip(1), env::vars())
+    }
+
+    pub fn new<A, V>(args: A, vars: V) -> Result<ACCDConfigLoader, ACCDConfigError>
+    where
+        A: IntoIterator<Item = String>,
+        V: IntoIterator<Item = (String, String)>,
+    {
+        let mut loader = ACCDConfigLoader::default();
+        let mut user_config_dir = None;
+        let mut home_dir = None;
+
+        for (name, value) in vars {
+            match name.as_str() {
+                "XDG_CONFIG_HOME" | "APPDATA" => user_config_dir = Some(PathBuf::from(value)),
+                "HOME" => home_dir = Some(PathBuf::from(value)),
+                "ACCD_CONFIG" => loader.config_path = Some(value),
+                "ACCD_BROADCASTING_JSON" => loader.broadcasting_json = Some(value),
+                "ACCD_PROFILE" => loader.profile = Some(value),
+                "ACCD_CONNECTIONS" => loader.connections = split_list(&value),
+                _ => {
+                    let key = name
+                        .strip_prefix("ACCD_")
+                        .and_then(|key| overridable_key(&key.to_lowercase()));
+                    if let Some(key) = key {
+                        loader.env_overrides.push(Override {
+                            key,
+                            origin: name,
+                            value,
+                        });
+                    }
+                }
+            }
+        }
+
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        if let Some(exe_dir) = &exe_dir {
+            loader
+                .default_config_paths
+                .push(exe_dir.join(DEFAULT_CONFIG_FILE));
+        }
+        if let Some(dir) = user_config_dir.or_else(|| home_dir.map(|home| home.join(".config"))) {
+            loader
+                .default_config_paths
+                .push(dir.join("accd").join(DEFAULT_CONFIG_FILE));
+        }
+        // Binaries built in a checkout are in <repository>/target/<profile>.
+        if let Some(exe_dir) = &exe_dir {
+            loader.default_config_paths.extend(
+                exe_dir
+                    .ancestors()
+                    .skip(1)
+                    .map(|dir| dir.join(CHECKOUT_CONFIG_PATH))
+                    .find(|path| path.exists()),
+            );
+        }
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--print-config" {
+                loader.print_config = true;
+                continue;
+            }
+            if !arg.starts_with("--") {
+                loader.args.push(arg);
+                continue;
+            }
+
+            // Both "--flag value" and "--flag=value" are accepted.
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), value.to_string()),
+                None => match args.next() {
+                    Some(value) => (arg, value),
+                    None => {
+                        return Err(ACCDConfigError::Argument(format!(
+                            "{} requires a value",
+                            arg
+                        )))
+                    }
+                },
+            };
+
+            match flag.as_str() {
+                "--config" => loader.config_path = Some(value),
+                "--broadcasting-json" => loader.broadcasting_json = Some(value),
+                "--profile" => loader.profile = Some(value),
+                "--connections" => loader.connections = split_list(&value),
+                _ => match overridable_key(&flag[2..].replace('-', "_")) {
+                    Some(key) => loader.flag_overrides.push(Override {
+                        key,
+                        origin: flag,
+                        value,
+                    }),
+                    None => {
+                        return Err(ACCDConfigError::Argument(format!(
+                            "unknown option {}",
+                            flag
+                        )))
+                    }
+                },
+            }
+        }
+
+        Ok(loader)
+    }
+
+    /// Config file `load` reads, the first of `default_config_paths` that exists without
+    /// `--config`. It's an error when there's none, the built-in passwords are only examples.
+    pub fn config_file(&self) -> Result<String, ACCDConfigError> {
+        if let Some(path) = &self.config_path {
+            return Ok(path.clone());
+        }
+
+        self.default_config_paths
+            .iter()
+            .find(|path| path.exists())
+            .map(|path| path.display().to_string())
+            .ok_or_else(|| {
+                ACCDConfigError::NoConfigFile(
+                    self.default_config_paths
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect(),
+                )
+            })
+    }
+
+    pub fn load(&self) -> Result<ACCDConfig, ACCDConfigError> {
+        let path = self.config_file()?;
+        let source = fs::read_to_string(&path).map_err(|e| ACCDConfigError::Io(path.clone(), e))?;
+
+        // broadcasting.json goes under the profiles, the later layers go in every one.
+        let mut config = ACCDConfig::default();
+        config
+            .apply_toml_with_layer(&source, |config| match &self.broadcasting_json {
+                Some(json) => config
+                    .apply_broadcasting_json_file(json)
+                    .map_err(|e| in_file(json, e)),
+                None => Ok(()),
+            })
+            .map_err(|e| in_file(&path, e))?;
+
+        for o in self.env_overrides.iter().chain(self.flag_overrides.iter()) {
+            let invalid = |msg| ACCDConfigError::InvalidValue(o.origin.clone(), None, msg);
+            config.set(o.key, &o.value).map_err(invalid)?;
+            for profile in config.profiles.values_mut() {
+                profile.set(o.key, &o.value).map_err(invalid)?;
+            }
+        }
+
+        if let Some(name) = self.profile.as_ref().or(config.default_profile.as_ref()) {
+            let name = name.clone();
+            config.activate_profile(&name)?;
+        }
+
+        Ok(config)
+    }
+
+    /// One config per profile of `--connections`, together with the profile name.
+    /// Without `--connections` it's the config of `load`, named after its profile or "default".
+    pub fn load_connections(&self) -> Result<Vec<(String, ACCDConfig)>, ACCDConfigError> {
+        let config = self.load()?;
+        if self.connections.is_empty() {
+            let name = config
+                .active_profile
+                .clone()
+                .unwrap_or_else(|| "default".to_string());
+            return Ok(vec![(name, config)]);
+        }
+
+        let mut connections = Vec::new();
+        for name in self.connections.iter() {
+            let mut profile = config.clone();
+            profile.activate_profile(name)?;
+            connections.push((name.clone(), profile));
+        }
+
+        Ok(connections)
+    }
+}
+
+/// Error of the file at `path`, read errors and errors of other files already name theirs.
+fn in_file(path: &str, e: ACCDConfigError) -> ACCDConfigError {
+    match e {
+        ACCDConfigError::Io(..) | ACCDConfigError::File(..) => e,
+        e => ACCDConfigError::File(path.to_string(), Box::new(e)),
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn overridable_key(key: &str) -> Option<&'static str> {
+    OVERRIDABLE_KEYS.iter().find(|k| **k == key).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Writes `content` to a file of the temp directory unique to the test.
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = env::temp_dir().join(format!("accd_loader_{}_{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn missing_config_file_is_an_error() {
+        let mut loader = ACCDConfigLoader::new(Vec::new(), Vec::new()).unwrap();
+        loader.default_config_paths = vec![PathBuf::from("/nonexistent/accd.toml")];
+        match loader.load() {
+            Err(ACCDConfigError::NoConfigFile(paths)) => {
+                assert_eq!(paths, vec!["/nonexistent/accd.toml".to_string()])
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_paths_come_from_the_environment() {
+        let loader =
+            ACCDConfigLoader::new(Vec::new(), vars(&[("HOME", "/home/director")])).unwrap();
+        assert!(loader
+            .default_config_paths
+            .contains(&PathBuf::from("/home/director/.config/accd/accd.toml")));
+        assert!(loader
+            .default_config_paths
+            .iter()
+            .all(|path| path.is_absolute()));
+    }
+
+    #[test]
+    fn flags_override_env_override_file() {
+        let path = temp_file(
+            "layers.toml",
+            "display_name = \"File\"\nrace_laps = 10\nupdate_interval = 100\n",
+        );
+        let loader = ACCDConfigLoader::new(
+            args(&["--config", &path, "--race-laps=30", "127.0.0.1:8080"]),
+            vars(&[("ACCD_RACE_LAPS", "20"), ("ACCD_UPDATE_INTERVAL", "500")]),
+        )
+        .unwrap();
+        let config = loader.load().unwrap();
+
+        assert_eq!(config.display_name, "File");
+        assert_eq!(config.update_interval, 500);
+        assert_eq!(config.race_laps, Some(30));
+        assert_eq!(loader.args, args(&["127.0.0.1:8080"]));
+    }
+
+    #[test]
+    fn layers_apply_to_the_activated_profile() {
+        let path = temp_file(
+            "profiles.toml",
+            "default_profile = \"race\"\n\
+             [profiles.race]\n\
+             destination_address = \"192.168.1.11:9000\"\n\
+             connection_password = \"race\"\n\
+             [profiles.practice]\n\
+             display_name = \"Practice\"\n",
+        );
+        let json = temp_file(
+            "broadcasting.json",
+            "{\"updListenerPort\": 9100, \"connectionPassword\": \"acc\", \"commandPassword\": \"cmd\"}",
+        );
+        let loader = ACCDConfigLoader::new(
+            args(&["--config", &path, "--broadcasting-json", &json]),
+            vars(&[("ACCD_RACE_LAPS", "25")]),
+        )
+        .unwrap();
+
+        let config = loader.load().unwrap();
+        assert_eq!(config.active_profile.as_deref(), Some("race"));
+        // The keys set by the profile win over broadcasting.json.
+        assert_eq!(config.destination_addr.to_string(), "192.168.1.11:9000");
+        assert_eq!(config.connection_psw, "race");
+        assert_eq!(config.command_psw, "cmd");
+        assert_eq!(config.race_laps, Some(25));
+
+        let loader = ACCDConfigLoader {
+            profile: Some("practice".to_string()),
+            ..loader
+        };
+        let config = loader.load().unwrap();
+        assert_eq!(config.display_name, "Practice");
+        assert_eq!(config.destination_addr.port(), 9100);
+        assert_eq!(config.connection_psw, "acc");
+        assert_eq!(config.race_laps, Some(25));
+    }
+
+    #[test]
+    fn broadcasting_json_goes_under_the_profiles() {
+        let path = temp_file(
+            "layered.toml",
+            "destination_address = \"192.168.1.10:9000\"\n\
+             connection_password = \"file\"\n\
+             [profiles.a]\nbind_address = \"0.0.0.0:3401\"\nconnection_password = \"a\"\n\
+             [profiles.b]\nbind_address = \"0.0.0.0:3402\"\n",
+        );
+        let json = temp_file(
+            "layered.json",
+            "{\"updListenerPort\": 9100, \"connectionPassword\": \"acc\"}",
+        );
+        let loader = ACCDConfigLoader::new(
+            args(&[
+                "--config",
+                &path,
+                "--broadcasting-json",
+                &json,
+                "--connections",
+                "a,b",
+            ]),
+            Vec::new(),
+        )
+        .unwrap();
+
+        let connections = loader.load_connections().unwrap();
+        let (a, b) = (&connections[0].1, &connections[1].1);
+        assert_eq!(a.connection_psw, "a");
+        assert_eq!(b.connection_psw, "acc");
+        // broadcasting.json sets the port of the top level destination, the ip is kept.
+        assert_eq!(a.destination_addr.to_string(), "192.168.1.10:9100");
+        assert_eq!(b.destination_addr.to_string(), "192.168.1.10:9100");
+
+        let config = ACCDConfigLoader {
+            connections: Vec::new(),
+            ..loader
+        }
+        .load()
+        .unwrap();
+        assert_eq!(config.active_profile, None);
+        assert_eq!(config.connection_psw, "acc");
+    }
+
+    #[test]
+    fn connections_get_their_profile() {
+        let path = temp_file(
+            "connections.toml",
+            "[profiles.a]\nbind_address = \"0.0.0.0:3401\"\n\
+             [profiles.b]\nbind_address = \"0.0.0.0:3402\"\n",
+        );
+        let loader = ACCDConfigLoader::new(
+            args(&["--config", &path, "--connections", "b,a"]),
+            Vec::new(),
+        )
+        .unwrap();
+        let connections = loader.load_connections().unwrap();
+        let names: Vec<&str> = connections.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a"]);
+        assert_eq!(connections[0].1.bind_addr.port(), 3402);
+        assert_eq!(connections[1].1.bind_addr.port(), 3401);
+    }
+
+    #[test]
+    fn invalid_overrides_name_their_origin() {
+        let path = temp_file("origin.toml", "");
+        let loader = ACCDConfigLoader::new(
+            args(&["--config", &path]),
+            vars(&[("ACCD_UPDATE_INTERVAL", "often")]),
+        )
+        .unwrap();
+        match loader.load() {
+            Err(ACCDConfigError::InvalidValue(origin, _, _)) => {
+                assert_eq!(origin, "ACCD_UPDATE_INTERVAL")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        assert!(ACCDConfigLoader::new(args(&["--unknown", "1"]), Vec::new()).is_err());
+        assert!(ACCDConfigLoader::new(args(&["--config"]), Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::accd_config::ACCDConfig;
+use crate::accd_protocol::{ACCDProtocol, ListenResult};
+use crate::accd_session_state::ACCDSessionState;
+use crate::accd_session_time::SessionTime;
+
+/// A `ListenResult` tagged with the id of the connection it comes from.
+#[derive(Debug, Clone)]
+pub struct ACCDConnectionEvent {
+    pub connection_id: String,
+    pub listen_result: ListenResult,
+}
+
+#[derive(Debug)]
+pub enum ACCDConnectionError {
+    DuplicateId(String),
+    UnknownId(String),
+    Io(String, io::Error),
+}
+
+impl fmt::Display for ACCDConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ACCDConnectionError::DuplicateId(id) => {
+                write!(f, "connection \"{}\" already exists", id)
+            }
+            ACCDConnectionError::UnknownId(id) => write!(f, "unknown connection \"{}\"", id),
+            ACCDConnectionError::Io(id, e) => write!(f, "connection \"{}\": {}", id, e),
+        }
+    }
+}
+
+impl Error for ACCDConnectionError {}
+
+type Subscribers = Arc<Mutex<Vec<Sender<ACCDConnectionEvent>>>>;
+
+fn subscribe(subscribers: &Subscribers) -> Receiver<ACCDConnectionEvent> {
+    let (tx, rx) = mpsc::channel();
+    subscribers.lock().unwrap().push(tx);
+    rx
+}
+
+/// Sends the event to every subscriber, dropping the ones whose receiver is gone.
+fn publish(subscribers: &Subscribers, event: &ACCDConnectionEvent) {
+    subscribers
+        .lock()
+        .unwrap()
+        .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+}
+
+/// One ACC instance: its protocol, the session state kept up to date by a listener thread
+/// and the subscribers of its events.
+pub struct ACCDConnection {
+    pub id: String,
+    pub accdp: Arc<Mutex<ACCDProtocol>>,
+    pub state: Arc<Mutex<ACCDSessionState>>,
+    subscribers: Subscribers,
+    running: Arc<AtomicBool>,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl ACCDConnection {
+    /// Events of this connection only.
+    pub fn subscribe(&self) -> Receiver<ACCDConnectionEvent> {
+        subscribe(&self.subscribers)
+    }
+
+    fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(listener) = self.listener.take() {
+            // listen_shared times out, the thread notices the flag within LISTEN_TIMEOUT.
+            let _ = listener.join();
+        }
+        self.accdp.lock().unwrap().disconnect();
+        self.state.lock().unwrap().end_session();
+    }
+}
+
+/// Several ACC connections at once, each one with its own bind address, session state
+/// and event feed. Events can be received merged or per connection, commands are routed
+/// to a connection by id.
+#[derive(Default)]
+pub struct ACCDConnectionManager {
+    connections: Vec<ACCDConnection>,
+    subscribers: Subscribers,
+    /// Track maps directory of the connections added from now on.
+    pub track_maps_dir: Option<PathBuf>,
+    /// Reports directory of the connections added from now on.
+    pub reports_dir: Option<PathBuf>,
+    /// Timed position samples of the connections added from now on, see `ACCDPositionHistory`.
+    pub position_sample_interval: Option<SessionTime>,
+}
+
+impl ACCDConnectionManager {
+    pub fn new() -> Self {
+        ACCDConnectionManager::default()
+    }
+
+    /// Connects to the ACC instance of `config` and starts listening to it.
+    pub fn add(&mut self, id: &str, config: ACCDConfig) -> Result<(), ACCDConnectionError> {
+        if self.get(id).is_some() {
+            return Err(ACCDConnectionError::DuplicateId(id.to_string()));
+        }
+
+        let mut state = ACCDSessionState::new();
+        state.race_laps = config.race_laps;
+        state.track_maps_dir = self.track_maps_dir.clone();
+        state.reports_dir = self.reports_dir.clone();
+        state.position_history.sample_interval = self.position_sample_interval;
+
+        let mut accdp = ACCDProtocol::new(config);
+        accdp
+            .connect()
+            .map_err(|e| ACCDConnectionError::Io(id.to_string(), e))?;
+
+        let mut connection = ACCDConnection {
+            id: id.to_string(),
+            accdp: Arc::new(Mutex::new(accdp)),
+            state: Arc::new(Mutex::new(state)),
+            subscribers: Subscribers::default(),
+            running: Arc::new(AtomicBool::new(true)),
+            listener: None,
+        };
+
+        let c_id = id.to_string();
+        let c_accdp = Arc::clone(&connection.accdp);
+        let c_state = Arc::clone(&connection.state);
+        let c_subscribers = Arc::clone(&connection.subscribers);
+        let c_merged = Arc::clone(&self.subscribers);
+        let c_running = Arc::clone(&connection.running);
+        connection.listener = Some(thread::spawn(move || {
+            while c_running.load(Ordering::Relaxed) {
+                // The protocol isn't locked while waiting, commands go out meanwhile.
+                let listen_result = ACCDProtocol::listen_shared(&c_accdp);
+
+                if !matches!(listen_result, ListenResult::Error) {
+                    c_state.lock().unwrap().update(&listen_result);
+
+                    let event = ACCDConnectionEvent {
+                        connection_id: c_id.clone(),
+                        listen_result,
+                    };
+                    publish(&c_subscribers, &event);
+                    publish(&c_merged, &event);
+                }
+            }
+        }));
+
+        self.connections.push(connection);
+        Ok(())
+    }
+
+    /// Disconnects and forgets a connection, returns `false` if there's no such connection.
+    pub fn remove(&mut self, id: &str) -> bool {
+        match self.connections.iter().position(|c| c.id == id) {
+            Some(index) => {
+                self.connections.remove(index).stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Connections in the order they were added.
+    pub fn connections(&self) -> &[ACCDConnection] {
+        &self.connections
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ACCDConnection> {
+        self.connections.iter().find(|c| c.id == id)
+    }
+
+    /// The first connection added, used when a consumer doesn't name one.
+    pub fn first(&self) -> Option<&ACCDConnection> {
+        self.connections.first()
+    }
+
+    /// Events of every connection.
+    pub fn subscribe(&self) -> Receiver<ACCDConnectionEvent> {
+        subscribe(&self.subscribers)
+    }
+
+    /// Events of a single connection.
+    pub fn subscribe_to(
+        &self,
+        id: &str,
+    ) -> Result<Receiver<ACCDConnectionEvent>, ACCDConnectionError> {
+        self.get(id)
+            .map(|c| c.subscribe())
+            .ok_or_else(|| ACCDConnectionError::UnknownId(id.to_string()))
+    }
+
+    /// Runs `command` on the protocol of the given connection.
+    pub fn send<R, F>(&self, id: &str, command: F) -> Result<R, ACCDConnectionError>
+    where
+        F: FnOnce(&mut ACCDProtocol) -> R,
+    {
+        let connection = self
+            .get(id)
+            .ok_or_else(|| ACCDConnectionError::UnknownId(id.to_string()))?;
+        let mut accdp = connection.accdp.lock().unwrap();
+        Ok(command(&mut accdp))
+    }
+}
+
+impl Drop for ACCDConnectionManager {
+    fn drop(&mut self) {
+        for connection in self.connections.iter_mut() {
+            connection.stop();
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use std::fmt;
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 use crate::accd_enums::NationalityEnum;
 use crate::accd_utils::read_string;
@@ -7,6 +7,7 @@ use crate::accd_utils::read_string;
 use byteorder::*;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DriverCategory {
     Platinum = 3,
     Gold = 2,
@@ -28,6 +29,7 @@ impl From<u8> for DriverCategory {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDDriverInfo {
     pub first_name: String,
     pub last_name: String,
@@ -37,14 +39,14 @@ pub struct ACCDDriverInfo {
 }
 
 impl ACCDDriverInfo {
-    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> Self {
-        ACCDDriverInfo {
-            first_name: read_string(cur),
-            last_name: read_string(cur),
-            short_name: read_string(cur),
-            category: DriverCategory::from(cur.read_u8().unwrap()),
-            nationality: NationalityEnum::from(cur.read_u16::<NativeEndian>().unwrap() as u8),
-        }
+    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> io::Result<Self> {
+        Ok(ACCDDriverInfo {
+            first_name: read_string(cur)?,
+            last_name: read_string(cur)?,
+            short_name: read_string(cur)?,
+            category: DriverCategory::from(cur.read_u8()?),
+            nationality: NationalityEnum::from(cur.read_u16::<NativeEndian>()? as u8),
+        })
     }
 }
 
@@ -0,0 +1,385 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::accd_car_info::ACCDCarInfo;
+use crate::accd_realtime_car_update::ACCDRealtimeCarUpdate;
+use crate::accd_realtime_update::ACCDRealtimeUpdate;
+
+/// Entry list situations reported by `ACCDEntryListSync`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDEntryListSyncEvent {
+    /// A car update arrived for a car that is not in the entry list,
+    /// its updates are buffered until the entry list has it.
+    UnknownCar { car_index: u16 },
+    /// The driver count of a car update disagrees with the entry list,
+    /// usually after a driver swap. The updates are buffered until the entry list catches up.
+    DriverCountMismatch {
+        car_index: u16,
+        entry_list_drivers: usize,
+        update_drivers: u8,
+    },
+    /// The buffered updates of a car were delivered after the entry list caught up.
+    UpdatesReleased { car_index: u16, count: usize },
+    /// The buffered updates of a car were discarded, the new entry list doesn't have it.
+    UpdatesDropped { car_index: u16, count: usize },
+    /// A new session started, track data and entry list are requested again.
+    SessionChanged,
+}
+
+/// What the protocol has to deliver and request after feeding a message to the sync.
+#[derive(Debug, Default)]
+pub struct ACCDEntryListSyncOutput {
+    /// Car updates ready to be delivered, in arrival order.
+    pub car_updates: Vec<ACCDRealtimeCarUpdate>,
+    pub events: Vec<ACCDEntryListSyncEvent>,
+    pub request_entry_list: bool,
+    pub request_track_data: bool,
+}
+
+/// Keeps the realtime car updates consistent with the entry list: updates of unknown cars
+/// are buffered, a new entry list is requested (at most once every `entry_list_throttle`)
+/// and the track data is requested again when the session changes.
+///
+/// The current time is passed by the caller, the sync doesn't read the clock.
+#[derive(Debug, Clone)]
+pub struct ACCDEntryListSync {
+    /// Minimum time between two entry list requests.
+    pub entry_list_throttle: Duration,
+    /// Time between two track data requests after a session change, until ACC answers.
+    pub track_data_interval: Duration,
+    /// Updates kept per car while waiting for the entry list, older ones are discarded.
+    pub max_buffered_updates: usize,
+    last_entry_list_request: Option<Instant>,
+    last_track_data_request: Option<Instant>,
+    track_data_pending: bool,
+    session: Option<(i32, i32)>,
+    buffered: BTreeMap<u16, Vec<ACCDRealtimeCarUpdate>>,
+}
+
+impl Default for ACCDEntryListSync {
+    fn default() -> Self {
+        ACCDEntryListSync {
+            entry_list_throttle: Duration::from_secs(1),
+            track_data_interval: Duration::from_secs(2),
+            max_buffered_updates: 10,
+            last_entry_list_request: None,
+            last_track_data_request: None,
+            track_data_pending: false,
+            session: None,
+            buffered: BTreeMap::new(),
+        }
+    }
+}
+
+impl ACCDEntryListSync {
+    pub fn new() -> Self {
+        ACCDEntryListSync::default()
+    }
+
+    /// Car indexes with buffered updates.
+    pub fn waiting_cars(&self) -> Vec<u16> {
+        self.buffered.keys().copied().collect()
+    }
+
+    pub fn car_update(
+        &mut self,
+        entry_list: &[ACCDCarInfo],
+        car_update: ACCDRealtimeCarUpdate,
+        now: Instant,
+    ) -> ACCDEntryListSyncOutput {
+        let mut output = ACCDEntryListSyncOutput::default();
+        let car_index = car_update.car_index as u16;
+
+        let event = match entry_list.iter().find(|car| car.car_index == car_index) {
+            Some(car) if car.drivers.len() == car_update.driver_count as usize => {
+                self.release(car_index, car.drivers.len(), &mut output);
+                output.car_updates.push(car_update);
+                return output;
+            }
+            Some(car) => ACCDEntryListSyncEvent::DriverCountMismatch {
+                car_index,
+                entry_list_drivers: car.drivers.len(),
+                update_drivers: car_update.driver_count,
+            },
+            None => ACCDEntryListSyncEvent::UnknownCar { car_index },
+        };
+
+        let buffer = self.buffered.entry(car_index).or_default();
+        if buffer.is_empty() {
+            output.events.push(event);
+        }
+        buffer.push(car_update);
+        if buffer.len() > self.max_buffered_updates {
+            buffer.remove(0);
+        }
+
+        output.request_entry_list = self.entry_list_request_due(now);
+        output
+    }
+
+    /// A new entry list arrived, the drivers of its cars follow one by one.
+    pub fn entry_list(&mut self, entry_list: &[ACCDCarInfo]) -> ACCDEntryListSyncOutput {
+        let mut output = ACCDEntryListSyncOutput::default();
+
+        let gone: Vec<u16> = self
+            .buffered
+            .keys()
+            .copied()
+            .filter(|car_index| !entry_list.iter().any(|car| car.car_index == *car_index))
+            .collect();
+        for car_index in gone {
+            let count = self.buffered.remove(&car_index).map_or(0, |b| b.len());
+            output
+                .events
+                .push(ACCDEntryListSyncEvent::UpdatesDropped { car_index, count });
+        }
+
+        output
+    }
+
+    /// The drivers of a car arrived, its buffered updates are delivered if they match.
+    pub fn entry_list_car(&mut self, car_info: &ACCDCarInfo) -> ACCDEntryListSyncOutput {
+        let mut output = ACCDEntryListSyncOutput::default();
+
+        let matches = self
+            .buffered
+            .get(&car_info.car_index)
+            .and_then(|buffer| buffer.last())
+            .is_some_and(|last| last.driver_count as usize == car_info.drivers.len());
+
+        if matches {
+            self.release(car_info.car_index, car_info.drivers.len(), &mut output);
+        }
+
+        output
+    }
+
+    pub fn realtime_update(
+        &mut self,
+        realtime_update: &ACCDRealtimeUpdate,
+        now: Instant,
+    ) -> ACCDEntryListSyncOutput {
+        let mut output = ACCDEntryListSyncOutput::default();
+
+        let session = (
+            realtime_update.event_index(),
+            realtime_update.session_index(),
+        );
+        if self.session.is_some_and(|previous| previous != session) {
+            output.events.push(ACCDEntryListSyncEvent::SessionChanged);
+            self.track_data_pending = true;
+            self.last_track_data_request = None;
+            output.request_entry_list = self.entry_list_request_due(now);
+        }
+        self.session = Some(session);
+
+        if self.track_data_pending {
+            let due = match self.last_track_data_request {
+                Some(last) => now.saturating_duration_since(last) >= self.track_data_interval,
+                None => true,
+            };
+            if due {
+                self.last_track_data_request = Some(now);
+                output.request_track_data = true;
+            }
+        }
+
+        output
+    }
+
+    pub fn track_data(&mut self) {
+        self.track_data_pending = false;
+    }
+
+    /// Forgets everything, e.g. after connecting to another ACC instance.
+    pub fn reset(&mut self) {
+        *self = ACCDEntryListSync {
+            entry_list_throttle: self.entry_list_throttle,
+            track_data_interval: self.track_data_interval,
+            max_buffered_updates: self.max_buffered_updates,
+            ..ACCDEntryListSync::default()
+        };
+    }
+
+    /// Delivers the buffered updates of a car that match its entry list driver count,
+    /// the others refer to drivers that are not there anymore.
+    fn release(&mut self, car_index: u16, drivers: usize, output: &mut ACCDEntryListSyncOutput) {
+        if let Some(buffer) = self.buffered.remove(&car_index) {
+            let car_updates: Vec<ACCDRealtimeCarUpdate> = buffer
+                .into_iter()
+                .filter(|car_update| car_update.driver_count as usize == drivers)
+                .collect();
+            output.events.push(ACCDEntryListSyncEvent::UpdatesReleased {
+                car_index,
+                count: car_updates.len(),
+            });
+            output.car_updates.extend(car_updates);
+        }
+    }
+
+    fn entry_list_request_due(&mut self, now: Instant) -> bool {
+        let due = match self.last_entry_list_request {
+            Some(last) => now.saturating_duration_since(last) >= self.entry_list_throttle,
+            None => true,
+        };
+        if due {
+            self.last_entry_list_request = Some(now);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accd_driver_info::ACCDDriverInfo;
+
+    fn car(car_index: u16, drivers: usize) -> ACCDCarInfo {
+        let mut car = ACCDCarInfo::new(car_index);
+        car.drivers = vec![ACCDDriverInfo::default(); drivers];
+        car
+    }
+
+    fn car_update(car_index: i32, driver_count: u8) -> ACCDRealtimeCarUpdate {
+        ACCDRealtimeCarUpdate {
+            car_index,
+            driver_count,
+            ..ACCDRealtimeCarUpdate::default()
+        }
+    }
+
+    fn session(event_index: i32, session_index: i32) -> ACCDRealtimeUpdate {
+        ACCDRealtimeUpdate {
+            event_index,
+            session_index,
+            ..ACCDRealtimeUpdate::default()
+        }
+    }
+
+    #[test]
+    fn unknown_cars_are_buffered_until_the_entry_list_has_them() {
+        let mut sync = ACCDEntryListSync::new();
+        let now = Instant::now();
+
+        let output = sync.car_update(&[], car_update(4, 1), now);
+        assert!(output.car_updates.is_empty());
+        assert!(output.request_entry_list);
+        assert_eq!(
+            output.events,
+            vec![ACCDEntryListSyncEvent::UnknownCar { car_index: 4 }]
+        );
+
+        // Throttled, and reported only once per car.
+        let output = sync.car_update(&[], car_update(4, 1), now + Duration::from_millis(500));
+        assert!(!output.request_entry_list);
+        assert!(output.events.is_empty());
+        assert_eq!(sync.waiting_cars(), vec![4]);
+
+        let entry_list = vec![car(4, 0)];
+        assert!(sync.entry_list(&entry_list).events.is_empty());
+        let output = sync.entry_list_car(&car(4, 1));
+        assert_eq!(output.car_updates.len(), 2);
+        assert_eq!(
+            output.events,
+            vec![ACCDEntryListSyncEvent::UpdatesReleased {
+                car_index: 4,
+                count: 2
+            }]
+        );
+        assert!(sync.waiting_cars().is_empty());
+
+        let output = sync.car_update(&[car(4, 1)], car_update(4, 1), now + Duration::from_secs(2));
+        assert_eq!(output.car_updates.len(), 1);
+        assert!(!output.request_entry_list);
+    }
+
+    #[test]
+    fn driver_swaps_release_only_the_matching_updates() {
+        let mut sync = ACCDEntryListSync::new();
+        let now = Instant::now();
+        let entry_list = vec![car(2, 1)];
+
+        sync.car_update(&entry_list, car_update(2, 2), now);
+        let output = sync.car_update(&entry_list, car_update(2, 1), now);
+        assert_eq!(output.car_updates.len(), 1);
+        assert_eq!(
+            output.events,
+            vec![ACCDEntryListSyncEvent::UpdatesReleased {
+                car_index: 2,
+                count: 0
+            }]
+        );
+
+        let output = sync.car_update(&entry_list, car_update(2, 2), now);
+        assert_eq!(
+            output.events,
+            vec![ACCDEntryListSyncEvent::DriverCountMismatch {
+                car_index: 2,
+                entry_list_drivers: 1,
+                update_drivers: 2
+            }]
+        );
+        assert!(sync.entry_list_car(&car(2, 1)).car_updates.is_empty());
+        assert_eq!(sync.entry_list_car(&car(2, 2)).car_updates.len(), 1);
+    }
+
+    #[test]
+    fn buffers_are_capped_and_dropped_with_the_car() {
+        let mut sync = ACCDEntryListSync {
+            max_buffered_updates: 3,
+            ..ACCDEntryListSync::default()
+        };
+        let now = Instant::now();
+        for _ in 0..5 {
+            sync.car_update(&[], car_update(7, 1), now);
+        }
+
+        let output = sync.entry_list(&[car(1, 1)]);
+        assert_eq!(
+            output.events,
+            vec![ACCDEntryListSyncEvent::UpdatesDropped {
+                car_index: 7,
+                count: 3
+            }]
+        );
+        assert!(sync.waiting_cars().is_empty());
+    }
+
+    #[test]
+    fn session_changes_request_the_track_data_until_it_arrives() {
+        let mut sync = ACCDEntryListSync::new();
+        let now = Instant::now();
+
+        let output = sync.realtime_update(&session(0, 0), now);
+        assert!(output.events.is_empty());
+        assert!(!output.request_track_data);
+
+        let output = sync.realtime_update(&session(0, 1), now);
+        assert_eq!(output.events, vec![ACCDEntryListSyncEvent::SessionChanged]);
+        assert!(output.request_track_data);
+        assert!(output.request_entry_list);
+
+        let output = sync.realtime_update(&session(0, 1), now + Duration::from_secs(1));
+        assert!(!output.request_track_data);
+        let output = sync.realtime_update(&session(0, 1), now + Duration::from_secs(2));
+        assert!(output.request_track_data);
+
+        sync.track_data();
+        let output = sync.realtime_update(&session(0, 1), now + Duration::from_secs(5));
+        assert!(!output.request_track_data);
+    }
+
+    #[test]
+    fn reset_keeps_the_settings() {
+        let mut sync = ACCDEntryListSync {
+            max_buffered_updates: 3,
+            ..ACCDEntryListSync::default()
+        };
+        sync.car_update(&[], car_update(7, 1), Instant::now());
+
+        sync.reset();
+        assert_eq!(sync.max_buffered_updates, 3);
+        assert!(sync.waiting_cars().is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::accd_broadcasting_event::ACCDBroadcastingEvent;
+use crate::accd_car_info::ACCDCarInfo;
+use crate::accd_protocol::ListenResult;
+use crate::accd_realtime_car_update::ACCDRealtimeCarUpdate;
+use crate::accd_session_time::SessionTime;
+use crate::accd_session_tracker::{ACCDSessionEvent, ACCDSessionInfo};
+
+/// Splits in the lap rows, ACC tracks have 3 sectors.
+const SPLITS: usize = 3;
+
+const LAP_COLUMNS: &[&str] = &[
+    "session_time_ms",
+    "car_index",
+    "race_number",
+    "driver",
+    "lap",
+    "lap_time_ms",
+    "split_1_ms",
+    "split_2_ms",
+    "split_3_ms",
+    "is_invalid",
+    "is_valid_for_best",
+    "lap_type",
+];
+const EVENT_COLUMNS: &[&str] = &["session_time_ms", "event_type", "race_number", "message"];
+const CAR_COLUMNS: &[&str] = &[
+    "session_time_ms",
+    "car_index",
+    "race_number",
+    "position",
+    "kmh",
+    "gear",
+    "spline_position",
+    "laps",
+    "location",
+];
+
+/// A CSV and a JSON Lines file with the same rows.
+struct Table {
+    csv: BufWriter<File>,
+    jsonl: BufWriter<File>,
+    columns: &'static [&'static str],
+}
+
+impl Table {
+    fn create(dir: &Path, name: &str, columns: &'static [&'static str]) -> io::Result<Table> {
+        let mut csv = BufWriter::new(File::create(dir.join(format!("{}.csv", name)))?);
+        writeln!(csv, "{}", columns.join(","))?;
+        let jsonl = BufWriter::new(File::create(dir.join(format!("{}.jsonl", name)))?);
+        Ok(Table {
+            csv,
+            jsonl,
+            columns,
+        })
+    }
+
+    /// `values` in the order of the columns.
+    fn write(&mut self, values: Vec<Value>) -> io::Result<()> {
+        let cells: Vec<String> = values.iter().map(csv_cell).collect();
+        writeln!(self.csv, "{}", cells.join(","))?;
+
+        let row: serde_json::Map<String, Value> = self
+            .columns
+            .iter()
+            .map(|column| column.to_string())
+            .zip(values)
+            .collect();
+        writeln!(self.jsonl, "{}", Value::Object(row))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.csv.flush()?;
+        self.jsonl.flush()
+    }
+}
+
+/// Files of the session being exported.
+struct SessionFiles {
+    laps: Table,
+    events: Table,
+    cars: Table,
+}
+
+/// Writes completed laps, broadcasting events and down-sampled car updates to CSV and
+/// JSON Lines files, a new set of files for every session:
+/// `<time>_<session type>_e<event>_s<session>_{laps,events,cars}.{csv,jsonl}`.
+///
+/// Times are session times from the realtime updates, so a recording exports the same
+/// files it would have exported live.
+pub struct ACCDExporter {
+    pub dir: PathBuf,
+    /// Minimum session time between two rows of the same car in the cars files.
+    pub car_update_interval: SessionTime,
+    files: Option<SessionFiles>,
+    entry_list_cars: Vec<ACCDCarInfo>,
+    session_time: SessionTime,
+    laps: HashMap<u16, i32>,
+    car_samples: HashMap<u16, SessionTime>,
+}
+
+impl ACCDExporter {
+    /// Files are created in `dir` with the next realtime update.
+    pub fn new(dir: &Path) -> Self {
+        ACCDExporter {
+            dir: dir.to_path_buf(),
+            car_update_interval: SessionTime::from_secs(1.0),
+            files: None,
+            entry_list_cars: Vec::new(),
+            session_time: SessionTime::ZERO,
+            laps: HashMap::new(),
+            car_samples: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, listen_result: &ListenResult) -> io::Result<()> {
+        match listen_result {
+            ListenResult::Session(ACCDSessionEvent::SessionStarted(session)) => {
+                self.start_session(session)?;
+            }
+            ListenResult::RealTimeUpdate(rtu) => {
+                // Exporting started in the middle of a session.
+                if self.files.is_none() {
+                    self.start_session(&ACCDSessionInfo {
+                        event_index: rtu.event_index(),
+                        session_index: rtu.session_index(),
+                        session_type: rtu.session_type.clone(),
+                    })?;
+                }
+                self.session_time = rtu.session_time;
+            }
+            ListenResult::EntryList(entry_list_cars) => {
+                self.entry_list_cars = entry_list_cars.clone();
+            }
+            ListenResult::EntryListCar(car_info) => {
+                if let Some(car) = self
+                    .entry_list_cars
+                    .iter_mut()
+                    .find(|car| car.car_index == car_info.car_index)
+                {
+                    *car = car_info.clone();
+                }
+            }
+            ListenResult::RealTimeCarUpdate(car_update) => {
+                self.car_update(car_update)?;
+            }
+            ListenResult::BroadcastingEvent(broadcasting_event) => {
+                self.broadcasting_event(broadcasting_event)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(files) = &mut self.files {
+            files.laps.flush()?;
+            files.events.flush()?;
+            files.cars.flush()?;
+        }
+        Ok(())
+    }
+
+    fn start_session(&mut self, session: &ACCDSessionInfo) -> io::Result<()> {
+        self.flush()?;
+        self.files = None;
+        self.laps.clear();
+        self.car_samples.clear();
+
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let prefix = format!(
+            "{}_{:?}_e{}_s{}",
+            secs, session.session_type, session.event_index, session.session_index
+        )
+        .to_lowercase();
+
+        fs::create_dir_all(&self.dir)?;
+        let table = |name: &str, columns| {
+            Table::create(&self.dir, &format!("{}_{}", prefix, name), columns)
+        };
+        self.files = Some(SessionFiles {
+            laps: table("laps", LAP_COLUMNS)?,
+            events: table("events", EVENT_COLUMNS)?,
+            cars: table("cars", CAR_COLUMNS)?,
+        });
+        Ok(())
+    }
+
+    fn car_update(&mut self, car_update: &ACCDRealtimeCarUpdate) -> io::Result<()> {
+        let files = match &mut self.files {
+            Some(files) => files,
+            None => return Ok(()),
+        };
+        let car_index = car_update.car_index as u16;
+        let car_info = self
+            .entry_list_cars
+            .iter()
+            .find(|car_info| car_info.car_index == car_index);
+        let race_number = car_info.map(|car_info| car_info.race_number);
+
+        let previous_laps = self.laps.insert(car_index, car_update.laps);
+        if previous_laps.is_some_and(|laps| car_update.laps > laps) {
+            let lap = &car_update.last_lap;
+            let driver = car_info
+                .and_then(|car_info| car_info.drivers.get(lap.driver_index as usize))
+                .map(|driver| format!("{} {}", driver.first_name, driver.last_name));
+            let mut values = vec![
+                json!(self.session_time.as_millis().round() as i64),
+                json!(car_index),
+                json!(race_number),
+                json!(driver),
+                json!(car_update.laps),
+                json!(lap.lap_time_ms),
+            ];
+            values.extend((0..SPLITS).map(|i| json!(lap.splits.get(i).copied().flatten())));
+            values.extend(vec![
+                json!(lap.is_invalid),
+                json!(lap.is_valid_for_best),
+                json!(format!("{:?}", lap.lap_type)),
+            ]);
+            files.laps.write(values)?;
+        }
+
+        let sample_due = match self.car_samples.get(&car_index) {
+            Some(last) => {
+                self.session_time - *last >= self.car_update_interval || self.session_time < *last
+            }
+            None => true,
+        };
+        if sample_due {
+            self.car_samples.insert(car_index, self.session_time);
+            files.cars.write(vec![
+                json!(self.session_time.as_millis().round() as i64),
+                json!(car_index),
+                json!(race_number),
+                json!(car_update.position),
+                json!(car_update.kmh),
+                json!(car_update.gear),
+                json!(car_update.spline_position),
+                json!(car_update.laps),
+                json!(format!("{:?}", car_update.car_location)),
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    fn broadcasting_event(&mut self, broadcasting_event: &ACCDBroadcastingEvent) -> io::Result<()> {
+        let files = match &mut self.files {
+            Some(files) => files,
+            None => return Ok(()),
+        };
+        let race_number = broadcasting_event
+            .event_car_data
+            .as_ref()
+            .map(|car_info| car_info.race_number);
+
+        files.events.write(vec![
+            json!(broadcasting_event.event_time.as_millis().round() as i64),
+            json!(format!("{:?}", broadcasting_event.event_type)),
+            json!(race_number),
+            json!(broadcasting_event.event_msg),
+        ])
+    }
+}
+
+impl Drop for ACCDExporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            println!("ERROR({}): {}", self.dir.display(), e);
+        }
+    }
+}
+
+/// Strings quoted when needed, `null` as an empty cell.
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        Value::String(text) if text.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        }
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accd_realtime_update::RaceSessionType;
+    use std::env;
+
+    fn session(session_index: i32) -> ListenResult {
+        ListenResult::Session(ACCDSessionEvent::SessionStarted(ACCDSessionInfo {
+            event_index: 0,
+            session_index,
+            session_type: RaceSessionType::Race,
+        }))
+    }
+
+    fn car_update(laps: i32, lap_time_ms: i32) -> ListenResult {
+        let mut car_update = ACCDRealtimeCarUpdate {
+            car_index: 3,
+            laps,
+            ..ACCDRealtimeCarUpdate::default()
+        };
+        car_update.last_lap.lap_time_ms = Some(lap_time_ms);
+        ListenResult::RealTimeCarUpdate(car_update)
+    }
+
+    fn files(dir: &Path, suffix: &str) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().ends_with(suffix))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn csv_cells() {
+        assert_eq!(csv_cell(&Value::Null), "");
+        assert_eq!(csv_cell(&json!(12)), "12");
+        assert_eq!(csv_cell(&json!(1.5)), "1.5");
+        assert_eq!(csv_cell(&json!(true)), "true");
+        assert_eq!(csv_cell(&json!("Regular")), "Regular");
+        assert_eq!(csv_cell(&json!("Mario Rossi")), "Mario Rossi");
+        assert_eq!(csv_cell(&json!("Rossi, Mario")), "\"Rossi, Mario\"");
+        assert_eq!(csv_cell(&json!("5s \"penalty\"")), "\"5s \"\"penalty\"\"\"");
+        assert_eq!(csv_cell(&json!("line\nbreak")), "\"line\nbreak\"");
+        assert_eq!(csv_cell(&json!("line\r\nbreak")), "\"line\r\nbreak\"");
+    }
+
+    #[test]
+    fn sessions_get_their_own_files() {
+        let dir = env::temp_dir().join(format!("accd_export_sessions_{}", std::process::id()));
+        let mut exporter = ACCDExporter::new(&dir);
+        // Nothing is written before a session starts.
+        exporter.update(&car_update(1, 90_000)).unwrap();
+        assert!(!dir.exists());
+
+        exporter.update(&session(0)).unwrap();
+        assert_eq!(files(&dir, ".csv").len(), 3);
+        assert_eq!(files(&dir, ".jsonl").len(), 3);
+
+        exporter.update(&session(1)).unwrap();
+        let laps = files(&dir, "_laps.csv");
+        assert_eq!(laps.len(), 2);
+        assert!(laps[0].to_string_lossy().ends_with("_race_e0_s0_laps.csv"));
+        assert!(laps[1].to_string_lossy().ends_with("_race_e0_s1_laps.csv"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn one_lap_row_per_completed_lap() {
+        let dir = env::temp_dir().join(format!("accd_export_laps_{}", std::process::id()));
+        let mut exporter = ACCDExporter::new(&dir);
+        exporter.update(&session(0)).unwrap();
+
+        // The first update only tells the laps done so far.
+        exporter.update(&car_update(4, 91_000)).unwrap();
+        exporter.update(&car_update(5, 90_500)).unwrap();
+        exporter.update(&car_update(5, 90_500)).unwrap();
+        exporter.flush().unwrap();
+
+        let csv = fs::read_to_string(&files(&dir, "_laps.csv")[0]).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], LAP_COLUMNS.join(","));
+        assert!(rows[1].starts_with("0,3,,,5,90500,"));
+
+        let jsonl = fs::read_to_string(&files(&dir, "_laps.jsonl")[0]).unwrap();
+        let row: Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(row["lap"], 5);
+        assert_eq!(row["lap_time_ms"], 90_500);
+        assert_eq!(row["race_number"], Value::Null);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
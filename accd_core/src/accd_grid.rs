@@ -0,0 +1,488 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::accd_car_info::{ACCDCarInfo, CarClass};
+use crate::accd_realtime_car_update::ACCDRealtimeCarUpdate;
+use crate::accd_realtime_update::{ACCDRealtimeUpdate, RaceSessionType, SessionPhase};
+use crate::accd_session_tracker::ACCDSessionInfo;
+
+/// Place of a car on the starting grid.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDGridSlot {
+    pub car_index: u16,
+    pub race_number: i32,
+    pub car_class: CarClass,
+    pub position: i32,
+    /// Position among the cars of the same class.
+    pub class_position: i32,
+}
+
+/// Where a car is compared to where it started.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDPositionChange {
+    pub car_index: u16,
+    pub race_number: i32,
+    pub car_class: CarClass,
+    pub grid_position: i32,
+    pub position: i32,
+    /// Positions gained since the start, lost when negative.
+    pub gained: i32,
+    pub class_grid_position: i32,
+    pub class_position: i32,
+    pub class_gained: i32,
+}
+
+/// Starting grid of a race.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDGrid {
+    pub session: ACCDSessionInfo,
+    /// By grid position.
+    pub slots: Vec<ACCDGridSlot>,
+}
+
+impl ACCDGrid {
+    /// Grid of the cars of the entry list in the order of their positions,
+    /// cars without a position are left out.
+    pub fn new(
+        session: ACCDSessionInfo,
+        entry_list_cars: &[ACCDCarInfo],
+        positions: &HashMap<u16, i32>,
+    ) -> Self {
+        let mut cars: Vec<(&ACCDCarInfo, i32)> = entry_list_cars
+            .iter()
+            .filter_map(|car_info| {
+                let position = *positions.get(&car_info.car_index)?;
+                Some((car_info, position)).filter(|_| position > 0)
+            })
+            .collect();
+        cars.sort_by_key(|(_, position)| *position);
+
+        let mut class_counts: Vec<(CarClass, i32)> = Vec::new();
+        let slots = cars
+            .into_iter()
+            .map(|(car_info, position)| {
+                let car_class = car_info.car_class();
+                let class_position = match class_counts.iter_mut().find(|(c, _)| *c == car_class) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count
+                    }
+                    None => {
+                        class_counts.push((car_class, 1));
+                        1
+                    }
+                };
+                ACCDGridSlot {
+                    car_index: car_info.car_index,
+                    race_number: car_info.race_number,
+                    car_class,
+                    position,
+                    class_position,
+                }
+            })
+            .collect();
+
+        ACCDGrid { session, slots }
+    }
+
+    pub fn slot(&self, car_index: u16) -> Option<&ACCDGridSlot> {
+        self.slots.iter().find(|slot| slot.car_index == car_index)
+    }
+
+    /// Grid position by car index.
+    pub fn start_positions(&self) -> HashMap<u16, i32> {
+        self.slots
+            .iter()
+            .map(|slot| (slot.car_index, slot.position))
+            .collect()
+    }
+
+    /// Positions gained and lost by the cars of the grid, overall and in their class,
+    /// by current position. Cars without a position are left out.
+    pub fn position_changes(&self, positions: &HashMap<u16, i32>) -> Vec<ACCDPositionChange> {
+        let mut changes: Vec<ACCDPositionChange> = self
+            .slots
+            .iter()
+            .filter_map(|slot| {
+                let position = *positions.get(&slot.car_index)?;
+                if position <= 0 {
+                    return None;
+                }
+                // Only the cars of the grid count, late joiners didn't start with them.
+                let class_position = 1 + self
+                    .slots
+                    .iter()
+                    .filter(|other| other.car_class == slot.car_class)
+                    .filter(|other| {
+                        positions
+                            .get(&other.car_index)
+                            .is_some_and(|other_position| {
+                                *other_position > 0 && *other_position < position
+                            })
+                    })
+                    .count() as i32;
+                Some(ACCDPositionChange {
+                    car_index: slot.car_index,
+                    race_number: slot.race_number,
+                    car_class: slot.car_class,
+                    grid_position: slot.position,
+                    position,
+                    gained: slot.position - position,
+                    class_grid_position: slot.class_position,
+                    class_position,
+                    class_gained: slot.class_position - class_position,
+                })
+            })
+            .collect();
+        changes.sort_by_key(|change| change.position);
+        changes
+    }
+}
+
+/// Grid changes reported by `ACCDGridTracker`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDGridEvent {
+    /// The formation lap is over, the race starts from this grid.
+    GridCaptured(ACCDGrid),
+    /// A car gained or lost at least `first_lap_mover_threshold` positions,
+    /// overall or in its class, on the first lap.
+    FirstLapMover(ACCDPositionChange),
+}
+
+/// Captures the grid of races from the positions of the cars during the formation lap
+/// and reports the cars that moved the most on the first lap.
+#[derive(Debug, Clone)]
+pub struct ACCDGridTracker {
+    /// Positions a car has to gain or lose on the first lap to be reported.
+    pub first_lap_mover_threshold: i32,
+    session: Option<ACCDSessionInfo>,
+    /// The race is in `PreFormation` or `FormationLap`.
+    forming: bool,
+    grid: Option<ACCDGrid>,
+    positions: HashMap<u16, i32>,
+    laps: HashMap<u16, i32>,
+    /// Cars whose first lap was already looked at.
+    first_laps: HashSet<u16>,
+}
+
+impl Default for ACCDGridTracker {
+    fn default() -> Self {
+        ACCDGridTracker {
+            first_lap_mover_threshold: 3,
+            session: None,
+            forming: false,
+            grid: None,
+            positions: HashMap::new(),
+            laps: HashMap::new(),
+            first_laps: HashSet::new(),
+        }
+    }
+}
+
+impl ACCDGridTracker {
+    pub fn new() -> Self {
+        ACCDGridTracker::default()
+    }
+
+    /// Grid of the current race, once its formation lap is over.
+    pub fn grid(&self) -> Option<&ACCDGrid> {
+        self.grid.as_ref()
+    }
+
+    /// Forgets the session, e.g. after connecting to another ACC instance.
+    pub fn reset(&mut self) {
+        *self = ACCDGridTracker {
+            first_lap_mover_threshold: self.first_lap_mover_threshold,
+            ..ACCDGridTracker::default()
+        };
+    }
+
+    pub fn realtime_update(
+        &mut self,
+        realtime_update: &ACCDRealtimeUpdate,
+        entry_list_cars: &[ACCDCarInfo],
+    ) -> Vec<ACCDGridEvent> {
+        let session = ACCDSessionInfo {
+            event_index: realtime_update.event_index(),
+            session_index: realtime_update.session_index(),
+            session_type: realtime_update.session_type.clone(),
+        };
+        if self.session.as_ref() != Some(&session) {
+            self.reset();
+            self.session = Some(session.clone());
+        }
+
+        if session.session_type != RaceSessionType::Race {
+            return Vec::new();
+        }
+
+        let forming = matches!(
+            realtime_update.phase,
+            SessionPhase::PreFormation | SessionPhase::FormationLap
+        );
+        // Cars hold their grid positions until the start, the last ones seen are the grid.
+        let started = self.forming && !forming;
+        self.forming = forming;
+        if !started || self.grid.is_some() {
+            return Vec::new();
+        }
+
+        let grid = ACCDGrid::new(session, entry_list_cars, &self.positions);
+        self.grid = Some(grid.clone());
+        vec![ACCDGridEvent::GridCaptured(grid)]
+    }
+
+    pub fn car_update(&mut self, car_update: &ACCDRealtimeCarUpdate) -> Vec<ACCDGridEvent> {
+        let car_index = car_update.car_index as u16;
+        self.positions.insert(car_index, car_update.position);
+        let previous_laps = self.laps.insert(car_index, car_update.laps);
+
+        let grid = match &self.grid {
+            Some(grid) => grid,
+            None => return Vec::new(),
+        };
+        let first_lap = previous_laps == Some(0) && car_update.laps == 1;
+        if !first_lap || !self.first_laps.insert(car_index) {
+            return Vec::new();
+        }
+
+        grid.position_changes(&self.positions)
+            .into_iter()
+            .find(|change| change.car_index == car_index)
+            .filter(|change| {
+                change.gained.abs().max(change.class_gained.abs()) >= self.first_lap_mover_threshold
+            })
+            .map(ACCDGridEvent::FirstLapMover)
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GT3: u8 = 0;
+    const GT4: u8 = 50;
+
+    fn car(car_index: u16, car_model_type: u8) -> ACCDCarInfo {
+        ACCDCarInfo {
+            car_index,
+            car_model_type,
+            race_number: car_index as i32 + 100,
+            ..ACCDCarInfo::default()
+        }
+    }
+
+    fn entry_list() -> Vec<ACCDCarInfo> {
+        vec![
+            car(0, GT3),
+            car(1, GT4),
+            car(2, GT3),
+            car(3, GT4),
+            car(4, GT3),
+        ]
+    }
+
+    fn session(session_type: RaceSessionType) -> ACCDSessionInfo {
+        ACCDSessionInfo {
+            event_index: 0,
+            session_index: 0,
+            session_type,
+        }
+    }
+
+    fn positions(positions: &[(u16, i32)]) -> HashMap<u16, i32> {
+        positions.iter().copied().collect()
+    }
+
+    fn realtime_update(session_type: RaceSessionType, phase: SessionPhase) -> ACCDRealtimeUpdate {
+        ACCDRealtimeUpdate {
+            event_index: 0,
+            session_index: 0,
+            session_type,
+            phase,
+            ..ACCDRealtimeUpdate::default()
+        }
+    }
+
+    fn car_update(car_index: u16, position: i32, laps: i32) -> ACCDRealtimeCarUpdate {
+        ACCDRealtimeCarUpdate {
+            car_index: car_index as i32,
+            position,
+            laps,
+            ..ACCDRealtimeCarUpdate::default()
+        }
+    }
+
+    /// A tracker that saw the formation lap with the cars in car index order.
+    fn tracker_after_the_start() -> ACCDGridTracker {
+        let mut tracker = ACCDGridTracker::new();
+        tracker.realtime_update(
+            &realtime_update(RaceSessionType::Race, SessionPhase::FormationLap),
+            &entry_list(),
+        );
+        for car_index in 0..5 {
+            tracker.car_update(&car_update(car_index, car_index as i32 + 1, 0));
+        }
+        tracker.realtime_update(
+            &realtime_update(RaceSessionType::Race, SessionPhase::Session),
+            &entry_list(),
+        );
+        tracker
+    }
+
+    #[test]
+    fn grid_order_and_class_positions() {
+        let grid = ACCDGrid::new(
+            session(RaceSessionType::Race),
+            &entry_list(),
+            &positions(&[(0, 3), (1, 1), (2, 0), (3, 2), (4, 4)]),
+        );
+
+        let slots: Vec<(u16, i32, i32)> = grid
+            .slots
+            .iter()
+            .map(|slot| (slot.car_index, slot.position, slot.class_position))
+            .collect();
+        // Car 2 has no position yet.
+        assert_eq!(slots, vec![(1, 1, 1), (3, 2, 2), (0, 3, 1), (4, 4, 2)]);
+        assert_eq!(grid.slot(0).unwrap().car_class, CarClass::GT3);
+        assert_eq!(grid.slot(0).unwrap().race_number, 100);
+        assert!(grid.slot(2).is_none());
+        assert_eq!(
+            grid.start_positions(),
+            positions(&[(0, 3), (1, 1), (3, 2), (4, 4)])
+        );
+    }
+
+    #[test]
+    fn class_positions_count_only_the_grid_cars() {
+        let grid = ACCDGrid::new(
+            session(RaceSessionType::Race),
+            &entry_list(),
+            &positions(&[(0, 1), (1, 2), (2, 3), (3, 4)]),
+        );
+
+        // Car 4 joined late and is ahead of everyone.
+        let changes = grid.position_changes(&positions(&[(4, 1), (2, 2), (0, 3), (3, 4), (1, 0)]));
+        let changes: Vec<(u16, i32, i32, i32, i32)> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.car_index,
+                    change.position,
+                    change.gained,
+                    change.class_position,
+                    change.class_gained,
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            vec![(2, 2, 1, 1, 1), (0, 3, -2, 2, -1), (3, 4, 0, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn grid_is_captured_once_at_the_start() {
+        let mut tracker = ACCDGridTracker::new();
+        let update = |tracker: &mut ACCDGridTracker, phase| {
+            tracker.realtime_update(
+                &realtime_update(RaceSessionType::Race, phase),
+                &entry_list(),
+            )
+        };
+
+        // Joined after the start: there was no formation lap to take the grid from.
+        assert!(update(&mut tracker, SessionPhase::Session).is_empty());
+
+        tracker.reset();
+        assert!(update(&mut tracker, SessionPhase::PreFormation).is_empty());
+        tracker.car_update(&car_update(0, 2, 0));
+        assert!(update(&mut tracker, SessionPhase::FormationLap).is_empty());
+        tracker.car_update(&car_update(1, 1, 0));
+
+        let events = update(&mut tracker, SessionPhase::Session);
+        match events.as_slice() {
+            [ACCDGridEvent::GridCaptured(grid)] => {
+                assert_eq!(grid.start_positions(), positions(&[(0, 2), (1, 1)]));
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        assert!(tracker.grid().is_some());
+
+        // Positions changing after the start don't change the grid.
+        tracker.car_update(&car_update(0, 1, 0));
+        assert!(update(&mut tracker, SessionPhase::Session).is_empty());
+        assert!(update(&mut tracker, SessionPhase::FormationLap).is_empty());
+        assert!(update(&mut tracker, SessionPhase::Session).is_empty());
+        assert_eq!(tracker.grid().unwrap().slot(0).unwrap().position, 2);
+    }
+
+    #[test]
+    fn no_grid_outside_races() {
+        let mut tracker = ACCDGridTracker::new();
+        for phase in [SessionPhase::FormationLap, SessionPhase::Session].iter() {
+            let update = realtime_update(RaceSessionType::Qualifying, phase.clone());
+            assert!(tracker.realtime_update(&update, &entry_list()).is_empty());
+        }
+        assert!(tracker.grid().is_none());
+        assert!(tracker.car_update(&car_update(0, 1, 0)).is_empty());
+        assert!(tracker.car_update(&car_update(0, 5, 1)).is_empty());
+    }
+
+    #[test]
+    fn first_lap_movers() {
+        let mut tracker = tracker_after_the_start();
+
+        // Car 4 goes from 5th to 2nd and car 1 from 2nd to 5th, car 0 drops to 3rd.
+        for (car_index, position) in [(4, 2), (1, 5), (0, 3), (2, 1), (3, 4)].iter() {
+            tracker.car_update(&car_update(*car_index, *position, 0));
+        }
+
+        let events = tracker.car_update(&car_update(4, 2, 1));
+        match events.as_slice() {
+            [ACCDGridEvent::FirstLapMover(change)] => {
+                assert_eq!((change.car_index, change.gained), (4, 3));
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        // Reported once.
+        assert!(tracker.car_update(&car_update(4, 2, 1)).is_empty());
+
+        // Lost 3 positions, exactly the threshold.
+        assert_eq!(tracker.car_update(&car_update(1, 5, 1)).len(), 1);
+        // Lost 2 positions, 1 in its class.
+        assert!(tracker.car_update(&car_update(0, 3, 1)).is_empty());
+        // Only the 0 -> 1 lap change counts.
+        assert!(tracker.car_update(&car_update(2, 1, 2)).is_empty());
+        assert!(tracker.car_update(&car_update(2, 1, 3)).is_empty());
+    }
+
+    #[test]
+    fn first_lap_movers_in_their_class() {
+        let mut tracker = tracker_after_the_start();
+        tracker.first_lap_mover_threshold = 2;
+
+        // Car 3 stays 4th overall but passes car 1, the other GT4, 1 class position.
+        tracker.car_update(&car_update(1, 5, 0));
+        tracker.car_update(&car_update(4, 2, 0));
+        assert!(tracker.car_update(&car_update(3, 4, 1)).is_empty());
+        // Car 1 lost 3 overall, 1 in class.
+        assert_eq!(tracker.car_update(&car_update(1, 5, 1)).len(), 1);
+
+        let mut tracker = tracker_after_the_start();
+        tracker.first_lap_mover_threshold = 1;
+        tracker.car_update(&car_update(1, 5, 0));
+        match tracker.car_update(&car_update(3, 4, 1)).as_slice() {
+            [ACCDGridEvent::FirstLapMover(change)] => {
+                assert_eq!((change.gained, change.class_gained), (0, 1));
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+    }
+}
@@ -1,9 +1,21 @@
 use std::fmt;
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 use byteorder::{NativeEndian, ReadBytesExt};
 
+use crate::accd_session_time::SessionTime;
+
+/// ACC sends `i32::MAX` for laps and splits that are not done.
+fn lap_time(millis: i32) -> Option<i32> {
+    if millis == i32::MAX {
+        None
+    } else {
+        Some(millis)
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LapType {
     Error = 0,
     Outlap = 1,
@@ -23,9 +35,11 @@ impl From<u8> for LapType {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDLapInfo {
-    pub lap_time_ms: i32, //maybe use Option
-    pub splits: Vec<i32>, //maybe use Vec<Option>
+    /// `None` while the lap is not done.
+    pub lap_time_ms: Option<i32>,
+    pub splits: Vec<Option<i32>>,
     pub car_index: u16,
     pub driver_index: u16,
     pub is_invalid: bool,
@@ -34,41 +48,25 @@ pub struct ACCDLapInfo {
 }
 
 impl ACCDLapInfo {
-    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> ACCDLapInfo {
-        let lap_time_ms = cur.read_i32::<NativeEndian>().unwrap();
+    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> io::Result<ACCDLapInfo> {
+        let lap_time_ms = lap_time(cur.read_i32::<NativeEndian>()?);
 
-        let car_index = cur.read_u16::<NativeEndian>().unwrap();
-        let driver_index = cur.read_u16::<NativeEndian>().unwrap();
+        let car_index = cur.read_u16::<NativeEndian>()?;
+        let driver_index = cur.read_u16::<NativeEndian>()?;
 
-        let split_count = cur.read_u8().unwrap();
-        let mut splits: Vec<i32> = Vec::new();
+        let split_count = cur.read_u8()?;
+        let mut splits: Vec<Option<i32>> = Vec::new();
         for _i in 0..split_count {
-            splits.push(cur.read_i32::<NativeEndian>().unwrap());
+            splits.push(lap_time(cur.read_i32::<NativeEndian>()?));
         }
 
-        let is_invalid = if cur.read_u8().unwrap() > 0 {
-            true
-        } else {
-            false
-        };
+        let is_invalid = if cur.read_u8()? > 0 { true } else { false };
 
-        let is_valid_for_best = if cur.read_u8().unwrap() > 0 {
-            true
-        } else {
-            false
-        };
+        let is_valid_for_best = if cur.read_u8()? > 0 { true } else { false };
 
-        let is_out_lap = if cur.read_u8().unwrap() > 0 {
-            true
-        } else {
-            false
-        };
+        let is_out_lap = if cur.read_u8()? > 0 { true } else { false };
 
-        let is_in_lap = if cur.read_u8().unwrap() > 0 {
-            true
-        } else {
-            false
-        };
+        let is_in_lap = if cur.read_u8()? > 0 { true } else { false };
 
         let lap_type: LapType;
         if is_out_lap {
@@ -79,7 +77,7 @@ impl ACCDLapInfo {
             lap_type = LapType::Regular;
         };
 
-        ACCDLapInfo {
+        Ok(ACCDLapInfo {
             lap_time_ms,
             splits,
             car_index,
@@ -87,16 +85,21 @@ impl ACCDLapInfo {
             is_invalid,
             is_valid_for_best,
             lap_type,
-        }
+        })
+    }
+
+    pub fn lap_time(&self) -> Option<SessionTime> {
+        self.lap_time_ms
+            .map(|millis| SessionTime::from_millis(millis as f64))
     }
 
     fn to_string(&self) -> String {
-        let v = self
-            .splits
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>();
-        let s = self.lap_time_ms.to_string() + &v.join("|");
+        let fmt_time = |time: &Option<i32>| match time {
+            Some(millis) => SessionTime::from_millis(*millis as f64).to_string(),
+            None => "-".to_string(),
+        };
+        let v = self.splits.iter().map(fmt_time).collect::<Vec<String>>();
+        let s = fmt_time(&self.lap_time_ms) + " | " + &v.join("|");
         s
     }
 }
@@ -104,7 +107,7 @@ impl ACCDLapInfo {
 impl Default for ACCDLapInfo {
     fn default() -> Self {
         ACCDLapInfo {
-            lap_time_ms: 0,
+            lap_time_ms: None,
             splits: Vec::new(),
             car_index: 0,
             driver_index: 0,
@@ -0,0 +1,255 @@
+use crate::accd_lap_info::{ACCDLapInfo, LapType};
+use crate::accd_session_time::SessionTime;
+
+/// Laps of the rolling average when none is asked for.
+pub const DEFAULT_ROLLING_LAPS: usize = 5;
+/// Pace laps needed for a trend, fewer give a line through noise.
+const MIN_TREND_LAPS: usize = 3;
+
+/// Pace of a car over a stint, from its valid regular laps.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDStintPace {
+    /// Laps of the stint, in and out laps included.
+    pub laps: usize,
+    /// Laps the pace is measured on.
+    pub pace_laps: usize,
+    pub best: Option<SessionTime>,
+    pub average: Option<SessionTime>,
+    /// Average of the last pace laps.
+    pub rolling_average: Option<SessionTime>,
+    /// Time lost every lap according to a linear fit of the pace laps,
+    /// mostly tyre wear and fuel burn. Negative when the car gets faster.
+    pub trend: Option<SessionTime>,
+    /// Standard deviation of the pace laps, lower is more consistent.
+    pub consistency: Option<SessionTime>,
+}
+
+impl ACCDStintPace {
+    /// Pace of the laps of a stint, oldest first.
+    pub fn new(laps: &[ACCDLapInfo], rolling_laps: usize) -> Self {
+        // Lap of the stint and lap time, the trend skips the laps left out.
+        let pace_laps: Vec<(f64, f64)> = laps
+            .iter()
+            .enumerate()
+            .filter(|(_, lap)| is_pace_lap(lap))
+            .filter_map(|(i, lap)| Some((i as f64, lap.lap_time_ms? as f64)))
+            .collect();
+        let times: Vec<f64> = pace_laps.iter().map(|(_, time)| *time).collect();
+        let rolling = &times[times.len().saturating_sub(rolling_laps.max(1))..];
+
+        ACCDStintPace {
+            laps: laps.len(),
+            pace_laps: times.len(),
+            best: times
+                .iter()
+                .copied()
+                .reduce(f64::min)
+                .map(SessionTime::from_millis),
+            average: mean(&times).map(SessionTime::from_millis),
+            rolling_average: mean(rolling).map(SessionTime::from_millis),
+            trend: slope(&pace_laps).map(SessionTime::from_millis),
+            consistency: std_dev(&times).map(SessionTime::from_millis),
+        }
+    }
+}
+
+/// Pace of a car through the session, a stint ends with an in lap.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDCarPace {
+    pub car_index: u16,
+    /// Pit stops seen, stops before the client connected are missing.
+    pub pit_stops: usize,
+    /// Oldest first, the last one is the current stint.
+    pub stints: Vec<ACCDStintPace>,
+}
+
+impl ACCDCarPace {
+    /// Pace of a car from its completed laps, oldest first.
+    pub fn new(car_index: u16, laps: &[ACCDLapInfo], rolling_laps: usize) -> Self {
+        let mut stints = Vec::new();
+        let mut stint_start = 0;
+        for (i, lap) in laps.iter().enumerate() {
+            if matches!(lap.lap_type, LapType::Inlap) {
+                stints.push(ACCDStintPace::new(&laps[stint_start..=i], rolling_laps));
+                stint_start = i + 1;
+            }
+        }
+        let pit_stops = stints.len();
+        // The car is out of the pits, on a stint without laps yet.
+        stints.push(ACCDStintPace::new(&laps[stint_start..], rolling_laps));
+
+        ACCDCarPace {
+            car_index,
+            pit_stops,
+            stints,
+        }
+    }
+
+    pub fn current_stint(&self) -> &ACCDStintPace {
+        // `new` always adds the current stint.
+        self.stints.last().unwrap()
+    }
+}
+
+/// Current stint of a car against the one of another car.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDPaceComparison {
+    pub car_index: u16,
+    pub other_car_index: u16,
+    /// Rolling average of the car minus the one of the other car, negative when faster.
+    pub rolling_delta: Option<SessionTime>,
+    /// Trend of the car minus the one of the other car, negative when it degrades less.
+    pub trend_delta: Option<SessionTime>,
+    /// Laps of the current stint of the car minus the ones of the other car,
+    /// positive when its tyres are older.
+    pub stint_laps_delta: i32,
+}
+
+impl ACCDPaceComparison {
+    pub fn new(pace: &ACCDCarPace, other: &ACCDCarPace) -> Self {
+        let (stint, other_stint) = (pace.current_stint(), other.current_stint());
+        let delta = |a: Option<SessionTime>, b: Option<SessionTime>| Some(a? - b?);
+        ACCDPaceComparison {
+            car_index: pace.car_index,
+            other_car_index: other.car_index,
+            rolling_delta: delta(stint.rolling_average, other_stint.rolling_average),
+            trend_delta: delta(stint.trend, other_stint.trend),
+            stint_laps_delta: stint.laps as i32 - other_stint.laps as i32,
+        }
+    }
+}
+
+/// Laps driven at racing pace: valid, timed and neither in nor out of the pits.
+pub fn is_pace_lap(lap: &ACCDLapInfo) -> bool {
+    matches!(lap.lap_type, LapType::Regular) && !lap.is_invalid && lap.lap_time_ms.is_some()
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn std_dev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    let variance =
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    Some(variance.sqrt())
+}
+
+/// Slope of the least squares line through the points.
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < MIN_TREND_LAPS {
+        return None;
+    }
+    let xs: Vec<f64> = points.iter().map(|(x, _)| *x).collect();
+    let ys: Vec<f64> = points.iter().map(|(_, y)| *y).collect();
+    let (mean_x, mean_y) = (mean(&xs)?, mean(&ys)?);
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance += (x - mean_x).powi(2);
+    }
+    Some(covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lap(lap_type: LapType, lap_time_ms: i32) -> ACCDLapInfo {
+        ACCDLapInfo {
+            lap_time_ms: Some(lap_time_ms),
+            lap_type,
+            ..ACCDLapInfo::default()
+        }
+    }
+
+    fn invalid(lap_time_ms: i32) -> ACCDLapInfo {
+        ACCDLapInfo {
+            is_invalid: true,
+            ..lap(LapType::Regular, lap_time_ms)
+        }
+    }
+
+    /// Half a second lost every lap, the out lap and an invalid lap left out.
+    fn stint() -> Vec<ACCDLapInfo> {
+        vec![
+            lap(LapType::Outlap, 95_000),
+            lap(LapType::Regular, 90_000),
+            lap(LapType::Regular, 90_500),
+            invalid(80_000),
+            lap(LapType::Regular, 91_500),
+        ]
+    }
+
+    fn millis(time: Option<SessionTime>) -> f64 {
+        time.unwrap().as_millis()
+    }
+
+    #[test]
+    fn trend_follows_the_lap_of_the_stint() {
+        let pace = ACCDStintPace::new(&stint(), 2);
+
+        assert_eq!(pace.laps, 5);
+        assert_eq!(pace.pace_laps, 3);
+        assert_eq!(pace.best, Some(SessionTime::from_millis(90_000.0)));
+        assert!((millis(pace.trend) - 500.0).abs() < 1e-6);
+        assert!((millis(pace.average) - 90_666.667).abs() < 0.001);
+        assert_eq!(
+            pace.rolling_average,
+            Some(SessionTime::from_millis(91_000.0))
+        );
+    }
+
+    #[test]
+    fn consistency_is_the_deviation_of_the_pace_laps() {
+        let pace = ACCDStintPace::new(&stint(), DEFAULT_ROLLING_LAPS);
+        assert!((millis(pace.consistency) - 763.763).abs() < 0.001);
+
+        let steady = vec![lap(LapType::Regular, 90_000); 4];
+        let pace = ACCDStintPace::new(&steady, DEFAULT_ROLLING_LAPS);
+        assert_eq!(pace.consistency, Some(SessionTime::ZERO));
+        assert_eq!(pace.trend, Some(SessionTime::ZERO));
+    }
+
+    #[test]
+    fn short_stints_have_no_trend() {
+        let pace = ACCDStintPace::new(&stint()[..3], DEFAULT_ROLLING_LAPS);
+        assert_eq!(pace.pace_laps, 2);
+        assert_eq!(pace.trend, None);
+        assert!(pace.consistency.is_some());
+
+        let pace = ACCDStintPace::new(&[], DEFAULT_ROLLING_LAPS);
+        assert_eq!(
+            (pace.best, pace.average, pace.consistency),
+            (None, None, None)
+        );
+    }
+
+    #[test]
+    fn stints_end_with_an_in_lap() {
+        let mut laps = stint();
+        laps.push(lap(LapType::Inlap, 100_000));
+        laps.extend(stint());
+
+        let pace = ACCDCarPace::new(7, &laps, DEFAULT_ROLLING_LAPS);
+        assert_eq!(pace.pit_stops, 1);
+        assert_eq!(pace.stints.len(), 2);
+        assert_eq!(pace.stints[0].laps, 6);
+        assert_eq!(pace.current_stint().laps, 5);
+
+        let other = ACCDCarPace::new(8, &stint()[..4], DEFAULT_ROLLING_LAPS);
+        let comparison = ACCDPaceComparison::new(&pace, &other);
+        assert_eq!(comparison.stint_laps_delta, 1);
+        assert_eq!(comparison.trend_delta, None);
+        assert!(millis(comparison.rolling_delta) > 0.0);
+    }
+}
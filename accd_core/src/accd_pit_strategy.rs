@@ -0,0 +1,485 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::accd_car_info::ACCDCarInfo;
+use crate::accd_lap_info::LapType;
+use crate::accd_pace::is_pace_lap;
+use crate::accd_realtime_car_update::{ACCDRealtimeCarUpdate, CarLocationEnum};
+use crate::accd_realtime_update::{ACCDRealtimeUpdate, RaceSessionType};
+use crate::accd_session_time::SessionTime;
+use crate::accd_session_tracker::ACCDSessionInfo;
+
+/// Pace laps before a stop the pit loss is measured against.
+const REFERENCE_LAPS: usize = 5;
+
+/// A visit to the pit lane.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDPitStop {
+    /// Completed laps when the car entered the pit lane.
+    pub lap: i32,
+    pub entry_time: SessionTime,
+    pub exit_time: SessionTime,
+}
+
+impl ACCDPitStop {
+    /// Time spent in the pit lane.
+    pub fn pit_lane_time(&self) -> SessionTime {
+        self.exit_time - self.entry_time
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDPitCycleResult {
+    /// The car stopped first and came out ahead of a car that was ahead.
+    Undercut,
+    /// The car stopped later and came out ahead of a car that was ahead.
+    Overcut,
+}
+
+/// Two cars running next to each other swapped places while both of them stopped.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDPitCycle {
+    pub result: ACCDPitCycleResult,
+    /// The car that gained the place.
+    pub car_index: u16,
+    pub other_car_index: u16,
+    /// Positions of the car before its stop and once both stopped.
+    pub position_before: i32,
+    pub position_after: i32,
+}
+
+/// Pit lane activity reported by `ACCDPitTracker`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDPitEvent {
+    PitEntry {
+        car_index: u16,
+        lap: i32,
+    },
+    PitExit {
+        car_index: u16,
+        stop: ACCDPitStop,
+    },
+    /// The out lap after a stop was completed, in races only: `loss` is the time the in
+    /// and out laps took more than two laps at the pace before the stop.
+    PitLoss {
+        car_index: u16,
+        loss: SessionTime,
+        /// Median of the losses seen on the track.
+        track_pit_loss: SessionTime,
+    },
+    PitCycle(ACCDPitCycle),
+}
+
+/// Strategy of a car: its stops and where it would rejoin if it stopped now.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDCarStrategy {
+    pub car_index: u16,
+    pub race_number: i32,
+    pub position: i32,
+    pub stops: Vec<ACCDPitStop>,
+    /// Laps since the last stop, or since the start without one.
+    pub laps_since_stop: i32,
+    pub in_pit_lane: bool,
+    /// Position the car would rejoin in if it stopped now, races only and once
+    /// the pit loss of the track is known.
+    pub rejoin_position: Option<i32>,
+    /// Gap to the car it would rejoin behind.
+    pub rejoin_gap_ahead: Option<SessionTime>,
+}
+
+/// A car stopped first, waiting for the car next to it to stop too.
+#[derive(Debug, Clone)]
+struct OpenCycle {
+    first: u16,
+    second: u16,
+    first_was_ahead: bool,
+    first_position: i32,
+    second_position: i32,
+    /// Laps of the first car when it stopped.
+    lap: i32,
+    /// The second car is in the pit lane.
+    second_stopping: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CarPit {
+    laps: Option<i32>,
+    position: i32,
+    in_pit_lane: bool,
+    entry: Option<(i32, SessionTime)>,
+    /// Latest pace laps, oldest first.
+    pace_laps: VecDeque<f64>,
+    /// Reference pace and in lap time of the last stop, until its out lap is completed.
+    in_lap: Option<(f64, f64)>,
+}
+
+/// Follows the cars in and out of the pit lane, learns how much time a stop costs on
+/// the track and reports the undercuts and overcuts of the pit cycles.
+#[derive(Debug, Clone)]
+pub struct ACCDPitTracker {
+    /// Laps the second car of a pit cycle has to stop within, after the first one.
+    pub cycle_laps: i32,
+    session: Option<ACCDSessionInfo>,
+    session_time: SessionTime,
+    track_id: i32,
+    cars: HashMap<u16, CarPit>,
+    cycles: Vec<OpenCycle>,
+    /// Pit losses of the races on the current track, kept from one session to the next.
+    losses: Vec<SessionTime>,
+}
+
+impl Default for ACCDPitTracker {
+    fn default() -> Self {
+        ACCDPitTracker {
+            cycle_laps: 5,
+            session: None,
+            session_time: SessionTime::ZERO,
+            track_id: -1,
+            cars: HashMap::new(),
+            cycles: Vec::new(),
+            losses: Vec::new(),
+        }
+    }
+}
+
+impl ACCDPitTracker {
+    pub fn new() -> Self {
+        ACCDPitTracker::default()
+    }
+
+    /// Median of the pit losses seen on the track, `None` before the first out lap.
+    pub fn pit_loss(&self) -> Option<SessionTime> {
+        median(&self.losses)
+    }
+
+    /// Pit losses are learned again on another track.
+    pub fn track_data(&mut self, track_id: i32) {
+        if track_id != self.track_id {
+            self.track_id = track_id;
+            self.losses.clear();
+        }
+    }
+
+    /// Forgets the session, e.g. after connecting to another ACC instance.
+    /// The pit losses are kept until the track changes.
+    pub fn reset(&mut self) {
+        self.session = None;
+        self.session_time = SessionTime::ZERO;
+        self.cars.clear();
+        self.cycles.clear();
+    }
+
+    pub fn realtime_update(&mut self, realtime_update: &ACCDRealtimeUpdate) {
+        let session = ACCDSessionInfo {
+            event_index: realtime_update.event_index(),
+            session_index: realtime_update.session_index(),
+            session_type: realtime_update.session_type.clone(),
+        };
+        if self.session.as_ref() != Some(&session) {
+            self.reset();
+            self.session = Some(session);
+        }
+        self.session_time = realtime_update.session_time;
+    }
+
+    pub fn car_update(
+        &mut self,
+        car_update: &ACCDRealtimeCarUpdate,
+        entry_list_cars: &[ACCDCarInfo],
+    ) -> Vec<ACCDPitEvent> {
+        let mut events = Vec::new();
+        let car_index = car_update.car_index as u16;
+        let session_time = self.session_time;
+        // Stops outside races are no reference: setup changes, long stays in the box.
+        let race = self
+            .session
+            .as_ref()
+            .is_some_and(|session| session.session_type == RaceSessionType::Race);
+
+        let car = self.cars.entry(car_index).or_default();
+        let lap_completed = car.laps.is_some_and(|laps| car_update.laps > laps);
+        car.laps = Some(car_update.laps);
+        car.position = car_update.position;
+
+        if lap_completed {
+            let lap = &car_update.last_lap;
+            let lap_time = lap.lap_time_ms.map(|lap_time_ms| lap_time_ms as f64);
+            match (&lap.lap_type, lap_time) {
+                (LapType::Inlap, Some(lap_time)) if !car.pace_laps.is_empty() => {
+                    let reference = car.pace_laps.iter().sum::<f64>() / car.pace_laps.len() as f64;
+                    car.in_lap = Some((reference, lap_time));
+                }
+                (LapType::Outlap, Some(lap_time)) => {
+                    if let (Some((reference, in_lap)), true) = (car.in_lap.take(), race) {
+                        let loss = SessionTime::from_millis(in_lap + lap_time - 2.0 * reference);
+                        self.losses.push(loss);
+                        events.push(ACCDPitEvent::PitLoss {
+                            car_index,
+                            loss,
+                            track_pit_loss: median(&self.losses).unwrap_or(loss),
+                        });
+                    }
+                    // Pace before the next stop.
+                    car.pace_laps.clear();
+                }
+                _ if is_pace_lap(lap) => {
+                    car.pace_laps.extend(lap_time);
+                    if car.pace_laps.len() > REFERENCE_LAPS {
+                        car.pace_laps.pop_front();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let car = self.cars.entry(car_index).or_default();
+        let in_pit_lane = matches!(
+            car_update.car_location,
+            CarLocationEnum::PitEntry | CarLocationEnum::Pitlane | CarLocationEnum::PitExit
+        );
+        let entered = in_pit_lane && !car.in_pit_lane;
+        let exited = !in_pit_lane && car.in_pit_lane;
+        car.in_pit_lane = in_pit_lane;
+        // Cars already in the pit lane when the client connected have no entry.
+        let entry = if exited { car.entry.take() } else { None };
+        if entered {
+            car.entry = Some((car_update.laps, session_time));
+        }
+
+        if entered {
+            events.push(ACCDPitEvent::PitEntry {
+                car_index,
+                lap: car_update.laps,
+            });
+            self.open_cycles(car_index, car_update, entry_list_cars);
+        }
+        if exited {
+            if let Some((lap, entry_time)) = entry {
+                events.push(ACCDPitEvent::PitExit {
+                    car_index,
+                    stop: ACCDPitStop {
+                        lap,
+                        entry_time,
+                        exit_time: session_time,
+                    },
+                });
+            }
+            events.extend(
+                self.close_cycles(car_index)
+                    .into_iter()
+                    .map(ACCDPitEvent::PitCycle),
+            );
+        }
+
+        events
+    }
+
+    /// A car stopped: it's the second car of the cycles it's waiting for,
+    /// and the first one of new cycles with the cars of its class next to it.
+    fn open_cycles(
+        &mut self,
+        car_index: u16,
+        car_update: &ACCDRealtimeCarUpdate,
+        entry_list_cars: &[ACCDCarInfo],
+    ) {
+        let laps = car_update.laps;
+        let cycle_laps = self.cycle_laps;
+        // Cycles whose second car never stopped, or whose first car stops again.
+        self.cycles
+            .retain(|cycle| cycle.first != car_index && laps - cycle.lap <= cycle_laps);
+        for cycle in self
+            .cycles
+            .iter_mut()
+            .filter(|cycle| cycle.second == car_index)
+        {
+            cycle.second_stopping = true;
+        }
+
+        let car_class = entry_list_cars
+            .iter()
+            .find(|car_info| car_info.car_index == car_index)
+            .map(|car_info| car_info.car_class());
+        let position = car_update.position;
+        let mut class_cars: Vec<(u16, i32)> = entry_list_cars
+            .iter()
+            .filter(|car_info| Some(car_info.car_class()) == car_class)
+            .filter_map(|car_info| {
+                let car = self.cars.get(&car_info.car_index)?;
+                Some((car_info.car_index, car.position)).filter(|_| car.position > 0)
+            })
+            .collect();
+        class_cars.sort_by_key(|(_, position)| *position);
+
+        let i = match class_cars.iter().position(|(index, _)| *index == car_index) {
+            Some(i) => i,
+            None => return,
+        };
+        let neighbours = [i.checked_sub(1), Some(i + 1)];
+        for (other, other_position) in neighbours
+            .iter()
+            .flatten()
+            .filter_map(|j| class_cars.get(*j).copied())
+        {
+            let in_cycle = self.cycles.iter().any(|cycle| {
+                (cycle.first, cycle.second) == (other, car_index)
+                    || (cycle.first, cycle.second) == (car_index, other)
+            });
+            if in_cycle {
+                continue;
+            }
+            self.cycles.push(OpenCycle {
+                first: car_index,
+                second: other,
+                first_was_ahead: position < other_position,
+                first_position: position,
+                second_position: other_position,
+                lap: laps,
+                second_stopping: false,
+            });
+        }
+    }
+
+    /// A car left the pit lane: the cycles it was the second car of are over.
+    fn close_cycles(&mut self, car_index: u16) -> Vec<ACCDPitCycle> {
+        let (closed, open): (Vec<OpenCycle>, Vec<OpenCycle>) = self
+            .cycles
+            .drain(..)
+            .partition(|cycle| cycle.second == car_index && cycle.second_stopping);
+        self.cycles = open;
+
+        let position = |car_index: u16| self.cars.get(&car_index).map_or(0, |car| car.position);
+        closed
+            .into_iter()
+            .filter_map(|cycle| {
+                let (first_position, second_position) =
+                    (position(cycle.first), position(cycle.second));
+                if first_position <= 0 || second_position <= 0 {
+                    return None;
+                }
+                let first_ahead = first_position < second_position;
+                match (cycle.first_was_ahead, first_ahead) {
+                    (false, true) => Some(ACCDPitCycle {
+                        result: ACCDPitCycleResult::Undercut,
+                        car_index: cycle.first,
+                        other_car_index: cycle.second,
+                        position_before: cycle.first_position,
+                        position_after: first_position,
+                    }),
+                    (true, false) => Some(ACCDPitCycle {
+                        result: ACCDPitCycleResult::Overcut,
+                        car_index: cycle.second,
+                        other_car_index: cycle.first,
+                        position_before: cycle.second_position,
+                        position_after: second_position,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+fn median(times: &[SessionTime]) -> Option<SessionTime> {
+    let mut millis: Vec<f64> = times.iter().map(|time| time.as_millis()).collect();
+    millis.sort_by(|a, b| a.total_cmp(b));
+    let middle = millis.len() / 2;
+    match millis.len() {
+        0 => None,
+        len if len % 2 == 0 => Some(SessionTime::from_millis(
+            (millis[middle - 1] + millis[middle]) / 2.0,
+        )),
+        _ => Some(SessionTime::from_millis(millis[middle])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accd_lap_info::ACCDLapInfo;
+
+    fn session(session_type: RaceSessionType) -> ACCDRealtimeUpdate {
+        ACCDRealtimeUpdate {
+            session_type,
+            ..ACCDRealtimeUpdate::default()
+        }
+    }
+
+    fn car_update(laps: i32, lap_type: LapType, lap_time_ms: i32) -> ACCDRealtimeCarUpdate {
+        ACCDRealtimeCarUpdate {
+            car_index: 1,
+            laps,
+            car_location: CarLocationEnum::Track,
+            last_lap: ACCDLapInfo {
+                lap_time_ms: Some(lap_time_ms),
+                lap_type,
+                ..ACCDLapInfo::default()
+            },
+            ..ACCDRealtimeCarUpdate::default()
+        }
+    }
+
+    /// Two pace laps, an in lap and an out lap: 10 s and 20 s slower.
+    fn stop(tracker: &mut ACCDPitTracker) -> Vec<ACCDPitEvent> {
+        let laps = [
+            (LapType::Regular, 90_000),
+            (LapType::Regular, 90_000),
+            (LapType::Regular, 90_000),
+            (LapType::Inlap, 100_000),
+            (LapType::Outlap, 110_000),
+        ];
+        let mut events = Vec::new();
+        for (i, (lap_type, lap_time_ms)) in laps.iter().enumerate() {
+            events.extend(
+                tracker.car_update(&car_update(i as i32, lap_type.clone(), *lap_time_ms), &[]),
+            );
+        }
+        events
+    }
+
+    #[test]
+    fn pit_losses_are_learned_in_races() {
+        let mut tracker = ACCDPitTracker::new();
+        tracker.realtime_update(&session(RaceSessionType::Race));
+
+        let events = stop(&mut tracker);
+        assert_eq!(
+            events,
+            vec![ACCDPitEvent::PitLoss {
+                car_index: 1,
+                loss: SessionTime::from_secs(30.0),
+                track_pit_loss: SessionTime::from_secs(30.0),
+            }]
+        );
+        assert_eq!(tracker.pit_loss(), Some(SessionTime::from_secs(30.0)));
+    }
+
+    #[test]
+    fn pit_losses_are_not_learned_outside_races() {
+        let mut tracker = ACCDPitTracker::new();
+        tracker.realtime_update(&session(RaceSessionType::Practice));
+
+        assert!(stop(&mut tracker).is_empty());
+        assert_eq!(tracker.pit_loss(), None);
+    }
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        let secs = |values: &[f64]| -> Vec<SessionTime> {
+            values.iter().map(|s| SessionTime::from_secs(*s)).collect()
+        };
+
+        assert_eq!(median(&[]), None);
+        assert_eq!(
+            median(&secs(&[30.0, 90.0, 25.0])),
+            Some(SessionTime::from_secs(30.0))
+        );
+        assert_eq!(
+            median(&secs(&[30.0, 20.0, 90.0, 25.0])),
+            Some(SessionTime::from_secs(27.5))
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::accd_realtime_car_update::ACCDRealtimeCarUpdate;
+use crate::accd_session_time::SessionTime;
+
+/// Where a car was at a moment of the session.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDPositionSample {
+    pub session_time: SessionTime,
+    /// Completed laps.
+    pub laps: i32,
+    pub position: i32,
+}
+
+/// Positions of every car through the session: sampled when the car crosses the line
+/// and, with `sample_interval`, at regular intervals of session time.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDPositionHistory {
+    /// Session time between two timed samples of a car, `None` samples only at the line.
+    pub sample_interval: Option<SessionTime>,
+    /// Samples taken when a car completed a lap, by car index, oldest first.
+    pub lap_samples: HashMap<u16, Vec<ACCDPositionSample>>,
+    pub timed_samples: HashMap<u16, Vec<ACCDPositionSample>>,
+    /// Position of every car when it was first seen, or the grid once it's known.
+    pub start_positions: HashMap<u16, i32>,
+    positions: HashMap<u16, i32>,
+    laps: HashMap<u16, i32>,
+    session_time: SessionTime,
+}
+
+impl ACCDPositionHistory {
+    pub fn new() -> Self {
+        ACCDPositionHistory::default()
+    }
+
+    /// Session time of the car updates that follow.
+    pub fn realtime_update(&mut self, session_time: SessionTime) {
+        self.session_time = session_time;
+    }
+
+    pub fn car_update(&mut self, car_update: &ACCDRealtimeCarUpdate) {
+        let car_index = car_update.car_index as u16;
+        self.positions.insert(car_index, car_update.position);
+        if car_update.position > 0 {
+            self.start_positions
+                .entry(car_index)
+                .or_insert(car_update.position);
+        }
+
+        let previous_laps = self.laps.insert(car_index, car_update.laps);
+        if previous_laps.is_some_and(|laps| car_update.laps > laps) {
+            // The update arrives a bit after the line, the time of the new lap tells how much.
+            let since_line = car_update
+                .current_lap
+                .lap_time()
+                .filter(|time| !time.is_negative())
+                .unwrap_or(SessionTime::ZERO);
+            self.lap_samples
+                .entry(car_index)
+                .or_default()
+                .push(ACCDPositionSample {
+                    session_time: self.session_time - since_line,
+                    laps: car_update.laps,
+                    position: car_update.position,
+                });
+        }
+
+        if let Some(interval) = self.sample_interval {
+            let samples = self.timed_samples.entry(car_index).or_default();
+            let due = match samples.last() {
+                Some(last) => self.session_time - last.session_time >= interval,
+                None => true,
+            };
+            if due {
+                samples.push(ACCDPositionSample {
+                    session_time: self.session_time,
+                    laps: car_update.laps,
+                    position: car_update.position,
+                });
+            }
+        }
+    }
+
+    /// Replaces the positions the gains are counted from, e.g. with the starting grid.
+    pub fn set_start_positions(&mut self, start_positions: HashMap<u16, i32>) {
+        self.start_positions = start_positions;
+    }
+
+    /// Car indexes by position at the end of every lap: `lap_chart()[lap - 1][position - 1]`.
+    /// `None` where no car reported that position when crossing the line.
+    pub fn lap_chart(&self) -> Vec<Vec<Option<u16>>> {
+        let mut chart: Vec<Vec<Option<u16>>> = Vec::new();
+
+        for (car_index, samples) in self.lap_samples.iter() {
+            for sample in samples.iter().filter(|s| s.laps > 0 && s.position > 0) {
+                let (lap, position) = (sample.laps as usize - 1, sample.position as usize - 1);
+                if chart.len() <= lap {
+                    chart.resize(lap + 1, Vec::new());
+                }
+                let row = &mut chart[lap];
+                if row.len() <= position {
+                    row.resize(position + 1, None);
+                }
+                row[position] = Some(*car_index);
+            }
+        }
+
+        chart
+    }
+
+    /// Gap of every car to the first car that completed the lap, at the end of every lap:
+    /// `gap_chart()[lap - 1][&car_index]`.
+    pub fn gap_chart(&self) -> Vec<BTreeMap<u16, SessionTime>> {
+        let mut crossings: Vec<BTreeMap<u16, SessionTime>> = Vec::new();
+        for (car_index, samples) in self.lap_samples.iter() {
+            for sample in samples.iter().filter(|s| s.laps > 0) {
+                let lap = sample.laps as usize - 1;
+                if crossings.len() <= lap {
+                    crossings.resize(lap + 1, BTreeMap::new());
+                }
+                crossings[lap].insert(*car_index, sample.session_time);
+            }
+        }
+
+        crossings
+            .into_iter()
+            .map(|lap| {
+                let first = lap
+                    .values()
+                    .copied()
+                    .reduce(|a, b| if b < a { b } else { a });
+                lap.iter()
+                    .map(|(car_index, time)| (*car_index, *time - first.unwrap_or(*time)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Positions gained since the start (lost when negative), by car index.
+    pub fn gains(&self) -> HashMap<u16, i32> {
+        self.positions
+            .iter()
+            .filter(|(_, position)| **position > 0)
+            .filter_map(|(car_index, position)| {
+                let start = self.start_positions.get(car_index)?;
+                Some((*car_index, start - position))
+            })
+            .collect()
+    }
+
+    /// Forgets everything but `sample_interval`, e.g. when a new session starts.
+    pub fn reset(&mut self) {
+        *self = ACCDPositionHistory {
+            sample_interval: self.sample_interval,
+            ..ACCDPositionHistory::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accd_lap_info::ACCDLapInfo;
+
+    fn car_update(
+        car_index: i32,
+        laps: i32,
+        position: i32,
+        since_line_ms: i32,
+    ) -> ACCDRealtimeCarUpdate {
+        ACCDRealtimeCarUpdate {
+            car_index,
+            laps,
+            position,
+            current_lap: ACCDLapInfo {
+                lap_time_ms: Some(since_line_ms),
+                ..ACCDLapInfo::default()
+            },
+            ..ACCDRealtimeCarUpdate::default()
+        }
+    }
+
+    /// Car 1 leads the first lap, car 2 passes it on the second one.
+    fn two_laps() -> ACCDPositionHistory {
+        let mut history = ACCDPositionHistory::new();
+        history.realtime_update(SessionTime::from_secs(10.0));
+        history.car_update(&car_update(1, 0, 1, 10_000));
+        history.car_update(&car_update(2, 0, 2, 9_000));
+
+        history.realtime_update(SessionTime::from_secs(100.5));
+        history.car_update(&car_update(1, 1, 1, 500));
+        history.realtime_update(SessionTime::from_secs(102.0));
+        history.car_update(&car_update(2, 1, 2, 0));
+
+        history.realtime_update(SessionTime::from_secs(191.0));
+        history.car_update(&car_update(2, 2, 1, 1_000));
+        history.realtime_update(SessionTime::from_secs(191.5));
+        history.car_update(&car_update(1, 2, 2, 0));
+        history
+    }
+
+    #[test]
+    fn lap_chart_has_the_positions_at_the_line() {
+        let history = two_laps();
+        assert_eq!(
+            history.lap_chart(),
+            vec![vec![Some(1), Some(2)], vec![Some(2), Some(1)]]
+        );
+        // Taken when the car crossed the line, not when the update arrived.
+        assert_eq!(
+            history.lap_samples[&1][0].session_time,
+            SessionTime::from_secs(100.0)
+        );
+    }
+
+    #[test]
+    fn gap_chart_counts_from_the_first_car_at_the_line() {
+        let gaps = two_laps().gap_chart();
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0][&1], SessionTime::ZERO);
+        assert_eq!(gaps[0][&2], SessionTime::from_secs(2.0));
+        assert_eq!(gaps[1][&2], SessionTime::ZERO);
+        assert_eq!(gaps[1][&1], SessionTime::from_secs(1.5));
+    }
+
+    #[test]
+    fn gains_count_from_the_start_positions() {
+        let mut history = two_laps();
+        assert_eq!(history.gains()[&2], 1);
+        assert_eq!(history.gains()[&1], -1);
+
+        history.set_start_positions(vec![(1, 2), (2, 2)].into_iter().collect());
+        assert_eq!(history.gains()[&1], 0);
+    }
+
+    #[test]
+    fn timed_samples_follow_the_interval() {
+        let mut history = ACCDPositionHistory {
+            sample_interval: Some(SessionTime::from_secs(10.0)),
+            ..ACCDPositionHistory::default()
+        };
+        for secs in 0..25 {
+            history.realtime_update(SessionTime::from_secs(secs as f64));
+            history.car_update(&car_update(1, 0, 1, 0));
+        }
+        let times: Vec<f64> = history.timed_samples[&1]
+            .iter()
+            .map(|sample| sample.session_time.as_secs())
+            .collect();
+        assert_eq!(times, vec![0.0, 10.0, 20.0]);
+
+        history.reset();
+        assert!(history.timed_samples.is_empty());
+        assert_eq!(history.sample_interval, Some(SessionTime::from_secs(10.0)));
+    }
+}
@@ -1,22 +1,40 @@
 use std::cell::RefCell;
-use std::io::Cursor;
+use std::collections::VecDeque;
+use std::io::{self, Cursor, ErrorKind};
+use std::mem;
 use std::net::UdpSocket;
-use std::time::Instant;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use byteorder::{NativeEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::accd_broadcasting_event::ACCDBroadcastingEvent;
 use crate::accd_car_info::ACCDCarInfo;
+use crate::accd_conditions::{ACCDConditionEvent, ACCDConditionTracker};
 use crate::accd_config::ACCDConfig;
 use crate::accd_driver_info::ACCDDriverInfo;
+use crate::accd_entry_list_sync::{
+    ACCDEntryListSync, ACCDEntryListSyncEvent, ACCDEntryListSyncOutput,
+};
 use crate::accd_enums::NationalityEnum;
+use crate::accd_grid::{ACCDGridEvent, ACCDGridTracker};
+use crate::accd_pit_strategy::{ACCDPitEvent, ACCDPitTracker};
 use crate::accd_realtime_car_update::ACCDRealtimeCarUpdate;
 use crate::accd_realtime_update::ACCDRealtimeUpdate;
+use crate::accd_recording::ACCDRecorder;
 use crate::accd_registration_result::ACCDRegistrationResult;
+use crate::accd_session_tracker::{ACCDSessionEvent, ACCDSessionTracker};
+use crate::accd_speed_trap::{ACCDSpeedEvent, ACCDSpeedTrapTracker};
 use crate::accd_track_data::ACCDTrackData;
 
 use crate::accd_utils::{read_string, write_string};
 
+/// How long `listen_step` waits for a datagram, so that a listener thread
+/// doesn't keep the protocol locked forever when ACC stops sending.
+const LISTEN_TIMEOUT: Duration = Duration::from_millis(500);
+
 #[derive(Debug)]
 enum InboundMessageTypes {
     RegistrationResult = 1,
@@ -75,6 +93,7 @@ impl From<u8> for OutboundMessageTypes {
     }
 }
 
+#[derive(Debug, Clone)]
 pub enum ListenResult {
     RegistrationResult(ACCDRegistrationResult),
     RealTimeUpdate(ACCDRealtimeUpdate),
@@ -83,6 +102,17 @@ pub enum ListenResult {
     TrackData(ACCDTrackData),
     EntryListCar(ACCDCarInfo),
     BroadcastingEvent(ACCDBroadcastingEvent),
+    EntryListSync(ACCDEntryListSyncEvent),
+    /// Reported before the realtime update that caused it.
+    Session(ACCDSessionEvent),
+    /// Reported before the realtime update or after the car update that caused it.
+    Grid(ACCDGridEvent),
+    /// Reported after the car update that caused it.
+    Pit(ACCDPitEvent),
+    /// Reported before the realtime update that caused it.
+    Conditions(ACCDConditionEvent),
+    /// Reported after the car update that caused it.
+    SpeedTrap(ACCDSpeedEvent),
     Error,
 }
 
@@ -96,7 +126,21 @@ pub struct ACCDProtocol {
 
     pub(crate) entry_list_cars: Vec<ACCDCarInfo>,
 
-    last_entry_list_request: Instant,
+    /// Keeps car updates and entry list consistent, its throttle windows can be changed.
+    pub entry_list_sync: ACCDEntryListSync,
+    session_tracker: ACCDSessionTracker,
+    /// Captures the grid of races, its first lap mover threshold can be changed.
+    pub grid_tracker: ACCDGridTracker,
+    /// Follows the pit stops, its pit cycle window can be changed.
+    pub pit_tracker: ACCDPitTracker,
+    /// Reports weather and track changes, its thresholds can be changed.
+    pub condition_tracker: ACCDConditionTracker,
+    /// Measures the speeds, its traps come from `config.speed_traps` with the track data.
+    pub speed_trap_tracker: ACCDSpeedTrapTracker,
+    /// Results waiting to be returned by `listen_step`, e.g. buffered car updates.
+    pending: VecDeque<ListenResult>,
+    /// Datagrams received by `listen_step` are written to it.
+    recorder: Option<ACCDRecorder>,
 }
 
 impl Default for ACCDProtocol {
@@ -107,7 +151,14 @@ impl Default for ACCDProtocol {
             registration_result: ACCDRegistrationResult::default(),
             message_type: 1,
             entry_list_cars: Vec::new(),
-            last_entry_list_request: Instant::now(),
+            entry_list_sync: ACCDEntryListSync::new(),
+            session_tracker: ACCDSessionTracker::new(),
+            grid_tracker: ACCDGridTracker::new(),
+            pit_tracker: ACCDPitTracker::new(),
+            condition_tracker: ACCDConditionTracker::new(),
+            speed_trap_tracker: ACCDSpeedTrapTracker::new(),
+            pending: VecDeque::new(),
+            recorder: None,
         }
     }
 }
@@ -120,6 +171,56 @@ impl ACCDProtocol {
         }
     }
 
+    /// Binds the socket to `config.bind_addr`, unless it's already bound there,
+    /// and asks ACC to register this application.
+    pub fn connect(&mut self) -> io::Result<()> {
+        let bound = match &self.socket {
+            Some(socket) => socket.borrow().local_addr()? == self.config.bind_addr,
+            None => false,
+        };
+
+        if !bound {
+            // Release the old port first, the new config may bind the same one.
+            self.socket = None;
+            let socket = UdpSocket::bind(self.config.bind_addr)?;
+            socket.set_read_timeout(Some(LISTEN_TIMEOUT))?;
+            self.socket = Some(RefCell::new(socket));
+        }
+
+        self.request_connection();
+        Ok(())
+    }
+
+    /// Disconnects from the current ACC instance and connects with `config`,
+    /// nothing received from the previous connection is kept. The settings of the
+    /// sync and of the trackers are, and so are the pit losses learned on the track.
+    pub fn reconnect(&mut self, config: ACCDConfig) -> io::Result<()> {
+        if self.is_connected() {
+            self.disconnect();
+        }
+
+        self.entry_list_sync.reset();
+        self.grid_tracker.reset();
+        self.pit_tracker.reset();
+        self.condition_tracker.reset();
+        self.speed_trap_tracker.reset();
+        *self = ACCDProtocol {
+            socket: self.socket.take(),
+            entry_list_sync: mem::take(&mut self.entry_list_sync),
+            grid_tracker: mem::take(&mut self.grid_tracker),
+            pit_tracker: mem::take(&mut self.pit_tracker),
+            condition_tracker: mem::take(&mut self.condition_tracker),
+            speed_trap_tracker: mem::take(&mut self.speed_trap_tracker),
+            recorder: self.recorder.take(),
+            ..ACCDProtocol::new(config)
+        };
+        self.connect()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.registration_result.connection_success != 0
+    }
+
     pub fn request_connection(&self) {
         let mut buffer = Vec::new();
         buffer.write_u8(self.message_type).unwrap();
@@ -151,7 +252,11 @@ impl ACCDProtocol {
         }
     }
 
-    pub fn disconnect(&self) {
+    pub fn disconnect(&mut self) {
+        if self.socket.is_none() {
+            return;
+        }
+
         let mut buffer = Vec::new();
         buffer
             .write_u8(OutboundMessageTypes::UnregisterCommandApplication as u8)
@@ -175,9 +280,18 @@ impl ACCDProtocol {
                 println!("ERROR: {}", e);
             }
         }
+
+        self.registration_result = ACCDRegistrationResult::default();
+        self.entry_list_cars.clear();
     }
 
     fn request_entry_list(&self) {
+        // Offline, e.g. playing a recording back.
+        let socket = match &self.socket {
+            Some(socket) => socket,
+            None => return,
+        };
+
         let mut buffer = Vec::new();
         buffer
             .write_u8(OutboundMessageTypes::RequestEntryList as u8)
@@ -186,10 +300,7 @@ impl ACCDProtocol {
             .write_i32::<NativeEndian>(self.registration_result.connection_id)
             .unwrap();
 
-        match self
-            .socket
-            .as_ref()
-            .unwrap()
+        match socket
             .borrow()
             .send_to(&buffer, &self.config.destination_addr)
         {
@@ -204,6 +315,12 @@ impl ACCDProtocol {
     }
 
     fn request_track_data(&self) {
+        // Offline, e.g. playing a recording back.
+        let socket = match &self.socket {
+            Some(socket) => socket,
+            None => return,
+        };
+
         let mut buffer = Vec::new();
         buffer
             .write_u8(OutboundMessageTypes::RequestTrackData as u8)
@@ -212,10 +329,7 @@ impl ACCDProtocol {
             .write_i32::<NativeEndian>(self.registration_result.connection_id)
             .unwrap();
 
-        match self
-            .socket
-            .as_ref()
-            .unwrap()
+        match socket
             .borrow()
             .send_to(&buffer, self.config.destination_addr)
         {
@@ -283,11 +397,13 @@ impl ACCDProtocol {
         };
     }
 
+    /// Plays the replay of `duration_ms` from `start_session_time`,
+    /// `initial_focused_car_index` `None` keeps the car focused by ACC.
     pub fn request_instant_replay(
         &self,
         start_session_time: f32,
         duration_ms: f32,
-        initial_focused_car_index: i32,
+        initial_focused_car_index: Option<u16>,
         initial_camera_set: String,
         initial_camera: String,
     ) {
@@ -303,7 +419,7 @@ impl ACCDProtocol {
             .unwrap();
         buffer.write_f32::<NativeEndian>(duration_ms).unwrap();
         buffer
-            .write_i32::<NativeEndian>(initial_focused_car_index)
+            .write_i32::<NativeEndian>(initial_focused_car_index.map_or(-1, i32::from))
             .unwrap();
         write_string(&mut buffer, &initial_camera_set);
         write_string(&mut buffer, &initial_camera);
@@ -350,101 +466,271 @@ impl ACCDProtocol {
         }
     }
 
+    // ACC lists this message as planned, it will be ignored until the game implements it.
+    pub fn save_manual_replay_highlight(&self) {
+        let mut buffer = Vec::new();
+        buffer
+            .write_u8(OutboundMessageTypes::SaveManualReplayHighlight as u8)
+            .unwrap();
+        buffer
+            .write_i32::<NativeEndian>(self.registration_result.connection_id)
+            .unwrap();
+
+        match self
+            .socket
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .send_to(&buffer, self.config.destination_addr)
+        {
+            Ok(bytes) => {
+                println!("=== Save Manual Replay Highlight ({} bytes)", bytes);
+            }
+            Err(e) => {
+                println!("ERROR: {}", e);
+            }
+        }
+    }
+
+    /// Records the datagrams received from now on to `path`. The recording starts with
+    /// the registration result and the entry list and track data are requested again,
+    /// so it can be played back on its own.
+    pub fn start_recording(&mut self, path: &Path) -> io::Result<()> {
+        let mut recorder = ACCDRecorder::create(path)?;
+
+        if self.registration_result.connection_id >= 0 {
+            let mut buffer = vec![InboundMessageTypes::RegistrationResult as u8];
+            buffer.write_i32::<NativeEndian>(self.registration_result.connection_id)?;
+            buffer.write_u8(self.registration_result.connection_success)?;
+            buffer.write_u8(self.registration_result.is_read_only)?;
+            write_string(&mut buffer, &self.registration_result.err_msg);
+            recorder.write(&buffer)?;
+
+            self.request_track_data();
+            self.request_entry_list();
+        }
+
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Returns false if it wasn't recording.
+    pub fn stop_recording(&mut self) -> bool {
+        self.recorder.take().is_some()
+    }
+
+    pub fn connection_id(&self) -> i32 {
+        self.registration_result.connection_id
+    }
+
     pub fn listen_step(&mut self) -> ListenResult {
-        let mut msg = [0; 2048];
-        match self.socket.as_ref().unwrap().borrow().recv_from(&mut msg) {
-            Ok(_) => {}
+        if let Some(listen_result) = self.next_pending() {
+            return listen_result;
+        }
+
+        match self.recv_datagram() {
+            Some(msg) => self.receive_datagram(&msg),
+            None => ListenResult::Error,
+        }
+    }
+
+    /// Like `listen_step`, but `accdp` is only locked to process what arrived,
+    /// so commands don't wait for ACC to send something.
+    pub fn listen_shared(accdp: &Mutex<ACCDProtocol>) -> ListenResult {
+        let socket = {
+            let mut accdp = accdp.lock().unwrap();
+            if let Some(listen_result) = accdp.next_pending() {
+                return listen_result;
+            }
+            accdp
+                .socket
+                .as_ref()
+                .and_then(|socket| socket.borrow().try_clone().ok())
+        };
+
+        let socket = match socket {
+            Some(socket) => socket,
+            None => {
+                // Not connected yet, don't spin on the lock.
+                thread::sleep(LISTEN_TIMEOUT);
+                return ListenResult::Error;
+            }
+        };
+        match recv(&socket) {
+            Some(msg) => accdp.lock().unwrap().receive_datagram(&msg),
+            None => ListenResult::Error,
+        }
+    }
+
+    /// Records a datagram received from ACC when recording and processes it.
+    fn receive_datagram(&mut self, msg: &[u8]) -> ListenResult {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.write(msg) {
+                println!("ERROR: recording stopped, {}", e);
+                self.recorder = None;
+            }
+        }
+        self.process_datagram(msg)
+    }
+
+    /// A result produced by an earlier datagram and not returned yet,
+    /// callers of `process_datagram` need to drain them.
+    pub fn next_pending(&mut self) -> Option<ListenResult> {
+        self.pending.pop_front()
+    }
+
+    /// Sends the requests the entry list sync asks for and queues what it delivers.
+    fn apply_sync(&mut self, output: ACCDEntryListSyncOutput) {
+        if output.request_entry_list {
+            self.request_entry_list();
+        }
+        if output.request_track_data {
+            self.request_track_data();
+        }
+
+        for event in output.events {
+            println!("Entry list sync: {:?}", event);
+            self.pending.push_back(ListenResult::EntryListSync(event));
+        }
+        for car_update in output.car_updates {
+            let grid_events = self.grid_tracker.car_update(&car_update);
+            let pit_events = self
+                .pit_tracker
+                .car_update(&car_update, &self.entry_list_cars);
+            let speed_events = self
+                .speed_trap_tracker
+                .car_update(&car_update, &self.entry_list_cars);
+            self.pending
+                .push_back(ListenResult::RealTimeCarUpdate(car_update));
+            for event in grid_events {
+                self.pending.push_back(ListenResult::Grid(event));
+            }
+            for event in pit_events {
+                self.pending.push_back(ListenResult::Pit(event));
+            }
+            for event in speed_events {
+                self.pending.push_back(ListenResult::SpeedTrap(event));
+            }
+        }
+    }
+
+    /// Waits for the next datagram from ACC, `None` if nothing arrived within `LISTEN_TIMEOUT`.
+    pub fn recv_datagram(&self) -> Option<Vec<u8>> {
+        recv(&self.socket.as_ref().unwrap().borrow())
+    }
+
+    /// Parses a datagram received from ACC, following up with the requests it implies
+    /// (e.g. track data and entry list after the registration).
+    /// Truncated or malformed datagrams are an error, leaving the state as it was.
+    pub fn process_datagram(&mut self, msg: &[u8]) -> ListenResult {
+        match self.parse_datagram(msg) {
+            Ok(result) => result,
 
             Err(e) => {
-                panic!("ERROR: {}", e);
+                println!("ERROR(datagram): {}", e);
+                ListenResult::Error
             }
         }
+    }
 
+    fn parse_datagram(&mut self, msg: &[u8]) -> io::Result<ListenResult> {
         let msg = msg.to_vec();
         let mut cur = Cursor::new(&msg);
-        let message_type = InboundMessageTypes::from(cur.read_u8().unwrap());
+        let message_type = InboundMessageTypes::from(cur.read_u8()?);
 
-        match message_type {
+        let result = match message_type {
             InboundMessageTypes::RegistrationResult => {
-                self.registration_result = ACCDRegistrationResult::new(&mut cur);
+                self.registration_result = ACCDRegistrationResult::new(&mut cur)?;
                 self.request_track_data();
                 self.request_entry_list();
                 ListenResult::RegistrationResult(self.registration_result.clone())
             }
 
             InboundMessageTypes::RealTimeUpdate => {
-                ListenResult::RealTimeUpdate(ACCDRealtimeUpdate::new(&mut cur))
+                let realtime_update = ACCDRealtimeUpdate::new(&mut cur)?;
+                for event in self.session_tracker.realtime_update(&realtime_update) {
+                    self.pending.push_back(ListenResult::Session(event));
+                }
+                for event in self
+                    .grid_tracker
+                    .realtime_update(&realtime_update, &self.entry_list_cars)
+                {
+                    self.pending.push_back(ListenResult::Grid(event));
+                }
+                self.pit_tracker.realtime_update(&realtime_update);
+                self.speed_trap_tracker.realtime_update(&realtime_update);
+                for event in self.condition_tracker.realtime_update(&realtime_update) {
+                    self.pending.push_back(ListenResult::Conditions(event));
+                }
+                let output = self
+                    .entry_list_sync
+                    .realtime_update(&realtime_update, Instant::now());
+                self.apply_sync(output);
+
+                // Session events go first, so consumers can archive the previous session.
+                self.pending
+                    .push_back(ListenResult::RealTimeUpdate(realtime_update));
+                self.next_pending().unwrap_or(ListenResult::Error)
             }
 
             InboundMessageTypes::RealTimeCarUpdate => {
-                let car_index = cur.read_u16::<NativeEndian>().unwrap() as i32;
-                let driver_index = cur.read_u16::<NativeEndian>().unwrap() as i32;
-                let driver_count = cur.read_u8().unwrap();
-
-                match self
-                    .entry_list_cars
-                    .iter()
-                    .find(|car_info| car_info.car_index == car_index as u16)
-                {
-                    Some(car_info) => {
-                        if car_info.drivers.len() != driver_count as usize {
-                            if Instant::now()
-                                .saturating_duration_since(self.last_entry_list_request)
-                                .as_secs()
-                                > 1
-                            {
-                                self.last_entry_list_request = Instant::now();
-                                self.request_entry_list();
-                                println!(
-                                    "CarUpdate {}|{} not know, will ask for new EntryList",
-                                    car_index, driver_index
-                                );
-                            };
-                        } else {
-                            let real_time_car_update = ACCDRealtimeCarUpdate::new(
-                                &mut cur,
-                                car_index,
-                                driver_index,
-                                driver_count,
-                            );
-                            return ListenResult::RealTimeCarUpdate(real_time_car_update);
-                        }
-                    }
-
-                    None => {}
-                };
+                let car_index = cur.read_u16::<NativeEndian>()? as i32;
+                let driver_index = cur.read_u16::<NativeEndian>()? as i32;
+                let driver_count = cur.read_u8()?;
+
+                let real_time_car_update =
+                    ACCDRealtimeCarUpdate::new(&mut cur, car_index, driver_index, driver_count)?;
+                let output = self.entry_list_sync.car_update(
+                    &self.entry_list_cars,
+                    real_time_car_update,
+                    Instant::now(),
+                );
+                self.apply_sync(output);
 
-                ListenResult::Error
+                // Nothing to return when the update is buffered until the entry list arrives.
+                self.next_pending().unwrap_or(ListenResult::Error)
             }
 
             InboundMessageTypes::EntryList => {
-                self.entry_list_cars.clear();
-                let connection_id = cur.read_i32::<NativeEndian>().unwrap();
+                let connection_id = cur.read_i32::<NativeEndian>()?;
+                let mut entry_list_cars = Vec::new();
 
                 if connection_id == self.registration_result.connection_id {
-                    let car_entry_count = cur.read_u16::<NativeEndian>().unwrap();
+                    let car_entry_count = cur.read_u16::<NativeEndian>()?;
 
                     for _i in 0..car_entry_count {
-                        self.entry_list_cars
-                            .push(ACCDCarInfo::new(cur.read_u16::<NativeEndian>().unwrap()));
+                        entry_list_cars.push(ACCDCarInfo::new(cur.read_u16::<NativeEndian>()?));
                     }
                 }
+                self.entry_list_cars = entry_list_cars;
 
+                let output = self.entry_list_sync.entry_list(&self.entry_list_cars);
+                self.apply_sync(output);
                 ListenResult::EntryList(self.entry_list_cars.clone())
             }
 
             InboundMessageTypes::TrackData => {
-                let connection_id = cur.read_i32::<NativeEndian>().unwrap();
+                let connection_id = cur.read_i32::<NativeEndian>()?;
 
                 if connection_id == self.registration_result.connection_id {
-                    ListenResult::TrackData(ACCDTrackData::new(&mut cur))
+                    let track_data = ACCDTrackData::new(&mut cur)?;
+                    self.entry_list_sync.track_data();
+                    self.pit_tracker.track_data(track_data.track_id());
+                    self.speed_trap_tracker.set_traps(
+                        self.config
+                            .speed_traps
+                            .get(&track_data.track_name)
+                            .cloned()
+                            .unwrap_or_default(),
+                    );
+                    ListenResult::TrackData(track_data)
                 } else {
                     ListenResult::TrackData(ACCDTrackData::default())
                 }
             }
 
             InboundMessageTypes::EntryListCar => {
-                let car_index = cur.read_u16::<NativeEndian>().unwrap();
+                let car_index = cur.read_u16::<NativeEndian>()?;
                 let mut car_info = ACCDCarInfo::default();
 
                 match self
@@ -453,21 +739,23 @@ impl ACCDProtocol {
                     .find(|car_info| car_info.car_index == car_index)
                 {
                     Some(element) => {
-                        element.car_model_type = cur.read_u8().unwrap();
-                        element.team_name = read_string(&mut cur);
-                        element.race_number = cur.read_i32::<NativeEndian>().unwrap();
-                        element.cup_category = cur.read_u8().unwrap();
-                        element.current_driver_index = cur.read_u8().unwrap() as i32;
-                        element.nationality =
-                            NationalityEnum::from(cur.read_u16::<NativeEndian>().unwrap() as u8);
-
-                        element.drivers = Vec::new();
-                        let drivers_car_count = cur.read_u8().unwrap();
+                        let mut update = element.clone();
+                        update.car_model_type = cur.read_u8()?;
+                        update.team_name = read_string(&mut cur)?;
+                        update.race_number = cur.read_i32::<NativeEndian>()?;
+                        update.cup_category = cur.read_u8()?;
+                        update.current_driver_index = cur.read_u8()? as i32;
+                        update.nationality =
+                            NationalityEnum::from(cur.read_u16::<NativeEndian>()? as u8);
+
+                        update.drivers = Vec::new();
+                        let drivers_car_count = cur.read_u8()?;
 
                         for _i in 0..drivers_car_count {
-                            element.drivers.push(ACCDDriverInfo::new(&mut cur));
+                            update.drivers.push(ACCDDriverInfo::new(&mut cur)?);
                         }
 
+                        *element = update;
                         car_info = element.clone();
                     }
                     None => {
@@ -475,15 +763,106 @@ impl ACCDProtocol {
                     }
                 }
 
+                // Buffered updates of the car come after its entry.
+                let output = self.entry_list_sync.entry_list_car(&car_info);
+                self.apply_sync(output);
+
                 ListenResult::EntryListCar(car_info)
             }
 
             InboundMessageTypes::BroadcastingEvent => {
-                let broadcasting_event = ACCDBroadcastingEvent::new(&mut cur, &self);
+                let broadcasting_event = ACCDBroadcastingEvent::new(&mut cur, &self)?;
                 ListenResult::BroadcastingEvent(broadcasting_event)
             }
 
             InboundMessageTypes::Error => ListenResult::Error,
+        };
+
+        Ok(result)
+    }
+}
+
+fn recv(socket: &UdpSocket) -> Option<Vec<u8>> {
+    let mut msg = [0; 2048];
+    match socket.recv_from(&mut msg) {
+        Ok((len, _)) => Some(msg[..len].to_vec()),
+
+        Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => None,
+
+        Err(e) => {
+            println!("ERROR: {}", e);
+            None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_keeps_the_tracker_settings() {
+        let mut accdp = ACCDProtocol::default();
+        accdp.grid_tracker.first_lap_mover_threshold = 5;
+        accdp.pit_tracker.cycle_laps = 8;
+        accdp.condition_tracker.temperature_swing = 6;
+        accdp.speed_trap_tracker.set_traps(vec![0.25, 0.75]);
+
+        let config = ACCDConfig {
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            destination_addr: "127.0.0.1:9".parse().unwrap(),
+            ..ACCDConfig::default()
+        };
+        accdp.reconnect(config).unwrap();
+
+        assert_eq!(accdp.grid_tracker.first_lap_mover_threshold, 5);
+        assert_eq!(accdp.pit_tracker.cycle_laps, 8);
+        assert_eq!(accdp.condition_tracker.temperature_swing, 6);
+        assert_eq!(accdp.speed_trap_tracker.traps(), &[0.25, 0.75]);
+        assert_eq!(accdp.config.destination_addr.port(), 9);
+    }
+
+    #[test]
+    fn truncated_datagrams_are_errors() {
+        let mut accdp = ACCDProtocol::default();
+
+        for msg in [
+            &[][..],
+            &[1, 0, 0],
+            &[2],
+            &[2, 0, 0, 0, 0, 1, 2],
+            &[3, 1],
+            &[3, 1, 0, 0, 0, 1],
+            &[4, 0xff, 0xff, 0xff, 0xff],
+            &[5, 0xff, 0xff, 0xff, 0xff],
+            &[6, 0],
+            &[7, 1, 9, 0],
+        ]
+        .iter()
+        {
+            assert!(
+                matches!(accdp.process_datagram(msg), ListenResult::Error),
+                "{:?}",
+                msg
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_entry_list_car_keeps_the_entry() {
+        let mut accdp = ACCDProtocol::default();
+        let mut car = ACCDCarInfo::new(3);
+        car.team_name = String::from("Team");
+        accdp.entry_list_cars.push(car);
+
+        let mut msg = vec![6];
+        msg.extend_from_slice(&3u16.to_ne_bytes());
+        msg.push(1);
+        msg.extend_from_slice(&5u16.to_ne_bytes());
+        msg.extend_from_slice(b"Ot");
+
+        assert!(matches!(accdp.process_datagram(&msg), ListenResult::Error));
+        assert_eq!(accdp.entry_list_cars[0].team_name, "Team");
+        assert_eq!(accdp.entry_list_cars[0].car_model_type, 255);
+    }
+}
@@ -1,11 +1,12 @@
 use std::fmt;
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 use byteorder::{NativeEndian, ReadBytesExt};
 
 use crate::accd_lap_info::ACCDLapInfo;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CarLocationEnum {
     NONE = 0,
     Track = 1,
@@ -29,6 +30,7 @@ impl From<u8> for CarLocationEnum {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDRealtimeCarUpdate {
     pub car_index: i32,
     pub driver_index: i32,
@@ -56,27 +58,27 @@ impl ACCDRealtimeCarUpdate {
         car_index: i32,
         driver_index: i32,
         driver_count: u8,
-    ) -> Self {
-        ACCDRealtimeCarUpdate {
+    ) -> io::Result<Self> {
+        Ok(ACCDRealtimeCarUpdate {
             car_index,
             driver_index,
             driver_count,
-            gear: (cur.read_u8().unwrap() as i32 - 1) as i32,
-            world_pos_x: cur.read_f32::<NativeEndian>().unwrap(),
-            world_pos_y: cur.read_f32::<NativeEndian>().unwrap(),
-            yaw: cur.read_f32::<NativeEndian>().unwrap(),
-            car_location: CarLocationEnum::from(cur.read_u8().unwrap()),
-            kmh: cur.read_u16::<NativeEndian>().unwrap() as i32,
-            position: cur.read_u16::<NativeEndian>().unwrap() as i32,
-            cup_position: cur.read_u16::<NativeEndian>().unwrap(),
-            track_position: cur.read_u16::<NativeEndian>().unwrap() as i32,
-            spline_position: cur.read_f32::<NativeEndian>().unwrap(),
-            laps: cur.read_u16::<NativeEndian>().unwrap() as i32,
-            delta: cur.read_i32::<NativeEndian>().unwrap(),
-            best_session_lap: ACCDLapInfo::new(cur),
-            last_lap: ACCDLapInfo::new(cur),
-            current_lap: ACCDLapInfo::new(cur),
-        }
+            gear: (cur.read_u8()? as i32 - 1) as i32,
+            world_pos_x: cur.read_f32::<NativeEndian>()?,
+            world_pos_y: cur.read_f32::<NativeEndian>()?,
+            yaw: cur.read_f32::<NativeEndian>()?,
+            car_location: CarLocationEnum::from(cur.read_u8()?),
+            kmh: cur.read_u16::<NativeEndian>()? as i32,
+            position: cur.read_u16::<NativeEndian>()? as i32,
+            cup_position: cur.read_u16::<NativeEndian>()?,
+            track_position: cur.read_u16::<NativeEndian>()? as i32,
+            spline_position: cur.read_f32::<NativeEndian>()?,
+            laps: cur.read_u16::<NativeEndian>()? as i32,
+            delta: cur.read_i32::<NativeEndian>()?,
+            best_session_lap: ACCDLapInfo::new(cur)?,
+            last_lap: ACCDLapInfo::new(cur)?,
+            current_lap: ACCDLapInfo::new(cur)?,
+        })
     }
 }
 
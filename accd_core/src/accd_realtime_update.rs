@@ -1,13 +1,15 @@
+use std::convert::TryFrom;
 use std::fmt;
-use std::io::Cursor;
-use std::time::Duration;
+use std::io::{self, Cursor};
 
 use byteorder::{NativeEndian, ReadBytesExt};
 
 use crate::accd_lap_info::ACCDLapInfo;
+use crate::accd_session_time::SessionTime;
 use crate::accd_utils::read_string;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SessionPhase {
     NONE = 0,
     Starting = 1,
@@ -38,7 +40,8 @@ impl From<u8> for SessionPhase {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RaceSessionType {
     Practice = 0,
     Qualifying = 4,
@@ -69,27 +72,33 @@ impl From<u8> for RaceSessionType {
 
 #[allow(unused_variables)]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDRealtimeUpdate {
-    event_index: i32,
-    session_index: i32,
+    pub(crate) event_index: i32,
+    pub(crate) session_index: i32,
     pub phase: SessionPhase,
-    pub session_time: Duration,
-    pub remaining_time: Duration,
-    pub time_of_day: Duration,
+    pub session_time: SessionTime,
+    /// Remaining time of the replay while one is playing, of the session otherwise.
+    pub remaining_time: SessionTime,
+    pub time_of_day: SessionTime,
     pub rain_level: f32,
     pub clouds: f32,
     pub wetness: f32,
     pub best_session_lap: ACCDLapInfo,
-    pub bestlap_car_index: u16,
-    pub bestlap_driver_index: u16,
-    pub focused_car_index: i32,
+    /// Owner of `best_session_lap`, `None` until someone sets a lap time.
+    pub bestlap_car_index: Option<u16>,
+    pub bestlap_driver_index: Option<u16>,
+    /// `None` when ACC has no car focused.
+    pub focused_car_index: Option<u16>,
     pub active_camera_set: String,
     pub active_camera: String,
     pub is_replay_playing: bool,
-    pub replay_session_time: f32,
-    pub replay_remaining_time: f32,
-    pub session_remaining_time: Duration,
-    pub session_end_time: Duration,
+    /// `None` while no replay is playing.
+    pub replay_session_time: Option<SessionTime>,
+    pub replay_remaining_time: Option<SessionTime>,
+    /// `session_end_time - session_time`, zero once the time is over.
+    pub session_remaining_time: SessionTime,
+    pub session_end_time: SessionTime,
     pub session_type: RaceSessionType,
     pub ambient_temp: u8,
     pub track_temp: u8,
@@ -97,65 +106,91 @@ pub struct ACCDRealtimeUpdate {
 }
 
 impl ACCDRealtimeUpdate {
-    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> Self {
-        let event_index = cur.read_u16::<NativeEndian>().unwrap() as i32;
-        let session_index = cur.read_u16::<NativeEndian>().unwrap() as i32;
-        let session_type = RaceSessionType::from(cur.read_u8().unwrap());
-        let phase = SessionPhase::from(cur.read_u8().unwrap());
-        let session_time = Duration::from_millis(cur.read_f32::<NativeEndian>().unwrap() as u64);
-        let session_end_time =
-            Duration::from_millis(cur.read_f32::<NativeEndian>().unwrap() as u64);
-
-        let focused_car_index = cur.read_i32::<NativeEndian>().unwrap();
-        let active_camera_set = read_string(cur);
-        let active_camera = read_string(cur);
-        let current_hud_page = read_string(cur);
-
-        let mut replay_session_time: f32 = 0.0;
-        let mut replay_remaining_time: f32 = 0.0;
-        let is_replay_playing = if cur.read_u8().unwrap() > 0 {
-            replay_session_time = cur.read_f32::<NativeEndian>().unwrap();
-            replay_remaining_time = cur.read_f32::<NativeEndian>().unwrap();
+    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> io::Result<Self> {
+        let event_index = cur.read_u16::<NativeEndian>()? as i32;
+        let session_index = cur.read_u16::<NativeEndian>()? as i32;
+        let session_type = RaceSessionType::from(cur.read_u8()?);
+        let phase = SessionPhase::from(cur.read_u8()?);
+        let session_time = SessionTime::from_millis(cur.read_f32::<NativeEndian>()? as f64);
+        let session_end_time = SessionTime::from_millis(cur.read_f32::<NativeEndian>()? as f64);
+
+        let focused_car_index = u16::try_from(cur.read_i32::<NativeEndian>()?).ok();
+        let active_camera_set = read_string(cur)?;
+        let active_camera = read_string(cur)?;
+        let current_hud_page = read_string(cur)?;
+
+        let mut replay_session_time = None;
+        let mut replay_remaining_time = None;
+        let is_replay_playing = if cur.read_u8()? > 0 {
+            replay_session_time = Some(SessionTime::from_millis(
+                cur.read_f32::<NativeEndian>()? as f64
+            ));
+            replay_remaining_time = Some(SessionTime::from_millis(
+                cur.read_f32::<NativeEndian>()? as f64
+            ));
             true
         } else {
             false
         };
 
-        let time_of_day = Duration::from_millis(cur.read_f32::<NativeEndian>().unwrap() as u64);
-        let ambient_temp = cur.read_u8().unwrap();
-        let track_temp = cur.read_u8().unwrap();
-        let clouds = (cur.read_u8().unwrap() as f32) / 10f32;
-        let rain_level = (cur.read_u8().unwrap() as f32) / 10f32;
-        let wetness = (cur.read_u8().unwrap() as f32) / 10f32;
+        let time_of_day = SessionTime::from_millis(cur.read_f32::<NativeEndian>()? as f64);
+        let ambient_temp = cur.read_u8()?;
+        let track_temp = cur.read_u8()?;
+        let clouds = (cur.read_u8()? as f32) / 10f32;
+        let rain_level = (cur.read_u8()? as f32) / 10f32;
+        let wetness = (cur.read_u8()? as f32) / 10f32;
 
-        let best_session_lap = ACCDLapInfo::new(cur);
+        let best_session_lap = ACCDLapInfo::new(cur)?;
+        let (bestlap_car_index, bestlap_driver_index) = match best_session_lap.lap_time_ms {
+            Some(_) => (
+                Some(best_session_lap.car_index),
+                Some(best_session_lap.driver_index),
+            ),
+            None => (None, None),
+        };
 
-        ACCDRealtimeUpdate {
+        let session_remaining_time = session_end_time.saturating_sub(session_time);
+        // What's on screen: the replay while one is playing, the session otherwise.
+        let remaining_time = replay_remaining_time.unwrap_or(session_remaining_time);
+
+        Ok(ACCDRealtimeUpdate {
             event_index,
             session_index,
             phase,
             session_time,
-            remaining_time: Duration::from_millis(0),
+            remaining_time,
             time_of_day,
             rain_level,
             clouds,
             wetness,
             best_session_lap,
-            bestlap_car_index: 0,
-            bestlap_driver_index: 0,
+            bestlap_car_index,
+            bestlap_driver_index,
             focused_car_index,
             active_camera_set,
             active_camera,
             is_replay_playing,
             replay_session_time,
             replay_remaining_time,
-            session_remaining_time: Duration::from_millis(0),
+            session_remaining_time,
             session_end_time,
             session_type,
             ambient_temp,
             track_temp,
             current_hud_page,
-        }
+        })
+    }
+}
+
+impl ACCDRealtimeUpdate {
+    /// Index of the event (race weekend) loaded in ACC, -1 before the first update.
+    pub fn event_index(&self) -> i32 {
+        self.event_index
+    }
+
+    /// Index of the session within the event, -1 before the first update.
+    pub fn session_index(&self) -> i32 {
+        self.session_index
     }
 }
 
@@ -169,18 +204,20 @@ impl fmt::Display for ACCDRealtimeUpdate {
         )?;
         writeln!(
             f,
-            "session type: {:#?} | end time: {:?} | remaining time(?): {:?}",
+            "session type: {:#?} | end time: {} | remaining time: {}",
             self.session_type, self.session_end_time, self.remaining_time
         )?;
         writeln!(
             f,
-            "session time: {:?} | remaining time: {:?}",
+            "session time: {} | session remaining time: {}",
             self.session_time, self.session_remaining_time
         )?;
         writeln!(
             f,
             "time of day: {:?} | ambient temp: {} | track temp: {}",
-            self.time_of_day, self.ambient_temp, self.track_temp
+            self.time_of_day.to_duration().unwrap_or_default(),
+            self.ambient_temp,
+            self.track_temp
         )?;
         writeln!(
             f,
@@ -189,17 +226,17 @@ impl fmt::Display for ACCDRealtimeUpdate {
         )?;
         writeln!(
             f,
-            "car id: {} | driver id: {} | best lap:\n {}",
+            "car id: {:?} | driver id: {:?} | best lap:\n {}",
             self.bestlap_car_index, self.bestlap_driver_index, self.best_session_lap
         )?;
         writeln!(
             f,
-            "{} | {} | focused car: {}",
+            "{} | {} | focused car: {:?}",
             self.active_camera_set, self.active_camera, self.focused_car_index
         )?;
         writeln!(
             f,
-            "replay playing: {} | session time: {} | remaining time: {}",
+            "replay playing: {} | session time: {:?} | remaining time: {:?}",
             self.is_replay_playing, self.replay_session_time, self.replay_remaining_time
         )?;
         writeln!(f, "current hud: {}", self.current_hud_page)?;
@@ -207,29 +244,32 @@ impl fmt::Display for ACCDRealtimeUpdate {
     }
 }
 
+/// The update shown before ACC sends one. Weather and temperatures stay plain values:
+/// every update from ACC has them, the `-1.0` and `255` here are never seen by the condition
+/// tracker or the condition history, which only get the updates ACC sent.
 impl Default for ACCDRealtimeUpdate {
     fn default() -> Self {
         ACCDRealtimeUpdate {
             event_index: -1,
             session_index: -1,
             phase: SessionPhase::NONE,
-            session_time: Duration::from_secs(0),
-            remaining_time: Duration::from_secs(00),
-            time_of_day: Duration::from_secs(0),
+            session_time: SessionTime::ZERO,
+            remaining_time: SessionTime::ZERO,
+            time_of_day: SessionTime::ZERO,
             rain_level: -1.0,
             clouds: -1.0,
             wetness: -1.0,
             best_session_lap: ACCDLapInfo::default(),
-            bestlap_car_index: 65535,
-            bestlap_driver_index: 65535,
-            focused_car_index: -1,
+            bestlap_car_index: None,
+            bestlap_driver_index: None,
+            focused_car_index: None,
             active_camera_set: "".to_string(),
             active_camera: "".to_string(),
             is_replay_playing: false,
-            replay_session_time: -1.0,
-            replay_remaining_time: -1.0,
-            session_remaining_time: Duration::from_secs(0),
-            session_end_time: Duration::from_secs(0),
+            replay_session_time: None,
+            replay_remaining_time: None,
+            session_remaining_time: SessionTime::ZERO,
+            session_end_time: SessionTime::ZERO,
             session_type: RaceSessionType::Error,
             ambient_temp: 255,
             track_temp: 255,
@@ -237,3 +277,84 @@ impl Default for ACCDRealtimeUpdate {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accd_car_info::ACCDCarInfo;
+    use crate::accd_protocol::ListenResult;
+    use crate::accd_session_state::ACCDSessionState;
+    use crate::accd_utils::write_string;
+    use byteorder::WriteBytesExt;
+
+    /// A realtime update datagram without its type, 20 minutes into a one hour race.
+    fn realtime_update(best_lap_ms: i32, car_index: u16, driver_index: u16) -> Vec<u8> {
+        let mut msg = Vec::new();
+        msg.write_u16::<NativeEndian>(1).unwrap();
+        msg.write_u16::<NativeEndian>(2).unwrap();
+        msg.write_u8(RaceSessionType::Race as u8).unwrap();
+        msg.write_u8(SessionPhase::Session as u8).unwrap();
+        msg.write_f32::<NativeEndian>(1_200_000.0).unwrap();
+        msg.write_f32::<NativeEndian>(3_600_000.0).unwrap();
+        msg.write_i32::<NativeEndian>(car_index as i32).unwrap();
+        for text in &["set", "camera", "hud"] {
+            write_string(&mut msg, &text.to_string());
+        }
+        msg.write_u8(0).unwrap(); // no replay
+        msg.write_f32::<NativeEndian>(50_400_000.0).unwrap();
+        msg.extend_from_slice(&[22, 30, 2, 0, 0]);
+
+        msg.write_i32::<NativeEndian>(best_lap_ms).unwrap();
+        msg.write_u16::<NativeEndian>(car_index).unwrap();
+        msg.write_u16::<NativeEndian>(driver_index).unwrap();
+        msg.write_u8(0).unwrap(); // splits
+        msg.extend_from_slice(&[0, 1, 0, 0]);
+        msg
+    }
+
+    fn parse(msg: &Vec<u8>) -> ACCDRealtimeUpdate {
+        ACCDRealtimeUpdate::new(&mut Cursor::new(msg)).unwrap()
+    }
+
+    #[test]
+    fn best_lap_owner() {
+        let update = parse(&realtime_update(90_123, 7, 1));
+        assert_eq!(update.best_session_lap.lap_time_ms, Some(90_123));
+        assert_eq!(update.bestlap_car_index, Some(7));
+        assert_eq!(update.bestlap_driver_index, Some(1));
+    }
+
+    #[test]
+    fn no_owner_without_a_best_lap() {
+        // ACC sends the indexes of the first car with the "no lap" time.
+        let update = parse(&realtime_update(i32::MAX, 0, 0));
+        assert_eq!(update.best_session_lap.lap_time_ms, None);
+        assert_eq!(update.bestlap_car_index, None);
+        assert_eq!(update.bestlap_driver_index, None);
+    }
+
+    #[test]
+    fn best_lap_of_a_car_missing_from_the_entry_list() {
+        let mut state = ACCDSessionState::new();
+        state.update(&ListenResult::EntryList(vec![ACCDCarInfo {
+            car_index: 1,
+            ..ACCDCarInfo::default()
+        }]));
+
+        // The owner is kept as ACC sent it, the entry list is updated later.
+        let update = parse(&realtime_update(90_123, 42, 0));
+        state.update(&ListenResult::RealTimeUpdate(update));
+        assert_eq!(state.realtime_update.bestlap_car_index, Some(42));
+        assert!(state.car_info(42).is_none());
+    }
+
+    #[test]
+    fn remaining_time_of_the_session() {
+        let update = parse(&realtime_update(i32::MAX, 0, 0));
+        assert_eq!(
+            update.session_remaining_time,
+            SessionTime::from_secs(2400.0)
+        );
+        assert_eq!(update.remaining_time, update.session_remaining_time);
+    }
+}
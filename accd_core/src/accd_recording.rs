@@ -0,0 +1,238 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::accd_protocol::{ACCDProtocol, ListenResult};
+
+/// First bytes of every recording file.
+const MAGIC: &[u8; 8] = b"ACCDREC1";
+
+/// Writes the datagrams received from ACC to a file, so a session can be played back later.
+///
+/// The file starts with `ACCDREC1`, then every datagram is stored as the milliseconds since
+/// the recording started (u64), its length (u16) and its bytes, little endian.
+#[derive(Debug)]
+pub struct ACCDRecorder {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+impl ACCDRecorder {
+    /// Creates the file, overwriting an existing one.
+    pub fn create(path: &Path) -> io::Result<ACCDRecorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        Ok(ACCDRecorder {
+            writer,
+            started: Instant::now(),
+        })
+    }
+
+    /// Appends a datagram, an error without writing anything when it's longer than the
+    /// 64 KiB the length field holds.
+    pub fn write(&mut self, msg: &[u8]) -> io::Result<()> {
+        let len = u16::try_from(msg.len()).map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("a datagram of {} bytes is too long to record", msg.len()),
+            )
+        })?;
+        let millis = self.started.elapsed().as_millis() as u64;
+        self.writer.write_u64::<LittleEndian>(millis)?;
+        self.writer.write_u16::<LittleEndian>(len)?;
+        self.writer.write_all(msg)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Drop for ACCDRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            println!("ERROR: {}", e);
+        }
+    }
+}
+
+/// Datagrams of a recording with the time they were received at, in order.
+pub struct ACCDRecording {
+    reader: BufReader<File>,
+}
+
+impl ACCDRecording {
+    pub fn open(path: &Path) -> io::Result<ACCDRecording> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "not an accd recording",
+            ));
+        }
+        Ok(ACCDRecording { reader })
+    }
+
+    fn read_datagram(&mut self) -> io::Result<Option<(Duration, Vec<u8>)>> {
+        let millis = match self.reader.read_u64::<LittleEndian>() {
+            Ok(millis) => millis,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let len = self.reader.read_u16::<LittleEndian>()? as usize;
+        let mut msg = vec![0; len];
+        self.reader.read_exact(&mut msg)?;
+        Ok(Some((Duration::from_millis(millis), msg)))
+    }
+}
+
+impl Iterator for ACCDRecording {
+    type Item = io::Result<(Duration, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_datagram().transpose()
+    }
+}
+
+/// Plays a recording back through an offline protocol, so the results are the same
+/// `ListenResult`s a live connection would have returned.
+pub struct ACCDRecordingPlayer {
+    accdp: ACCDProtocol,
+    recording: ACCDRecording,
+    /// Time of the datagram that produced the pending results.
+    time: Duration,
+}
+
+impl ACCDRecordingPlayer {
+    pub fn open(path: &Path) -> io::Result<ACCDRecordingPlayer> {
+        Ok(ACCDRecordingPlayer {
+            accdp: ACCDProtocol::default(),
+            recording: ACCDRecording::open(path)?,
+            time: Duration::ZERO,
+        })
+    }
+}
+
+impl Iterator for ACCDRecordingPlayer {
+    /// A result and the time since the start of the recording of the datagram it came from.
+    type Item = (Duration, ListenResult);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(listen_result) = self.accdp.next_pending() {
+            return Some((self.time, listen_result));
+        }
+
+        loop {
+            let (time, msg) = match self.recording.next()? {
+                Ok(datagram) => datagram,
+                Err(e) => {
+                    // A recording cut while writing ends with half a datagram.
+                    println!("ERROR: {}", e);
+                    return None;
+                }
+            };
+            self.time = time;
+
+            // Errors are datagrams without a result, e.g. a buffered car update.
+            match self.accdp.process_datagram(&msg) {
+                ListenResult::Error => continue,
+                listen_result => return Some((time, listen_result)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("accd_recording_{}_{}", std::process::id(), name))
+    }
+
+    fn record(path: &Path, datagrams: &[&[u8]]) {
+        let mut recorder = ACCDRecorder::create(path).unwrap();
+        for msg in datagrams {
+            recorder.write(msg).unwrap();
+        }
+    }
+
+    fn datagrams(path: &Path) -> Vec<io::Result<Vec<u8>>> {
+        ACCDRecording::open(path)
+            .unwrap()
+            .map(|datagram| datagram.map(|(_, msg)| msg))
+            .collect()
+    }
+
+    #[test]
+    fn recordings_read_back_the_datagrams() {
+        let path = temp_path("round_trip");
+        let long = vec![7; 2000];
+        record(&path, &[&[2, 1, 0], &[], &long]);
+
+        let read: Vec<Vec<u8>> = datagrams(&path).into_iter().map(Result::unwrap).collect();
+        assert_eq!(read, vec![vec![2, 1, 0], vec![], long]);
+
+        let times: Vec<Duration> = ACCDRecording::open(&path)
+            .unwrap()
+            .map(|datagram| datagram.unwrap().0)
+            .collect();
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recordings_cut_while_writing() {
+        let path = temp_path("truncated");
+        record(&path, &[&[2, 1, 0], &[3, 4, 5, 6]]);
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() - 2]).unwrap();
+
+        // The datagrams before the cut are read, the half one is an error.
+        let read = datagrams(&path);
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].as_ref().unwrap(), &vec![2, 1, 0]);
+        assert_eq!(
+            read[1].as_ref().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        // The player stops there.
+        assert_eq!(ACCDRecordingPlayer::open(&path).unwrap().count(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn other_files_are_not_recordings() {
+        let path = temp_path("magic");
+        fs::write(&path, b"ACCDREC2\0\0\0\0\0\0\0\0\0\0").unwrap();
+        let e = ACCDRecording::open(&path).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+        fs::write(&path, b"ACCD").unwrap();
+        assert!(ACCDRecording::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn oversized_datagrams_are_not_recorded() {
+        let path = temp_path("oversized");
+        let mut recorder = ACCDRecorder::create(&path).unwrap();
+        let e = recorder.write(&vec![0; 65536]).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        recorder.write(&vec![1; 65535]).unwrap();
+        drop(recorder);
+
+        let read: Vec<Vec<u8>> = datagrams(&path).into_iter().map(Result::unwrap).collect();
+        assert_eq!(read, vec![vec![1; 65535]]);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,10 @@
 use std::fmt;
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 use byteorder::{NativeEndian, ReadBytesExt};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDRegistrationResult {
     pub connection_id: i32,
     pub connection_success: u8,
@@ -12,11 +13,11 @@ pub struct ACCDRegistrationResult {
 }
 
 impl ACCDRegistrationResult {
-    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> Self {
-        ACCDRegistrationResult {
-            connection_id: cur.read_i32::<NativeEndian>().unwrap(),
-            connection_success: cur.read_u8().unwrap(),
-            is_read_only: cur.read_u8().unwrap(),
+    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> io::Result<Self> {
+        Ok(ACCDRegistrationResult {
+            connection_id: cur.read_i32::<NativeEndian>()?,
+            connection_success: cur.read_u8()?,
+            is_read_only: cur.read_u8()?,
             err_msg: match String::from_utf8(cur.get_mut().to_vec()) {
                 Ok(data) => data,
                 Err(e) => {
@@ -26,7 +27,7 @@ impl ACCDRegistrationResult {
             }, /* .unwrap()
                .trim()
                .to_string(), */
-        }
+        })
     }
 }
 
@@ -0,0 +1,719 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::accd_broadcasting_event::{ACCDBroadcastingEvent, BroadcastingCarEventType};
+use crate::accd_car_info::{ACCDCarInfo, CarClass};
+use crate::accd_driver_info::ACCDDriverInfo;
+use crate::accd_lap_info::{ACCDLapInfo, LapType};
+use crate::accd_pit_strategy::ACCDPitStop;
+use crate::accd_realtime_update::RaceSessionType;
+use crate::accd_session_state::ACCDSessionArchive;
+use crate::accd_session_time::SessionTime;
+use crate::accd_session_tracker::ACCDSessionInfo;
+
+/// Directory the applications write the session reports to.
+pub const DEFAULT_REPORTS_DIR: &str = "./reports";
+
+/// Distance of a car from the winner of its class.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDReportGap {
+    Time(SessionTime),
+    Laps(i32),
+}
+
+impl fmt::Display for ACCDReportGap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ACCDReportGap::Time(time) => write!(f, "+{:.3}", time.as_secs()),
+            ACCDReportGap::Laps(1) => write!(f, "+1 lap"),
+            ACCDReportGap::Laps(laps) => write!(f, "+{} laps", laps),
+        }
+    }
+}
+
+/// Laps driven between two pit stops.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDReportStint {
+    pub driver: String,
+    pub first_lap: usize,
+    pub laps: usize,
+    pub best_lap_ms: Option<i32>,
+    /// Of the valid regular laps.
+    pub average_lap_ms: Option<i32>,
+}
+
+/// A car of the final classification.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDReportCar {
+    pub position: usize,
+    pub class_position: usize,
+    pub car_class: CarClass,
+    pub race_number: i32,
+    pub team_name: String,
+    pub car_model: String,
+    pub drivers: Vec<String>,
+    pub laps: i32,
+    /// Sum of the lap times, when every lap was recorded.
+    pub total_time: Option<SessionTime>,
+    pub best_lap_ms: Option<i32>,
+    /// `None` for the class winner and for cars without laps.
+    pub gap: Option<ACCDReportGap>,
+    /// In laps of the pit stops, the laps the car entered the pit lane on.
+    pub pit_stops: Vec<usize>,
+    pub stints: Vec<ACCDReportStint>,
+}
+
+/// A lap or a sector time, with who drove it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDReportLap {
+    pub race_number: i32,
+    pub driver: String,
+    pub lap: usize,
+    pub time_ms: i32,
+}
+
+/// Fastest lap and sectors of a class.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDReportClassBests {
+    pub car_class: CarClass,
+    pub fastest_lap: Option<ACCDReportLap>,
+    pub best_sectors: Vec<Option<ACCDReportLap>>,
+}
+
+/// A penalty or an accident.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDReportIncident {
+    pub time: SessionTime,
+    /// `None` when the car is not in the entry list.
+    pub race_number: Option<i32>,
+    pub message: String,
+}
+
+/// Results of a session, built from its archive and rendered as Markdown or HTML.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDReport {
+    pub session: ACCDSessionInfo,
+    pub track_name: String,
+    /// Overall classification.
+    pub cars: Vec<ACCDReportCar>,
+    /// Classes in order of their best placed car.
+    pub classes: Vec<CarClass>,
+    pub bests: Vec<ACCDReportClassBests>,
+    /// Race numbers in position order at the end of every lap, races only.
+    /// `None` where no car reported the position.
+    pub lap_chart: Vec<Vec<Option<i32>>>,
+    pub penalties: Vec<ACCDReportIncident>,
+    pub accidents: Vec<ACCDReportIncident>,
+}
+
+/// A titled part of the report, rendered the same way in both formats.
+struct Section {
+    title: String,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl ACCDReport {
+    pub fn new(archive: &ACCDSessionArchive, track_name: &str) -> Self {
+        let race = archive.session.session_type == RaceSessionType::Race;
+
+        let mut cars: Vec<ACCDReportCar> = Vec::new();
+        for standing in archive.standings.iter() {
+            let laps = archive
+                .laps
+                .get(&standing.car_info.car_index)
+                .map_or(&[][..], |laps| laps.as_slice());
+            let car_laps = standing
+                .car_update
+                .as_ref()
+                .map_or(laps.len() as i32, |car_update| car_update.laps);
+            let stops = archive
+                .pit_stops
+                .get(&standing.car_info.car_index)
+                .map_or(&[][..], |stops| stops.as_slice());
+            cars.push(report_car(
+                &standing.car_info,
+                laps,
+                stops,
+                car_laps,
+                cars.len() + 1,
+            ));
+        }
+
+        let mut classes: Vec<CarClass> = Vec::new();
+        for car in cars.iter() {
+            if !classes.contains(&car.car_class) {
+                classes.push(car.car_class);
+            }
+        }
+
+        for car_class in classes.iter() {
+            let mut class_cars: Vec<&mut ACCDReportCar> = cars
+                .iter_mut()
+                .filter(|car| car.car_class == *car_class)
+                .collect();
+            let winner = (
+                class_cars[0].laps,
+                class_cars[0].total_time,
+                class_cars[0].best_lap_ms,
+            );
+            for (i, car) in class_cars.iter_mut().enumerate() {
+                car.class_position = i + 1;
+                if i > 0 {
+                    car.gap = gap(race, winner, car);
+                }
+            }
+        }
+
+        let bests = classes
+            .iter()
+            .map(|car_class| class_bests(archive, *car_class))
+            .collect();
+
+        let lap_chart = if race { lap_chart(archive) } else { Vec::new() };
+
+        ACCDReport {
+            session: archive.session.clone(),
+            track_name: track_name.to_string(),
+            cars,
+            classes,
+            bests,
+            lap_chart,
+            penalties: incidents(
+                &archive.broadcasting_events,
+                BroadcastingCarEventType::PenaltyCommMsg,
+            ),
+            accidents: incidents(
+                &archive.broadcasting_events,
+                BroadcastingCarEventType::Accident,
+            ),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        format!("{:?} - {}", self.session.session_type, self.track_name)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n{}\n", self.title(), self.session);
+
+        for section in self.sections() {
+            markdown += &format!("\n## {}\n\n", section.title);
+            if section.rows.is_empty() {
+                markdown += "None.\n";
+                continue;
+            }
+
+            let cell = |text: &str| text.replace('|', "\\|");
+            markdown += &format!("| {} |\n", section.headers.join(" | "));
+            markdown += &format!("|{}\n", "---|".repeat(section.headers.len()));
+            for row in section.rows {
+                let row: Vec<String> = row.iter().map(|text| cell(text)).collect();
+                markdown += &format!("| {} |\n", row.join(" | "));
+            }
+        }
+
+        markdown
+    }
+
+    /// Standalone page, styles included.
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>\n\
+             body {{ font-family: sans-serif; margin: 2em; }}\n\
+             table {{ border-collapse: collapse; margin-bottom: 1em; }}\n\
+             th, td {{ border: 1px solid #ccc; padding: 2px 8px; text-align: left; }}\n\
+             th {{ background: #eee; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n<p>{session}</p>\n",
+            title = escape_html(&self.title()),
+            session = escape_html(&self.session.to_string())
+        );
+
+        for section in self.sections() {
+            html += &format!("<h2>{}</h2>\n", escape_html(&section.title));
+            if section.rows.is_empty() {
+                html += "<p>None.</p>\n";
+                continue;
+            }
+
+            html += "<table>\n<tr>";
+            for header in section.headers {
+                html += &format!("<th>{}</th>", escape_html(header));
+            }
+            html += "</tr>\n";
+            for row in section.rows {
+                html += "<tr>";
+                for text in row {
+                    html += &format!("<td>{}</td>", escape_html(&text));
+                }
+                html += "</tr>\n";
+            }
+            html += "</table>\n";
+        }
+
+        html += "</body>\n</html>\n";
+        html
+    }
+
+    /// Writes the Markdown and the HTML report to `dir`, named after the current time,
+    /// the track and the session type. Returns the paths of the files.
+    pub fn save(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let name = format!(
+            "report_{}_{}_{:?}",
+            secs,
+            self.track_name
+                .replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+            self.session.session_type
+        )
+        .to_lowercase();
+
+        fs::create_dir_all(dir)?;
+        let markdown = dir.join(format!("{}.md", name));
+        fs::write(&markdown, self.to_markdown())?;
+        let html = dir.join(format!("{}.html", name));
+        fs::write(&html, self.to_html())?;
+
+        Ok(vec![markdown, html])
+    }
+
+    fn sections(&self) -> Vec<Section> {
+        let mut sections = Vec::new();
+
+        for car_class in self.classes.iter() {
+            sections.push(Section {
+                title: format!("Classification {:?}", car_class),
+                headers: vec![
+                    "Pos", "Overall", "#", "Team", "Car", "Drivers", "Laps", "Time", "Gap",
+                    "Best lap", "Stops",
+                ],
+                rows: self
+                    .cars
+                    .iter()
+                    .filter(|car| car.car_class == *car_class)
+                    .map(|car| {
+                        vec![
+                            car.class_position.to_string(),
+                            car.position.to_string(),
+                            car.race_number.to_string(),
+                            car.team_name.clone(),
+                            car.car_model.clone(),
+                            car.drivers.join(", "),
+                            car.laps.to_string(),
+                            fmt_optional(car.total_time),
+                            fmt_optional(car.gap.as_ref()),
+                            fmt_lap_ms(car.best_lap_ms),
+                            car.pit_stops.len().to_string(),
+                        ]
+                    })
+                    .collect(),
+            });
+        }
+
+        let sectors = self
+            .bests
+            .iter()
+            .map(|bests| bests.best_sectors.len())
+            .max()
+            .unwrap_or(0);
+        let mut headers = vec!["Class", "Fastest lap"];
+        headers.extend(["Sector 1", "Sector 2", "Sector 3"].iter().take(sectors));
+        sections.push(Section {
+            title: "Fastest laps and sectors".to_string(),
+            headers,
+            rows: self
+                .bests
+                .iter()
+                .map(|bests| {
+                    let mut row = vec![
+                        format!("{:?}", bests.car_class),
+                        fmt_report_lap(bests.fastest_lap.as_ref()),
+                    ];
+                    row.extend((0..sectors.min(3)).map(|i| {
+                        fmt_report_lap(bests.best_sectors.get(i).and_then(|s| s.as_ref()))
+                    }));
+                    row
+                })
+                .collect(),
+        });
+
+        if self.session.session_type == RaceSessionType::Race {
+            let positions = self
+                .lap_chart
+                .iter()
+                .map(|lap| lap.len())
+                .max()
+                .unwrap_or(0);
+            let mut headers = vec!["Lap"];
+            headers.extend(POSITION_HEADERS.iter().take(positions));
+            sections.push(Section {
+                title: "Lap chart".to_string(),
+                headers,
+                rows: self
+                    .lap_chart
+                    .iter()
+                    .enumerate()
+                    // Laps before the connection have no positions.
+                    .filter(|(_, race_numbers)| race_numbers.iter().any(Option::is_some))
+                    .map(|(lap, race_numbers)| {
+                        let mut row = vec![(lap + 1).to_string()];
+                        row.extend(
+                            (0..positions.min(POSITION_HEADERS.len()))
+                                .map(|i| fmt_optional(race_numbers.get(i).copied().flatten())),
+                        );
+                        row
+                    })
+                    .collect(),
+            });
+        }
+
+        sections.push(Section {
+            title: "Pit stops and stints".to_string(),
+            headers: vec![
+                "#", "Stint", "Driver", "Laps", "From lap", "Best lap", "Average",
+            ],
+            rows: self
+                .cars
+                .iter()
+                .flat_map(|car| {
+                    car.stints.iter().enumerate().map(move |(i, stint)| {
+                        vec![
+                            car.race_number.to_string(),
+                            (i + 1).to_string(),
+                            stint.driver.clone(),
+                            stint.laps.to_string(),
+                            stint.first_lap.to_string(),
+                            fmt_lap_ms(stint.best_lap_ms),
+                            fmt_lap_ms(stint.average_lap_ms),
+                        ]
+                    })
+                })
+                .collect(),
+        });
+
+        for (title, incidents) in [
+            ("Penalties", &self.penalties),
+            ("Accidents", &self.accidents),
+        ] {
+            sections.push(Section {
+                title: title.to_string(),
+                headers: vec!["Time", "#", "Message"],
+                rows: incidents
+                    .iter()
+                    .map(|incident| {
+                        vec![
+                            incident.time.to_string(),
+                            fmt_optional(incident.race_number),
+                            incident.message.clone(),
+                        ]
+                    })
+                    .collect(),
+            });
+        }
+
+        sections
+    }
+}
+
+/// Lap chart columns, wider grids are cut.
+const POSITION_HEADERS: [&str; 60] = [
+    "P1", "P2", "P3", "P4", "P5", "P6", "P7", "P8", "P9", "P10", "P11", "P12", "P13", "P14", "P15",
+    "P16", "P17", "P18", "P19", "P20", "P21", "P22", "P23", "P24", "P25", "P26", "P27", "P28",
+    "P29", "P30", "P31", "P32", "P33", "P34", "P35", "P36", "P37", "P38", "P39", "P40", "P41",
+    "P42", "P43", "P44", "P45", "P46", "P47", "P48", "P49", "P50", "P51", "P52", "P53", "P54",
+    "P55", "P56", "P57", "P58", "P59", "P60",
+];
+
+fn report_car(
+    car_info: &ACCDCarInfo,
+    laps: &[ACCDLapInfo],
+    stops: &[ACCDPitStop],
+    car_laps: i32,
+    position: usize,
+) -> ACCDReportCar {
+    // Laps before the client connected are missing, the lap numbers start after them.
+    let first_lap = (car_laps as usize).saturating_sub(laps.len()) + 1;
+
+    let total_time = if first_lap == 1 && !laps.is_empty() {
+        laps.iter().try_fold(SessionTime::ZERO, |total, lap| {
+            Some(total + lap.lap_time()?)
+        })
+    } else {
+        None
+    };
+
+    // Stops count the completed laps, the car was on the next one.
+    let pit_stops: Vec<usize> = stops.iter().map(|stop| stop.lap as usize + 1).collect();
+    let mut stints = Vec::new();
+    let mut stint_start = 0;
+    for i in 0..laps.len() {
+        let in_lap = pit_stops.contains(&(first_lap + i));
+        if in_lap || i + 1 == laps.len() {
+            stints.push(stint(
+                car_info,
+                &laps[stint_start..=i],
+                first_lap + stint_start,
+            ));
+            stint_start = i + 1;
+        }
+    }
+
+    ACCDReportCar {
+        position,
+        class_position: 1,
+        car_class: car_info.car_class(),
+        race_number: car_info.race_number,
+        team_name: car_info.team_name.clone(),
+        car_model: car_info.car_model_name().to_string(),
+        drivers: car_info.drivers.iter().map(driver_name).collect(),
+        laps: car_laps,
+        total_time,
+        best_lap_ms: laps
+            .iter()
+            .filter(|lap| !lap.is_invalid)
+            .filter_map(|lap| lap.lap_time_ms)
+            .min(),
+        gap: None,
+        pit_stops,
+        stints,
+    }
+}
+
+fn stint(car_info: &ACCDCarInfo, laps: &[ACCDLapInfo], first_lap: usize) -> ACCDReportStint {
+    let clean: Vec<i32> = laps
+        .iter()
+        .filter(|lap| !lap.is_invalid && matches!(lap.lap_type, LapType::Regular))
+        .filter_map(|lap| lap.lap_time_ms)
+        .collect();
+
+    ACCDReportStint {
+        driver: lap_driver(car_info, &laps[0]),
+        first_lap,
+        laps: laps.len(),
+        best_lap_ms: clean.iter().copied().min(),
+        average_lap_ms: match clean.len() {
+            0 => None,
+            n => Some((clean.iter().map(|ms| *ms as i64).sum::<i64>() / n as i64) as i32),
+        },
+    }
+}
+
+/// Races are decided by laps and total time, the other sessions by the best lap.
+fn gap(
+    race: bool,
+    winner: (i32, Option<SessionTime>, Option<i32>),
+    car: &ACCDReportCar,
+) -> Option<ACCDReportGap> {
+    let (winner_laps, winner_time, winner_best) = winner;
+    if race {
+        if car.laps < winner_laps {
+            return Some(ACCDReportGap::Laps(winner_laps - car.laps));
+        }
+        Some(ACCDReportGap::Time(car.total_time? - winner_time?))
+    } else {
+        let best = car.best_lap_ms? - winner_best?;
+        Some(ACCDReportGap::Time(SessionTime::from_millis(best as f64)))
+    }
+}
+
+fn class_bests(archive: &ACCDSessionArchive, car_class: CarClass) -> ACCDReportClassBests {
+    let mut bests = ACCDReportClassBests {
+        car_class,
+        fastest_lap: None,
+        best_sectors: Vec::new(),
+    };
+    let faster = |best: &Option<ACCDReportLap>, time_ms: i32| match best {
+        Some(best) => time_ms < best.time_ms,
+        None => true,
+    };
+
+    for standing in archive.standings.iter() {
+        let car_info = &standing.car_info;
+        if car_info.car_class() != car_class {
+            continue;
+        }
+        let laps = match archive.laps.get(&car_info.car_index) {
+            Some(laps) => laps,
+            None => continue,
+        };
+        let first_lap = standing.car_update.as_ref().map_or(0, |car_update| {
+            (car_update.laps as usize).saturating_sub(laps.len())
+        }) + 1;
+
+        for (i, lap) in laps.iter().enumerate().filter(|(_, lap)| !lap.is_invalid) {
+            let report_lap = |time_ms| ACCDReportLap {
+                race_number: car_info.race_number,
+                driver: lap_driver(car_info, lap),
+                lap: first_lap + i,
+                time_ms,
+            };
+
+            if let Some(time_ms) = lap.lap_time_ms {
+                if faster(&bests.fastest_lap, time_ms) {
+                    bests.fastest_lap = Some(report_lap(time_ms));
+                }
+            }
+            if bests.best_sectors.len() < lap.splits.len() {
+                bests.best_sectors.resize(lap.splits.len(), None);
+            }
+            for (sector, split) in lap.splits.iter().enumerate() {
+                if let Some(time_ms) = split {
+                    if faster(&bests.best_sectors[sector], *time_ms) {
+                        bests.best_sectors[sector] = Some(report_lap(*time_ms));
+                    }
+                }
+            }
+        }
+    }
+
+    bests
+}
+
+/// Race numbers of the lap chart of the position history.
+fn lap_chart(archive: &ACCDSessionArchive) -> Vec<Vec<Option<i32>>> {
+    let race_number = |car_index: u16| {
+        archive
+            .standings
+            .iter()
+            .find(|standing| standing.car_info.car_index == car_index)
+            .map(|standing| standing.car_info.race_number)
+    };
+
+    archive
+        .position_history
+        .lap_chart()
+        .into_iter()
+        .map(|lap| {
+            lap.into_iter()
+                .map(|car_index| car_index.and_then(race_number))
+                .collect()
+        })
+        .collect()
+}
+
+fn incidents(
+    broadcasting_events: &[ACCDBroadcastingEvent],
+    event_type: BroadcastingCarEventType,
+) -> Vec<ACCDReportIncident> {
+    broadcasting_events
+        .iter()
+        .filter(|event| event.event_type == event_type)
+        .map(|event| ACCDReportIncident {
+            time: event.event_time,
+            race_number: event
+                .event_car_data
+                .as_ref()
+                .map(|car_info| car_info.race_number),
+            message: event.event_msg.clone(),
+        })
+        .collect()
+}
+
+fn driver_name(driver: &ACCDDriverInfo) -> String {
+    format!("{} {}", driver.first_name, driver.last_name)
+}
+
+fn lap_driver(car_info: &ACCDCarInfo, lap: &ACCDLapInfo) -> String {
+    car_info
+        .drivers
+        .get(lap.driver_index as usize)
+        .map(driver_name)
+        .unwrap_or_default()
+}
+
+fn fmt_lap_ms(lap_time_ms: Option<i32>) -> String {
+    fmt_optional(lap_time_ms.map(|ms| SessionTime::from_millis(ms as f64)))
+}
+
+fn fmt_report_lap(lap: Option<&ACCDReportLap>) -> String {
+    match lap {
+        Some(lap) => format!(
+            "{} #{} {} (lap {})",
+            SessionTime::from_millis(lap.time_ms as f64),
+            lap.race_number,
+            lap.driver,
+            lap.lap
+        ),
+        None => "-".to_string(),
+    }
+}
+
+fn fmt_optional<T: fmt::Display>(value: Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lap(lap_time_ms: i32) -> ACCDLapInfo {
+        ACCDLapInfo {
+            lap_time_ms: Some(lap_time_ms),
+            lap_type: LapType::Regular,
+            ..ACCDLapInfo::default()
+        }
+    }
+
+    #[test]
+    fn stints_end_with_the_laps_of_the_pit_stops() {
+        let laps = vec![
+            lap(90_000),
+            lap(91_000),
+            lap(110_000),
+            lap(115_000),
+            lap(89_000),
+        ];
+        let stops = vec![ACCDPitStop {
+            lap: 2,
+            entry_time: SessionTime::from_secs(270.0),
+            exit_time: SessionTime::from_secs(300.0),
+        }];
+
+        let car = report_car(&ACCDCarInfo::new(1), &laps, &stops, 5, 1);
+        assert_eq!(car.pit_stops, vec![3]);
+        assert_eq!(car.stints.len(), 2);
+        assert_eq!((car.stints[0].first_lap, car.stints[0].laps), (1, 3));
+        assert_eq!((car.stints[1].first_lap, car.stints[1].laps), (4, 2));
+        assert_eq!(car.total_time, Some(SessionTime::from_secs(495.0)));
+        assert_eq!(car.best_lap_ms, Some(89_000));
+    }
+
+    #[test]
+    fn lap_numbers_start_after_the_missing_laps() {
+        let laps = vec![lap(90_000), lap(110_000), lap(90_000)];
+        let stops = vec![ACCDPitStop {
+            lap: 11,
+            entry_time: SessionTime::ZERO,
+            exit_time: SessionTime::ZERO,
+        }];
+
+        let car = report_car(&ACCDCarInfo::new(1), &laps, &stops, 13, 1);
+        assert_eq!(car.pit_stops, vec![12]);
+        assert_eq!(car.stints[0].first_lap, 11);
+        assert_eq!(car.stints[0].laps, 2);
+        assert_eq!(car.total_time, None);
+    }
+}
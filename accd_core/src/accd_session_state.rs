@@ -0,0 +1,746 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::accd_broadcasting_event::ACCDBroadcastingEvent;
+use crate::accd_car_info::ACCDCarInfo;
+use crate::accd_conditions::ACCDConditionHistory;
+use crate::accd_grid::{ACCDGrid, ACCDGridEvent, ACCDPositionChange};
+use crate::accd_lap_info::ACCDLapInfo;
+use crate::accd_pace::{ACCDCarPace, ACCDPaceComparison};
+use crate::accd_pit_strategy::{ACCDCarStrategy, ACCDPitCycle, ACCDPitEvent, ACCDPitStop};
+use crate::accd_position_history::ACCDPositionHistory;
+use crate::accd_protocol::ListenResult;
+use crate::accd_realtime_car_update::{ACCDRealtimeCarUpdate, CarLocationEnum};
+use crate::accd_realtime_update::{ACCDRealtimeUpdate, RaceSessionType, SessionPhase};
+use crate::accd_registration_result::ACCDRegistrationResult;
+use crate::accd_report::ACCDReport;
+use crate::accd_session_time::SessionTime;
+use crate::accd_session_tracker::{ACCDSessionEvent, ACCDSessionInfo};
+use crate::accd_speed_trap::ACCDSpeedStats;
+use crate::accd_spline_model::ACCDSplineModel;
+use crate::accd_track_data::ACCDTrackData;
+use crate::accd_track_map::{ACCDMapCar, ACCDTrackMap};
+
+/// A car of the entry list together with its latest realtime update.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDStanding {
+    pub car_info: ACCDCarInfo,
+    pub car_update: Option<ACCDRealtimeCarUpdate>,
+    /// On track gap to the car one position ahead, in races only.
+    pub gap_ahead: Option<SessionTime>,
+    pub gap_to_leader: Option<SessionTime>,
+}
+
+/// How much of the session is left.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDRemaining {
+    Time(SessionTime),
+    /// Laps the leader still has to start, the current one included.
+    Laps(i32),
+    /// No session, or a lap-limited race without `race_laps`.
+    Unknown,
+}
+
+/// What was left of a session when it ended.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDSessionArchive {
+    pub session: ACCDSessionInfo,
+    pub realtime_update: ACCDRealtimeUpdate,
+    pub standings: Vec<ACCDStanding>,
+    pub laps: HashMap<u16, Vec<ACCDLapInfo>>,
+    pub position_history: ACCDPositionHistory,
+    pub condition_history: ACCDConditionHistory,
+    pub speed_stats: ACCDSpeedStats,
+    pub grid: Option<ACCDGrid>,
+    pub broadcasting_events: Vec<ACCDBroadcastingEvent>,
+    pub pit_stops: HashMap<u16, Vec<ACCDPitStop>>,
+    pub pit_cycles: Vec<ACCDPitCycle>,
+}
+
+/// Everything received from ACC so far, kept up to date by feeding it every `ListenResult`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDSessionState {
+    pub registration_result: ACCDRegistrationResult,
+    pub track_data: ACCDTrackData,
+    pub realtime_update: ACCDRealtimeUpdate,
+    pub entry_list_cars: Vec<ACCDCarInfo>,
+    pub car_updates: HashMap<u16, ACCDRealtimeCarUpdate>,
+    pub broadcasting_events: Vec<ACCDBroadcastingEvent>,
+    /// Completed laps of the current session by car index, oldest first.
+    pub laps: HashMap<u16, Vec<ACCDLapInfo>>,
+    /// Positions of the cars through the current session.
+    pub position_history: ACCDPositionHistory,
+    /// Weather and track conditions through the current session.
+    pub condition_history: ACCDConditionHistory,
+    /// Speed traps and top speeds of the current session.
+    pub speed_stats: ACCDSpeedStats,
+    /// Starting grid of the current race, once its formation lap is over.
+    pub grid: Option<ACCDGrid>,
+    /// Pit stops of the current session by car index, oldest first.
+    pub pit_stops: HashMap<u16, Vec<ACCDPitStop>>,
+    /// Undercuts and overcuts of the current session.
+    pub pit_cycles: Vec<ACCDPitCycle>,
+    /// Time a stop costs on the current track, learned from the stops seen.
+    pub pit_loss: Option<SessionTime>,
+    /// Previous sessions, oldest first.
+    pub archived_sessions: Vec<ACCDSessionArchive>,
+    /// Length of lap-limited races, ACC doesn't broadcast it.
+    pub race_laps: Option<i32>,
+    /// Outline of the current track, learned from the car positions.
+    pub track_map: ACCDTrackMap,
+    /// Time needed to reach every part of the current track, learned from clean laps.
+    pub spline_model: ACCDSplineModel,
+    /// Where track maps and spline models are loaded from and saved to, they are not kept without it.
+    pub track_maps_dir: Option<PathBuf>,
+    /// Where the report of every finished session is written, no reports without it.
+    pub reports_dir: Option<PathBuf>,
+    /// Lap count that ends a timed race, known once its time is over.
+    final_lap: Option<i32>,
+    /// Session in progress, `None` once it's archived.
+    session: Option<ACCDSessionInfo>,
+}
+
+impl ACCDSessionState {
+    pub fn new() -> Self {
+        ACCDSessionState::default()
+    }
+
+    pub fn update(&mut self, listen_result: &ListenResult) {
+        match listen_result {
+            ListenResult::RegistrationResult(reg_result) => {
+                self.registration_result = reg_result.clone();
+            }
+            ListenResult::RealTimeUpdate(rtu) => {
+                self.realtime_update = rtu.clone();
+                self.position_history.realtime_update(rtu.session_time);
+                self.condition_history.realtime_update(rtu);
+
+                // The race ends when the leader completes the lap it's on when the time runs out.
+                let time_over = rtu.session_type == RaceSessionType::Race
+                    && rtu.session_end_time > SessionTime::ZERO
+                    && rtu.session_remaining_time == SessionTime::ZERO
+                    && (rtu.phase == SessionPhase::Session
+                        || rtu.phase == SessionPhase::SessionOver);
+                if time_over && self.final_lap.is_none() {
+                    self.final_lap = Some(self.leader().map_or(0, |leader| leader.laps) + 1);
+                }
+            }
+            ListenResult::RealTimeCarUpdate(rt_car_update) => {
+                let was_complete = self.track_map.is_complete();
+                self.track_map.car_update(rt_car_update);
+                if !was_complete && self.track_map.is_complete() {
+                    self.save_track_map();
+                }
+                let was_complete = self.spline_model.is_complete();
+                if self.spline_model.car_update(rt_car_update)
+                    && !was_complete
+                    && self.spline_model.is_complete()
+                {
+                    self.save_spline_model();
+                }
+
+                self.position_history.car_update(rt_car_update);
+                self.condition_history.car_update(rt_car_update);
+                self.speed_stats.car_update(rt_car_update);
+
+                let car_index = rt_car_update.car_index as u16;
+                let lap_completed = self
+                    .car_updates
+                    .get(&car_index)
+                    .is_some_and(|previous| rt_car_update.laps > previous.laps);
+                if lap_completed {
+                    self.laps
+                        .entry(car_index)
+                        .or_default()
+                        .push(rt_car_update.last_lap.clone());
+                }
+
+                self.car_updates.insert(car_index, rt_car_update.clone());
+            }
+            ListenResult::EntryList(entry_list_cars) => {
+                self.entry_list_cars = entry_list_cars.clone();
+                self.car_updates.retain(|car_index, _| {
+                    entry_list_cars
+                        .iter()
+                        .any(|car_info| car_info.car_index == *car_index)
+                });
+            }
+            ListenResult::TrackData(trk_data) => {
+                if trk_data.track_id() != self.track_data.track_id() {
+                    self.pit_loss = None;
+                }
+                self.track_data = trk_data.clone();
+                if trk_data.track_id() != self.track_map.track_id {
+                    self.load_track_map();
+                }
+                if trk_data.track_id() != self.spline_model.track_id {
+                    self.load_spline_model();
+                }
+            }
+            ListenResult::EntryListCar(car_info) => {
+                if let Some(car) = self
+                    .entry_list_cars
+                    .iter_mut()
+                    .find(|car| car.car_index == car_info.car_index)
+                {
+                    *car = car_info.clone();
+                }
+            }
+            ListenResult::BroadcastingEvent(broadcasting_event) => {
+                self.broadcasting_events.push(broadcasting_event.clone());
+            }
+            ListenResult::Session(ACCDSessionEvent::SessionStarted(session)) => {
+                self.start_session(session.clone());
+            }
+            ListenResult::Session(ACCDSessionEvent::SessionEnded(_)) => {
+                self.end_session();
+            }
+            ListenResult::Grid(ACCDGridEvent::GridCaptured(grid)) => {
+                self.position_history
+                    .set_start_positions(grid.start_positions());
+                self.grid = Some(grid.clone());
+            }
+            ListenResult::Pit(ACCDPitEvent::PitExit { car_index, stop }) => {
+                self.pit_stops
+                    .entry(*car_index)
+                    .or_default()
+                    .push(stop.clone());
+            }
+            ListenResult::Pit(ACCDPitEvent::PitLoss { track_pit_loss, .. }) => {
+                self.pit_loss = Some(*track_pit_loss);
+            }
+            ListenResult::Pit(ACCDPitEvent::PitCycle(pit_cycle)) => {
+                self.pit_cycles.push(pit_cycle.clone());
+            }
+            ListenResult::Conditions(event) => {
+                self.condition_history.condition_event(event);
+            }
+            ListenResult::SpeedTrap(event) => {
+                self.speed_stats.speed_event(event);
+            }
+            ListenResult::Session(_)
+            | ListenResult::Grid(_)
+            | ListenResult::Pit(_)
+            | ListenResult::EntryListSync(_)
+            | ListenResult::Error => {}
+        }
+    }
+
+    /// Car update of the race leader.
+    pub fn leader(&self) -> Option<&ACCDRealtimeCarUpdate> {
+        self.car_updates
+            .values()
+            .find(|car_update| car_update.position == 1)
+    }
+
+    /// Time left in timed sessions, laps left to the leader in lap-limited races
+    /// and in timed races once their time is over.
+    pub fn remaining(&self) -> ACCDRemaining {
+        let realtime_update = &self.realtime_update;
+        let race_laps = match realtime_update.session_type {
+            RaceSessionType::Race => self.race_laps.or(self.final_lap),
+            _ => None,
+        };
+
+        if let Some(race_laps) = race_laps {
+            let leader_laps = self.leader().map_or(0, |leader| leader.laps);
+            return ACCDRemaining::Laps((race_laps - leader_laps).max(0));
+        }
+
+        if realtime_update.session_end_time <= SessionTime::ZERO {
+            return ACCDRemaining::Unknown;
+        }
+
+        ACCDRemaining::Time(realtime_update.session_remaining_time)
+    }
+
+    /// Cars with a realtime update, to be drawn on the track map.
+    pub fn map_cars(&self) -> Vec<ACCDMapCar> {
+        self.entry_list_cars
+            .iter()
+            .filter_map(|car_info| {
+                let car_update = self.car_updates.get(&car_info.car_index)?;
+                Some(ACCDMapCar {
+                    x: car_update.world_pos_x,
+                    y: car_update.world_pos_y,
+                    race_number: car_info.race_number,
+                    car_class: car_info.car_class(),
+                    focused: self.realtime_update.focused_car_index == Some(car_info.car_index),
+                })
+            })
+            .collect()
+    }
+
+    /// Saves the track map to `track_maps_dir`.
+    pub fn save_track_map(&self) {
+        if let Some(dir) = &self.track_maps_dir {
+            if self.track_map.track_id >= 0 && !self.track_map.is_empty() {
+                if let Err(e) = self.track_map.save(dir) {
+                    println!("ERROR({}): {}", dir.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Saves the spline model to `track_maps_dir`.
+    pub fn save_spline_model(&self) {
+        if let Some(dir) = &self.track_maps_dir {
+            if self.spline_model.track_id >= 0 && !self.spline_model.is_empty() {
+                if let Err(e) = self.spline_model.save(dir) {
+                    println!("ERROR({}): {}", dir.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Archives the session in progress and writes its report, e.g. before exiting
+    /// or disconnecting. The track models are saved even without a session.
+    pub fn end_session(&mut self) {
+        match self.session.take() {
+            Some(session) => self.archive_session(session),
+            None => self.save_track_models(),
+        }
+    }
+
+    /// Saves what was learned about the current track.
+    pub fn save_track_models(&self) {
+        self.save_track_map();
+        self.save_spline_model();
+    }
+
+    /// Switches to the map of the current track, the saved one if there's one.
+    fn load_track_map(&mut self) {
+        // The map of the previous track is kept for the next time.
+        self.save_track_map();
+
+        let track_id = self.track_data.track_id();
+        let saved = match &self.track_maps_dir {
+            Some(dir) => ACCDTrackMap::load(dir, track_id).unwrap_or_else(|e| {
+                println!(
+                    "ERROR({}): {}",
+                    ACCDTrackMap::file_path(dir, track_id).display(),
+                    e
+                );
+                None
+            }),
+            None => None,
+        };
+
+        self.track_map =
+            saved.unwrap_or_else(|| ACCDTrackMap::new(track_id, &self.track_data.track_name));
+    }
+
+    /// Switches to the spline model of the current track, the saved one if there's one.
+    fn load_spline_model(&mut self) {
+        self.save_spline_model();
+
+        let track_id = self.track_data.track_id();
+        let saved = match &self.track_maps_dir {
+            Some(dir) => ACCDSplineModel::load(dir, track_id).unwrap_or_else(|e| {
+                println!(
+                    "ERROR({}): {}",
+                    ACCDSplineModel::file_path(dir, track_id).display(),
+                    e
+                );
+                None
+            }),
+            None => None,
+        };
+
+        self.spline_model =
+            saved.unwrap_or_else(|| ACCDSplineModel::new(track_id, &self.track_data.track_name));
+    }
+
+    /// Time the car behind needs to get where the car ahead is, at the pace of its best lap
+    /// (or the usual lap time of the track before it has one). `None` without a lap time.
+    pub fn gap(
+        &self,
+        ahead: &ACCDRealtimeCarUpdate,
+        behind: &ACCDRealtimeCarUpdate,
+    ) -> Option<SessionTime> {
+        let lap_time = behind
+            .best_session_lap
+            .lap_time()
+            .or(self.spline_model.lap_time)?;
+        Some(self.spline_model.gap(ahead, behind, lap_time))
+    }
+
+    /// Completed laps of a car in the current session.
+    pub fn lap_history(&self, car_index: u16) -> &[ACCDLapInfo] {
+        self.laps
+            .get(&car_index)
+            .map_or(&[], |laps| laps.as_slice())
+    }
+
+    /// Positions gained and lost since the start of the race, overall and in class,
+    /// empty without a grid.
+    pub fn position_changes(&self) -> Vec<ACCDPositionChange> {
+        let positions: HashMap<u16, i32> = self
+            .car_updates
+            .iter()
+            .map(|(car_index, car_update)| (*car_index, car_update.position))
+            .collect();
+        self.grid
+            .as_ref()
+            .map_or(Vec::new(), |grid| grid.position_changes(&positions))
+    }
+
+    /// Pace of a car in every stint of the session, `rolling_laps` are averaged for the
+    /// rolling average.
+    pub fn pace(&self, car_index: u16, rolling_laps: usize) -> ACCDCarPace {
+        ACCDCarPace::new(car_index, self.lap_history(car_index), rolling_laps)
+    }
+
+    /// Current stint of a car against the cars of its class on the same strategy,
+    /// i.e. with as many pit stops.
+    pub fn pace_comparisons(&self, car_index: u16, rolling_laps: usize) -> Vec<ACCDPaceComparison> {
+        let car_class = match self.car_info(car_index) {
+            Some(car_info) => car_info.car_class(),
+            None => return Vec::new(),
+        };
+        let pace = self.pace(car_index, rolling_laps);
+
+        self.entry_list_cars
+            .iter()
+            .filter(|other| other.car_index != car_index && other.car_class() == car_class)
+            .map(|other| self.pace(other.car_index, rolling_laps))
+            .filter(|other| other.pit_stops == pace.pit_stops)
+            .map(|other| ACCDPaceComparison::new(&pace, &other))
+            .collect()
+    }
+
+    /// Stops of every car and, in races, where it would rejoin if it stopped now,
+    /// in position order.
+    pub fn strategy(&self) -> Vec<ACCDCarStrategy> {
+        let standings = self.standings();
+        let race = self.realtime_update.session_type == RaceSessionType::Race;
+        let gap_to_leader = |standing: &ACCDStanding| match &standing.car_update {
+            Some(car_update) if car_update.position == 1 => Some(SessionTime::ZERO),
+            _ => standing.gap_to_leader,
+        };
+
+        standings
+            .iter()
+            .filter_map(|standing| {
+                let car_update = standing.car_update.as_ref()?;
+                let car_index = standing.car_info.car_index;
+                let stops = self.pit_stops.get(&car_index).cloned().unwrap_or_default();
+                let laps_since_stop = car_update.laps - stops.last().map_or(0, |stop| stop.lap);
+
+                // Behind the cars that would be ahead once the stop is done.
+                let rejoin = match (race, self.pit_loss, gap_to_leader(standing)) {
+                    (true, Some(pit_loss), Some(gap)) => {
+                        let rejoin_gap = gap + pit_loss;
+                        let ahead: Vec<SessionTime> = standings
+                            .iter()
+                            .filter(|other| other.car_info.car_index != car_index)
+                            .filter_map(gap_to_leader)
+                            .filter(|other_gap| *other_gap < rejoin_gap)
+                            .collect();
+                        let closest = ahead
+                            .iter()
+                            .copied()
+                            .reduce(|a, b| if b > a { b } else { a });
+                        Some((
+                            ahead.len() as i32 + 1,
+                            closest.map(|closest| rejoin_gap - closest),
+                        ))
+                    }
+                    _ => None,
+                };
+
+                Some(ACCDCarStrategy {
+                    car_index,
+                    race_number: standing.car_info.race_number,
+                    position: car_update.position,
+                    stops,
+                    laps_since_stop,
+                    in_pit_lane: matches!(
+                        car_update.car_location,
+                        CarLocationEnum::PitEntry
+                            | CarLocationEnum::Pitlane
+                            | CarLocationEnum::PitExit
+                    ),
+                    rejoin_position: rejoin.map(|(position, _)| position),
+                    rejoin_gap_ahead: rejoin.and_then(|(_, gap_ahead)| gap_ahead),
+                })
+            })
+            .collect()
+    }
+
+    /// The current session as it would be archived if it ended now.
+    pub fn session_archive(&self) -> ACCDSessionArchive {
+        let realtime_update = &self.realtime_update;
+        ACCDSessionArchive {
+            session: ACCDSessionInfo {
+                event_index: realtime_update.event_index(),
+                session_index: realtime_update.session_index(),
+                session_type: realtime_update.session_type.clone(),
+            },
+            realtime_update: realtime_update.clone(),
+            standings: self.standings(),
+            laps: self.laps.clone(),
+            position_history: self.position_history.clone(),
+            condition_history: self.condition_history.clone(),
+            speed_stats: self.speed_stats.clone(),
+            grid: self.grid.clone(),
+            broadcasting_events: self.broadcasting_events.clone(),
+            pit_stops: self.pit_stops.clone(),
+            pit_cycles: self.pit_cycles.clone(),
+        }
+    }
+
+    /// Report of a session archive, on the current track.
+    pub fn report(&self, archive: &ACCDSessionArchive) -> ACCDReport {
+        ACCDReport::new(archive, &self.track_data.track_name)
+    }
+
+    /// Copies the per-session data to `archived_sessions` and writes the report,
+    /// the data stays on display until the next session starts.
+    fn archive_session(&mut self, session: ACCDSessionInfo) {
+        let archive = ACCDSessionArchive {
+            session,
+            ..self.session_archive()
+        };
+        if let Some(dir) = &self.reports_dir {
+            match self.report(&archive).save(dir) {
+                Ok(paths) => println!("Session report: {}", paths[0].display()),
+                Err(e) => println!("ERROR({}): {}", dir.display(), e),
+            }
+        }
+        self.archived_sessions.push(archive);
+        self.save_track_models();
+    }
+
+    /// Clears the per-session data, entry list and track data are kept for the new session.
+    fn start_session(&mut self, session: ACCDSessionInfo) {
+        self.laps.clear();
+        self.speed_stats = ACCDSpeedStats::default();
+        self.grid = None;
+        self.broadcasting_events.clear();
+        self.pit_stops.clear();
+        self.pit_cycles.clear();
+        self.position_history.reset();
+        self.condition_history.reset();
+        self.realtime_update = ACCDRealtimeUpdate::default();
+        self.car_updates.clear();
+        self.final_lap = None;
+        self.session = Some(session);
+    }
+
+    pub fn car_info(&self, car_index: u16) -> Option<&ACCDCarInfo> {
+        self.entry_list_cars
+            .iter()
+            .find(|car_info| car_info.car_index == car_index)
+    }
+
+    pub fn car_by_race_number(&self, race_number: i32) -> Option<&ACCDCarInfo> {
+        self.entry_list_cars
+            .iter()
+            .find(|car_info| car_info.race_number == race_number)
+    }
+
+    /// Entry list sorted by race position, cars without a realtime update yet come last.
+    pub fn standings(&self) -> Vec<ACCDStanding> {
+        let mut standings: Vec<ACCDStanding> = self
+            .entry_list_cars
+            .iter()
+            .map(|car_info| ACCDStanding {
+                car_info: car_info.clone(),
+                car_update: self.car_updates.get(&car_info.car_index).cloned(),
+                gap_ahead: None,
+                gap_to_leader: None,
+            })
+            .collect();
+
+        standings.sort_by_key(|standing| match &standing.car_update {
+            Some(car_update) if car_update.position > 0 => car_update.position,
+            _ => i32::MAX,
+        });
+
+        // Outside races the positions come from the lap times, not from the track.
+        if self.realtime_update.session_type == RaceSessionType::Race {
+            let leader = self.leader().cloned();
+            for i in 1..standings.len() {
+                let car_update = match &standings[i].car_update {
+                    Some(car_update) if car_update.position > 1 => car_update.clone(),
+                    _ => continue,
+                };
+                if let Some(ahead) = &standings[i - 1].car_update {
+                    standings[i].gap_ahead = self.gap(ahead, &car_update);
+                }
+                if let Some(leader) = &leader {
+                    standings[i].gap_to_leader = self.gap(leader, &car_update);
+                }
+            }
+        }
+
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(session_index: i32) -> ACCDSessionInfo {
+        ACCDSessionInfo {
+            event_index: 0,
+            session_index,
+            session_type: RaceSessionType::Race,
+        }
+    }
+
+    fn race_update(end_secs: f64, remaining_secs: f64, phase: SessionPhase) -> ListenResult {
+        ListenResult::RealTimeUpdate(ACCDRealtimeUpdate {
+            session_type: RaceSessionType::Race,
+            phase,
+            session_end_time: SessionTime::from_secs(end_secs),
+            session_remaining_time: SessionTime::from_secs(remaining_secs),
+            ..ACCDRealtimeUpdate::default()
+        })
+    }
+
+    fn set_leader_laps(state: &mut ACCDSessionState, laps: i32) {
+        state.car_updates.insert(
+            1,
+            ACCDRealtimeCarUpdate {
+                car_index: 1,
+                position: 1,
+                laps,
+                ..ACCDRealtimeCarUpdate::default()
+            },
+        );
+    }
+
+    #[test]
+    fn sessions_are_archived_once() {
+        let mut state = ACCDSessionState::new();
+        state.update(&ListenResult::Session(ACCDSessionEvent::SessionStarted(
+            info(0),
+        )));
+        state.laps.insert(3, vec![ACCDLapInfo::default()]);
+        state.update(&ListenResult::Pit(ACCDPitEvent::PitExit {
+            car_index: 3,
+            stop: ACCDPitStop {
+                lap: 0,
+                entry_time: SessionTime::ZERO,
+                exit_time: SessionTime::from_secs(30.0),
+            },
+        }));
+
+        state.update(&ListenResult::Session(ACCDSessionEvent::SessionEnded(
+            info(0),
+        )));
+        assert_eq!(state.archived_sessions.len(), 1);
+        assert_eq!(state.archived_sessions[0].laps[&3].len(), 1);
+        assert_eq!(state.archived_sessions[0].pit_stops[&3].len(), 1);
+        // Still on display until the next session starts.
+        assert_eq!(state.laps.len(), 1);
+
+        state.end_session();
+        assert_eq!(state.archived_sessions.len(), 1);
+
+        state.update(&ListenResult::Session(ACCDSessionEvent::SessionStarted(
+            info(1),
+        )));
+        assert!(state.laps.is_empty());
+        assert!(state.pit_stops.is_empty());
+    }
+
+    #[test]
+    fn ending_archives_the_session_in_progress() {
+        let mut state = ACCDSessionState::new();
+        state.end_session();
+        assert!(state.archived_sessions.is_empty());
+
+        state.update(&ListenResult::Session(ACCDSessionEvent::SessionStarted(
+            info(2),
+        )));
+        state.end_session();
+        assert_eq!(state.archived_sessions.len(), 1);
+        assert_eq!(state.archived_sessions[0].session, info(2));
+    }
+
+    #[test]
+    fn remaining_outside_races() {
+        let mut state = ACCDSessionState::new();
+        assert_eq!(state.remaining(), ACCDRemaining::Unknown);
+
+        // Lap-limited races don't make the other sessions count laps.
+        state.race_laps = Some(20);
+        state.update(&ListenResult::RealTimeUpdate(ACCDRealtimeUpdate {
+            session_type: RaceSessionType::Qualifying,
+            phase: SessionPhase::Session,
+            session_end_time: SessionTime::from_secs(900.0),
+            session_remaining_time: SessionTime::from_secs(300.0),
+            ..ACCDRealtimeUpdate::default()
+        }));
+        assert_eq!(
+            state.remaining(),
+            ACCDRemaining::Time(SessionTime::from_secs(300.0))
+        );
+    }
+
+    #[test]
+    fn remaining_laps_of_lap_limited_races() {
+        let mut state = ACCDSessionState::new();
+        state.race_laps = Some(20);
+        state.update(&race_update(0.0, 0.0, SessionPhase::Session));
+        assert_eq!(state.remaining(), ACCDRemaining::Laps(20));
+
+        set_leader_laps(&mut state, 5);
+        assert_eq!(state.remaining(), ACCDRemaining::Laps(15));
+        set_leader_laps(&mut state, 21);
+        assert_eq!(state.remaining(), ACCDRemaining::Laps(0));
+    }
+
+    #[test]
+    fn timed_races_count_laps_once_the_time_is_over() {
+        let mut state = ACCDSessionState::new();
+        set_leader_laps(&mut state, 12);
+
+        // Before the start the remaining time is shown even when it's zero.
+        state.update(&race_update(3600.0, 0.0, SessionPhase::PreSession));
+        assert_eq!(state.remaining(), ACCDRemaining::Time(SessionTime::ZERO));
+
+        state.update(&race_update(3600.0, 60.0, SessionPhase::Session));
+        assert_eq!(
+            state.remaining(),
+            ACCDRemaining::Time(SessionTime::from_secs(60.0))
+        );
+
+        // The leader finishes the lap it's on.
+        state.update(&race_update(3600.0, 0.0, SessionPhase::Session));
+        assert_eq!(state.remaining(), ACCDRemaining::Laps(1));
+        set_leader_laps(&mut state, 13);
+        state.update(&race_update(3600.0, 0.0, SessionPhase::Session));
+        assert_eq!(state.remaining(), ACCDRemaining::Laps(0));
+        state.update(&race_update(3600.0, 0.0, SessionPhase::SessionOver));
+        assert_eq!(state.remaining(), ACCDRemaining::Laps(0));
+
+        // The next session has its own final lap.
+        state.update(&ListenResult::Session(ACCDSessionEvent::SessionStarted(
+            info(1),
+        )));
+        set_leader_laps(&mut state, 13);
+        state.update(&race_update(3600.0, 60.0, SessionPhase::Session));
+        assert_eq!(
+            state.remaining(),
+            ACCDRemaining::Time(SessionTime::from_secs(60.0))
+        );
+    }
+
+    #[test]
+    fn race_laps_win_over_the_final_lap() {
+        let mut state = ACCDSessionState::new();
+        state.race_laps = Some(30);
+        set_leader_laps(&mut state, 12);
+        state.update(&race_update(3600.0, 0.0, SessionPhase::Session));
+        assert_eq!(state.remaining(), ACCDRemaining::Laps(18));
+    }
+}
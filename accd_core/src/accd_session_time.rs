@@ -0,0 +1,145 @@
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+use std::time::Duration;
+
+/// A time of the session clock, or a span of it, in milliseconds as sent by ACC.
+/// Unlike `Duration` it keeps fractions of a millisecond and can be negative,
+/// e.g. the countdown before the session starts.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct SessionTime(f64);
+
+impl SessionTime {
+    pub const ZERO: SessionTime = SessionTime(0.0);
+
+    pub fn from_millis(millis: f64) -> Self {
+        SessionTime(millis)
+    }
+
+    pub fn from_secs(secs: f64) -> Self {
+        SessionTime(secs * 1000.0)
+    }
+
+    pub fn as_millis(self) -> f64 {
+        self.0
+    }
+
+    pub fn as_secs(self) -> f64 {
+        self.0 / 1000.0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0.0
+    }
+
+    /// `None` for negative times.
+    pub fn to_duration(self) -> Option<Duration> {
+        if self.is_negative() {
+            None
+        } else {
+            Some(Duration::from_secs_f64(self.as_secs()))
+        }
+    }
+
+    /// `self - other`, zero instead of negative.
+    pub fn saturating_sub(self, other: SessionTime) -> SessionTime {
+        SessionTime((self.0 - other.0).max(0.0))
+    }
+}
+
+impl From<Duration> for SessionTime {
+    fn from(duration: Duration) -> Self {
+        SessionTime::from_secs(duration.as_secs_f64())
+    }
+}
+
+impl Add for SessionTime {
+    type Output = SessionTime;
+
+    fn add(self, other: SessionTime) -> SessionTime {
+        SessionTime(self.0 + other.0)
+    }
+}
+
+impl Sub for SessionTime {
+    type Output = SessionTime;
+
+    fn sub(self, other: SessionTime) -> SessionTime {
+        SessionTime(self.0 - other.0)
+    }
+}
+
+impl Neg for SessionTime {
+    type Output = SessionTime;
+
+    fn neg(self) -> SessionTime {
+        SessionTime(-self.0)
+    }
+}
+
+/// `mm:ss.sss`, minutes keep counting past the hour.
+impl fmt::Display for SessionTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = self.0.abs().round() as u64;
+        let sign = if self.is_negative() && millis > 0 {
+            "-"
+        } else {
+            ""
+        };
+
+        write!(
+            f,
+            "{}{:02}:{:02}.{:03}",
+            sign,
+            millis / 60_000,
+            (millis / 1000) % 60,
+            millis % 1000
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_counts_minutes_past_the_hour() {
+        assert_eq!(SessionTime::ZERO.to_string(), "00:00.000");
+        assert_eq!(SessionTime::from_millis(83_456.4).to_string(), "01:23.456");
+        assert_eq!(SessionTime::from_secs(3725.0).to_string(), "62:05.000");
+        assert_eq!(SessionTime::from_millis(-1500.0).to_string(), "-00:01.500");
+        // Rounded to zero, no sign.
+        assert_eq!(SessionTime::from_millis(-0.2).to_string(), "00:00.000");
+    }
+
+    #[test]
+    fn arithmetic_keeps_fractions_and_signs() {
+        let a = SessionTime::from_millis(1000.25);
+        let b = SessionTime::from_millis(1500.5);
+
+        assert_eq!((a + b).as_millis(), 2500.75);
+        assert_eq!((a - b).as_millis(), -500.25);
+        assert_eq!((-a).as_millis(), -1000.25);
+        assert_eq!(a.saturating_sub(b), SessionTime::ZERO);
+        assert_eq!(b.saturating_sub(a).as_millis(), 500.25);
+        assert_eq!(SessionTime::from_secs(1.5).as_millis(), 1500.0);
+        assert!(a < b);
+    }
+
+    #[test]
+    fn durations_are_never_negative() {
+        assert_eq!(SessionTime::from_millis(-1.0).to_duration(), None);
+        assert_eq!(
+            SessionTime::from_millis(2500.0).to_duration(),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(
+            SessionTime::from(Duration::from_millis(750)).as_millis(),
+            750.0
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+
+use crate::accd_realtime_update::{ACCDRealtimeUpdate, RaceSessionType, SessionPhase};
+
+/// Identifies a session of an ACC event.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDSessionInfo {
+    pub event_index: i32,
+    pub session_index: i32,
+    pub session_type: RaceSessionType,
+}
+
+impl fmt::Display for ACCDSessionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} (event {}, session {})",
+            self.session_type, self.event_index, self.session_index
+        )
+    }
+}
+
+/// Session lifecycle changes reported by `ACCDSessionTracker`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDSessionEvent {
+    /// The first realtime update of a session arrived.
+    SessionStarted(ACCDSessionInfo),
+    SessionPhaseChanged {
+        previous: SessionPhase,
+        phase: SessionPhase,
+    },
+    /// The session reached `PostSession`, once every car finished, or another session
+    /// started before that. Reported once per session.
+    SessionEnded(ACCDSessionInfo),
+    /// ACC loaded another event (e.g. a new race weekend), reported after
+    /// the `SessionEnded` of the old session and before the `SessionStarted` of the new one.
+    EventChanged { previous: i32, event_index: i32 },
+}
+
+/// Follows `event_index`, `session_index` and `phase` of the realtime updates
+/// and reports when they change.
+#[derive(Debug, Clone, Default)]
+pub struct ACCDSessionTracker {
+    session: Option<ACCDSessionInfo>,
+    phase: Option<SessionPhase>,
+    /// `SessionEnded` was reported for the current session.
+    ended: bool,
+}
+
+impl ACCDSessionTracker {
+    pub fn new() -> Self {
+        ACCDSessionTracker::default()
+    }
+
+    /// Current session, `None` before the first realtime update.
+    pub fn session(&self) -> Option<&ACCDSessionInfo> {
+        self.session.as_ref()
+    }
+
+    pub fn phase(&self) -> Option<&SessionPhase> {
+        self.phase.as_ref()
+    }
+
+    /// Events caused by the realtime update, in the order they happened.
+    pub fn realtime_update(
+        &mut self,
+        realtime_update: &ACCDRealtimeUpdate,
+    ) -> Vec<ACCDSessionEvent> {
+        let mut events = Vec::new();
+
+        let session = ACCDSessionInfo {
+            event_index: realtime_update.event_index(),
+            session_index: realtime_update.session_index(),
+            session_type: realtime_update.session_type.clone(),
+        };
+
+        let same_session = self.session.as_ref().is_some_and(|current| {
+            current.event_index == session.event_index
+                && current.session_index == session.session_index
+        });
+
+        if !same_session {
+            if let Some(previous) = self.session.take() {
+                let previous_event = previous.event_index;
+                if !self.ended {
+                    events.push(ACCDSessionEvent::SessionEnded(previous));
+                }
+                if previous_event != session.event_index {
+                    events.push(ACCDSessionEvent::EventChanged {
+                        previous: previous_event,
+                        event_index: session.event_index,
+                    });
+                }
+            }
+            events.push(ACCDSessionEvent::SessionStarted(session.clone()));
+            self.session = Some(session);
+            self.phase = None;
+            self.ended = false;
+        }
+
+        // Every session goes through its phases from NONE.
+        let previous = self.phase.clone().unwrap_or(SessionPhase::NONE);
+        if previous != realtime_update.phase {
+            events.push(ACCDSessionEvent::SessionPhaseChanged {
+                previous,
+                phase: realtime_update.phase.clone(),
+            });
+        }
+        self.phase = Some(realtime_update.phase.clone());
+
+        let over = realtime_update.phase == SessionPhase::PostSession
+            || realtime_update.phase == SessionPhase::ResultUI;
+        if over && !self.ended {
+            events.extend(self.session.clone().map(ACCDSessionEvent::SessionEnded));
+            self.ended = true;
+        }
+
+        events
+    }
+
+    /// Forgets the current session, e.g. after connecting to another ACC instance.
+    pub fn reset(&mut self) {
+        *self = ACCDSessionTracker::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(event_index: i32, session_index: i32, phase: SessionPhase) -> ACCDRealtimeUpdate {
+        ACCDRealtimeUpdate {
+            event_index,
+            session_index,
+            phase,
+            session_type: RaceSessionType::Race,
+            ..ACCDRealtimeUpdate::default()
+        }
+    }
+
+    fn info(event_index: i32, session_index: i32) -> ACCDSessionInfo {
+        ACCDSessionInfo {
+            event_index,
+            session_index,
+            session_type: RaceSessionType::Race,
+        }
+    }
+
+    #[test]
+    fn sessions_start_and_change_phase() {
+        let mut tracker = ACCDSessionTracker::new();
+
+        let events = tracker.realtime_update(&update(0, 0, SessionPhase::Session));
+        assert_eq!(
+            events,
+            vec![
+                ACCDSessionEvent::SessionStarted(info(0, 0)),
+                ACCDSessionEvent::SessionPhaseChanged {
+                    previous: SessionPhase::NONE,
+                    phase: SessionPhase::Session
+                },
+            ]
+        );
+        assert!(tracker
+            .realtime_update(&update(0, 0, SessionPhase::Session))
+            .is_empty());
+        assert_eq!(tracker.session(), Some(&info(0, 0)));
+    }
+
+    #[test]
+    fn sessions_end_once_every_car_finished() {
+        let mut tracker = ACCDSessionTracker::new();
+        tracker.realtime_update(&update(0, 0, SessionPhase::Session));
+
+        let events = tracker.realtime_update(&update(0, 0, SessionPhase::SessionOver));
+        assert!(!events.contains(&ACCDSessionEvent::SessionEnded(info(0, 0))));
+
+        let events = tracker.realtime_update(&update(0, 0, SessionPhase::PostSession));
+        assert_eq!(events[1], ACCDSessionEvent::SessionEnded(info(0, 0)));
+        let events = tracker.realtime_update(&update(0, 0, SessionPhase::ResultUI));
+        assert!(!events.contains(&ACCDSessionEvent::SessionEnded(info(0, 0))));
+
+        // Not ended again when the next session starts.
+        let events = tracker.realtime_update(&update(0, 1, SessionPhase::Starting));
+        assert_eq!(events[0], ACCDSessionEvent::SessionStarted(info(0, 1)));
+    }
+
+    #[test]
+    fn sessions_end_when_another_one_starts() {
+        let mut tracker = ACCDSessionTracker::new();
+        tracker.realtime_update(&update(0, 0, SessionPhase::Session));
+
+        let events = tracker.realtime_update(&update(1, 0, SessionPhase::Session));
+        assert_eq!(
+            events[..3],
+            [
+                ACCDSessionEvent::SessionEnded(info(0, 0)),
+                ACCDSessionEvent::EventChanged {
+                    previous: 0,
+                    event_index: 1
+                },
+                ACCDSessionEvent::SessionStarted(info(1, 0)),
+            ]
+        );
+        // Every session goes through its phases from NONE.
+        assert_eq!(
+            events[3],
+            ACCDSessionEvent::SessionPhaseChanged {
+                previous: SessionPhase::NONE,
+                phase: SessionPhase::Session
+            }
+        );
+    }
+}
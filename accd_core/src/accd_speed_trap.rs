@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+
+use crate::accd_car_info::{ACCDCarInfo, CarClass};
+use crate::accd_realtime_car_update::{ACCDRealtimeCarUpdate, CarLocationEnum};
+use crate::accd_realtime_update::ACCDRealtimeUpdate;
+use crate::accd_session_time::SessionTime;
+use crate::accd_session_tracker::ACCDSessionInfo;
+
+/// Speed of a car through a speed trap.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDTrapSpeed {
+    pub car_index: u16,
+    /// Index of the trap in the traps of the track.
+    pub trap: usize,
+    pub spline_position: f32,
+    /// Interpolated between the car updates before and after the trap.
+    pub kmh: i32,
+    /// Completed laps of the car.
+    pub laps: i32,
+    pub session_time: SessionTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDSpeedRecordKind {
+    Trap { trap: usize, spline_position: f32 },
+    TopSpeed,
+}
+
+/// Fastest speed of a class, or of the session when `overall`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDSpeedRecord {
+    pub kind: ACCDSpeedRecordKind,
+    pub car_index: u16,
+    pub car_class: CarClass,
+    pub kmh: i32,
+    /// The record it beats.
+    pub previous_kmh: i32,
+    pub overall: bool,
+    pub session_time: SessionTime,
+}
+
+/// Speeds reported by `ACCDSpeedTrapTracker`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ACCDSpeedEvent {
+    TrapSpeed(ACCDTrapSpeed),
+    Record(ACCDSpeedRecord),
+}
+
+/// Best speeds of a class and of the session, by trap and for the top speed.
+#[derive(Debug, Clone, Default)]
+struct Records {
+    classes: Vec<(CarClass, i32)>,
+    overall: Option<i32>,
+}
+
+impl Records {
+    /// The record beaten by `kmh` and whether it was the overall one, `None` when no record
+    /// is beaten. The first speed of a class sets its record without beating anything.
+    fn update(&mut self, car_class: CarClass, kmh: i32) -> Option<(i32, bool)> {
+        let overall = self.overall.is_some_and(|overall| kmh > overall);
+        if self.overall.is_none_or(|overall| kmh > overall) {
+            self.overall = Some(kmh);
+        }
+
+        match self.classes.iter_mut().find(|(c, _)| *c == car_class) {
+            Some((_, best)) if kmh > *best => {
+                let previous = *best;
+                *best = kmh;
+                Some((previous, overall))
+            }
+            Some(_) => None,
+            None => {
+                self.classes.push((car_class, kmh));
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CarSpeed {
+    spline_position: f32,
+    kmh: i32,
+    on_track: bool,
+}
+
+/// Measures the speed of the cars through the speed traps of the track and reports
+/// the speed records of every class and of the session.
+///
+/// Top speed records set on the first lap of a car are kept but not reported,
+/// every update would beat the previous one while the cars get up to speed.
+#[derive(Debug, Clone, Default)]
+pub struct ACCDSpeedTrapTracker {
+    /// Spline positions of the traps of the current track.
+    traps: Vec<f32>,
+    session: Option<ACCDSessionInfo>,
+    session_time: SessionTime,
+    cars: HashMap<u16, CarSpeed>,
+    trap_records: Vec<Records>,
+    top_speed_records: Records,
+}
+
+impl ACCDSpeedTrapTracker {
+    pub fn new() -> Self {
+        ACCDSpeedTrapTracker::default()
+    }
+
+    pub fn traps(&self) -> &[f32] {
+        &self.traps
+    }
+
+    /// Traps of the track, spline positions between 0 and 1.
+    pub fn set_traps(&mut self, traps: Vec<f32>) {
+        if traps != self.traps {
+            self.trap_records = vec![Records::default(); traps.len()];
+            self.traps = traps;
+        }
+    }
+
+    /// Forgets the session, e.g. after connecting to another ACC instance.
+    /// The traps are kept until the next track data.
+    pub fn reset(&mut self) {
+        self.session = None;
+        self.session_time = SessionTime::ZERO;
+        self.cars.clear();
+        self.trap_records = vec![Records::default(); self.traps.len()];
+        self.top_speed_records = Records::default();
+    }
+
+    pub fn realtime_update(&mut self, realtime_update: &ACCDRealtimeUpdate) {
+        let session = ACCDSessionInfo {
+            event_index: realtime_update.event_index(),
+            session_index: realtime_update.session_index(),
+            session_type: realtime_update.session_type.clone(),
+        };
+        if self.session.as_ref() != Some(&session) {
+            self.reset();
+            self.session = Some(session);
+        }
+        self.session_time = realtime_update.session_time;
+    }
+
+    pub fn car_update(
+        &mut self,
+        car_update: &ACCDRealtimeCarUpdate,
+        entry_list_cars: &[ACCDCarInfo],
+    ) -> Vec<ACCDSpeedEvent> {
+        let mut events = Vec::new();
+        let car_index = car_update.car_index as u16;
+        let car_class = entry_list_cars
+            .iter()
+            .find(|car_info| car_info.car_index == car_index)
+            .map_or(CarClass::Unknown, |car_info| car_info.car_class());
+        let current = CarSpeed {
+            spline_position: car_update.spline_position,
+            kmh: car_update.kmh,
+            on_track: matches!(car_update.car_location, CarLocationEnum::Track),
+        };
+        let previous = self.cars.insert(car_index, current.clone());
+        if !current.on_track {
+            return events;
+        }
+
+        if let Some(previous) = previous.filter(|previous| previous.on_track) {
+            for (trap, spline_position) in self.traps.iter().enumerate() {
+                let fraction = match crossing(
+                    previous.spline_position,
+                    current.spline_position,
+                    *spline_position,
+                ) {
+                    Some(fraction) => fraction,
+                    None => continue,
+                };
+                let kmh = previous.kmh as f32 + (current.kmh - previous.kmh) as f32 * fraction;
+                let trap_speed = ACCDTrapSpeed {
+                    car_index,
+                    trap,
+                    spline_position: *spline_position,
+                    kmh: kmh.round() as i32,
+                    laps: car_update.laps,
+                    session_time: self.session_time,
+                };
+
+                let record = self.trap_records[trap].update(car_class, trap_speed.kmh);
+                events.push(ACCDSpeedEvent::TrapSpeed(trap_speed.clone()));
+                if let Some((previous_kmh, overall)) = record {
+                    events.push(ACCDSpeedEvent::Record(ACCDSpeedRecord {
+                        kind: ACCDSpeedRecordKind::Trap {
+                            trap,
+                            spline_position: *spline_position,
+                        },
+                        car_index,
+                        car_class,
+                        kmh: trap_speed.kmh,
+                        previous_kmh,
+                        overall,
+                        session_time: self.session_time,
+                    }));
+                }
+            }
+        }
+
+        let record = self.top_speed_records.update(car_class, current.kmh);
+        if let Some((previous_kmh, overall)) = record.filter(|_| car_update.laps > 0) {
+            events.push(ACCDSpeedEvent::Record(ACCDSpeedRecord {
+                kind: ACCDSpeedRecordKind::TopSpeed,
+                car_index,
+                car_class,
+                kmh: current.kmh,
+                previous_kmh,
+                overall,
+                session_time: self.session_time,
+            }));
+        }
+
+        events
+    }
+}
+
+/// Share of the way from `previous` to `current` where a car passed `trap`,
+/// `None` when it didn't pass it. Cars only go forward, so a smaller spline position
+/// means the car crossed the line.
+fn crossing(previous: f32, current: f32, trap: f32) -> Option<f32> {
+    let distance = (current - previous).rem_euclid(1.0);
+    let to_trap = (trap - previous).rem_euclid(1.0);
+    // Long jumps are a car reset to the pits or to the track, not a crossing.
+    if distance == 0.0 || distance > 0.5 || to_trap == 0.0 || to_trap > distance {
+        return None;
+    }
+    Some(to_trap / distance)
+}
+
+/// Fastest car of a class through a trap or at top speed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDClassSpeed {
+    pub car_class: CarClass,
+    pub car_index: u16,
+    pub kmh: i32,
+}
+
+/// Speeds of a car through the session.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDCarSpeeds {
+    pub car_index: u16,
+    pub race_number: i32,
+    pub car_class: CarClass,
+    pub top_speed: Option<i32>,
+    /// Best speed through every trap.
+    pub trap_bests: Vec<Option<i32>>,
+}
+
+/// Speeds of the current session, kept up to date with the speed events and the car updates.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDSpeedStats {
+    /// Spline positions of the traps the cars went through.
+    pub traps: Vec<f32>,
+    /// Every speed through a trap by car index, oldest first.
+    pub trap_speeds: HashMap<u16, Vec<ACCDTrapSpeed>>,
+    /// Highest speed on track of every car.
+    pub top_speeds: HashMap<u16, i32>,
+    /// Records reported so far, oldest first.
+    pub records: Vec<ACCDSpeedRecord>,
+}
+
+impl ACCDSpeedStats {
+    pub fn new() -> Self {
+        ACCDSpeedStats::default()
+    }
+
+    pub fn car_update(&mut self, car_update: &ACCDRealtimeCarUpdate) {
+        if matches!(car_update.car_location, CarLocationEnum::Track) {
+            let top_speed = self
+                .top_speeds
+                .entry(car_update.car_index as u16)
+                .or_insert(car_update.kmh);
+            *top_speed = car_update.kmh.max(*top_speed);
+        }
+    }
+
+    pub fn speed_event(&mut self, event: &ACCDSpeedEvent) {
+        match event {
+            ACCDSpeedEvent::TrapSpeed(trap_speed) => {
+                if self.traps.len() <= trap_speed.trap {
+                    self.traps.resize(trap_speed.trap + 1, 0.0);
+                }
+                self.traps[trap_speed.trap] = trap_speed.spline_position;
+                self.trap_speeds
+                    .entry(trap_speed.car_index)
+                    .or_default()
+                    .push(trap_speed.clone());
+            }
+            ACCDSpeedEvent::Record(record) => self.records.push(record.clone()),
+        }
+    }
+
+    /// Best speed of a car through every trap.
+    pub fn trap_bests(&self, car_index: u16) -> Vec<Option<i32>> {
+        let mut bests = vec![None; self.traps.len()];
+        for trap_speed in self.trap_speeds.get(&car_index).into_iter().flatten() {
+            let best = &mut bests[trap_speed.trap];
+            *best = Some(best.map_or(trap_speed.kmh, |kmh: i32| kmh.max(trap_speed.kmh)));
+        }
+        bests
+    }
+
+    /// Speeds of the cars of the entry list, fastest top speed first.
+    pub fn cars(&self, entry_list_cars: &[ACCDCarInfo]) -> Vec<ACCDCarSpeeds> {
+        let mut cars: Vec<ACCDCarSpeeds> = entry_list_cars
+            .iter()
+            .map(|car_info| ACCDCarSpeeds {
+                car_index: car_info.car_index,
+                race_number: car_info.race_number,
+                car_class: car_info.car_class(),
+                top_speed: self.top_speeds.get(&car_info.car_index).copied(),
+                trap_bests: self.trap_bests(car_info.car_index),
+            })
+            .collect();
+        cars.sort_by_key(|car| std::cmp::Reverse(car.top_speed));
+        cars
+    }
+
+    /// Fastest car of every class at top speed, classes in the order of the entry list.
+    pub fn class_top_speeds(&self, entry_list_cars: &[ACCDCarInfo]) -> Vec<ACCDClassSpeed> {
+        class_bests(entry_list_cars, |car_index| {
+            self.top_speeds.get(&car_index).copied()
+        })
+    }
+
+    /// Fastest car of every class through a trap.
+    pub fn class_trap_bests(
+        &self,
+        entry_list_cars: &[ACCDCarInfo],
+        trap: usize,
+    ) -> Vec<ACCDClassSpeed> {
+        class_bests(entry_list_cars, |car_index| {
+            self.trap_bests(car_index).get(trap).copied().flatten()
+        })
+    }
+
+    /// Forgets everything, e.g. when a new session starts.
+    pub fn reset(&mut self) {
+        *self = ACCDSpeedStats::default();
+    }
+}
+
+fn class_bests(
+    entry_list_cars: &[ACCDCarInfo],
+    kmh: impl Fn(u16) -> Option<i32>,
+) -> Vec<ACCDClassSpeed> {
+    let mut bests: Vec<ACCDClassSpeed> = Vec::new();
+    for car_info in entry_list_cars {
+        let kmh = match kmh(car_info.car_index) {
+            Some(kmh) => kmh,
+            None => continue,
+        };
+        let car_class = car_info.car_class();
+        match bests.iter_mut().find(|best| best.car_class == car_class) {
+            Some(best) if kmh > best.kmh => {
+                best.car_index = car_info.car_index;
+                best.kmh = kmh;
+            }
+            Some(_) => {}
+            None => bests.push(ACCDClassSpeed {
+                car_class,
+                car_index: car_info.car_index,
+                kmh,
+            }),
+        }
+    }
+    bests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn car_update(
+        car_index: i32,
+        laps: i32,
+        spline_position: f32,
+        kmh: i32,
+    ) -> ACCDRealtimeCarUpdate {
+        ACCDRealtimeCarUpdate {
+            car_index,
+            laps,
+            spline_position,
+            kmh,
+            car_location: CarLocationEnum::Track,
+            ..ACCDRealtimeCarUpdate::default()
+        }
+    }
+
+    #[test]
+    fn crossing_between_two_updates() {
+        assert_eq!(crossing(0.25, 0.5, 0.375), Some(0.5));
+        assert_eq!(crossing(0.25, 0.5, 0.5), Some(1.0));
+        assert_eq!(crossing(0.25, 0.5, 0.25), None);
+        assert_eq!(crossing(0.25, 0.5, 0.625), None);
+    }
+
+    #[test]
+    fn crossing_across_the_line() {
+        let fraction = crossing(0.95, 0.05, 0.99).unwrap();
+        assert!((fraction - 0.4).abs() < 1e-5);
+        assert!(crossing(0.95, 0.05, 0.5).is_none());
+    }
+
+    #[test]
+    fn no_crossing_when_stopped_or_reset() {
+        assert_eq!(crossing(0.3, 0.3, 0.3), None);
+        // Backwards, or a jump back to the pits.
+        assert_eq!(crossing(0.4, 0.2, 0.3), None);
+        assert_eq!(crossing(0.1, 0.8, 0.5), None);
+    }
+
+    #[test]
+    fn trap_speeds_are_interpolated_and_records_reported() {
+        let mut tracker = ACCDSpeedTrapTracker::new();
+        tracker.set_traps(vec![0.5]);
+
+        // On the first lap, the top speed record is kept but not reported.
+        tracker.car_update(&car_update(1, 0, 0.375, 200), &[]);
+        let events = tracker.car_update(&car_update(1, 0, 0.625, 240), &[]);
+        assert!(matches!(
+            &events[0],
+            ACCDSpeedEvent::TrapSpeed(ACCDTrapSpeed {
+                kmh: 220,
+                trap: 0,
+                ..
+            })
+        ));
+        // The first speed sets the records without beating anything.
+        assert_eq!(events.len(), 1);
+
+        tracker.car_update(&car_update(2, 1, 0.25, 230), &[]);
+        let events = tracker.car_update(&car_update(2, 1, 0.75, 250), &[]);
+        let records: Vec<(ACCDSpeedRecordKind, i32, i32, bool)> = events
+            .iter()
+            .filter_map(|event| match event {
+                ACCDSpeedEvent::Record(record) => Some((
+                    record.kind.clone(),
+                    record.kmh,
+                    record.previous_kmh,
+                    record.overall,
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (
+                    ACCDSpeedRecordKind::Trap {
+                        trap: 0,
+                        spline_position: 0.5
+                    },
+                    240,
+                    220,
+                    true
+                ),
+                (ACCDSpeedRecordKind::TopSpeed, 250, 240, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_lap_top_speeds_are_not_reported() {
+        let mut tracker = ACCDSpeedTrapTracker::new();
+        tracker.car_update(&car_update(1, 0, 0.1, 100), &[]);
+        assert!(tracker
+            .car_update(&car_update(1, 0, 0.2, 150), &[])
+            .is_empty());
+        assert_eq!(
+            tracker.car_update(&car_update(1, 1, 0.3, 160), &[]).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::accd_lap_info::{ACCDLapInfo, LapType};
+use crate::accd_realtime_car_update::{ACCDRealtimeCarUpdate, CarLocationEnum};
+use crate::accd_session_time::SessionTime;
+
+/// Slices of the lap the model is made of.
+const BINS: usize = 200;
+/// Laps averaged in a slice, older ones weigh less and less
+/// so the model follows the conditions of the later sessions.
+const MAX_BIN_SAMPLES: u32 = 50;
+/// Share of slices learned needed to consider the model complete.
+const COMPLETE_COVERAGE: f32 = 0.98;
+/// Samples a lap needs to be learned, fewer means most of it was missed.
+const MIN_LAP_SAMPLES: usize = 20;
+/// Largest difference between the offset of a sample and the model, as a share of the lap.
+/// Samples sent around the start line can pair the spline position of one lap
+/// with the lap time of the other.
+const MAX_OFFSET_ERROR: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Bin {
+    /// Mean share of the lap time spent to get to the slice, minus its spline position.
+    offset: f64,
+    samples: u32,
+}
+
+/// Lap in progress of a car: spline positions with the lap time so far.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CurrentLap {
+    laps: i32,
+    samples: Vec<(f32, i32)>,
+    /// The car left the track during the lap.
+    dirty: bool,
+}
+
+/// Time needed to reach every spline position of a track, learned from clean laps.
+///
+/// Spline positions are shares of the lap distance, but a car spends much more time
+/// in a slow corner than on a straight of the same length. The model keeps, for every slice
+/// of the lap, the share of the lap time it takes to get there, so a spline distance becomes
+/// a time at racing pace. Slices that were not learned yet assume a constant speed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDSplineModel {
+    pub track_id: i32,
+    pub track_name: String,
+    bins: Vec<Bin>,
+    /// Clean laps learned so far.
+    pub laps: u32,
+    /// Mean time of the clean laps.
+    pub lap_time: Option<SessionTime>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    current_laps: HashMap<u16, CurrentLap>,
+}
+
+impl Default for ACCDSplineModel {
+    fn default() -> Self {
+        ACCDSplineModel::new(-1, "")
+    }
+}
+
+impl ACCDSplineModel {
+    pub fn new(track_id: i32, track_name: &str) -> Self {
+        ACCDSplineModel {
+            track_id,
+            track_name: track_name.to_string(),
+            bins: vec![Bin::default(); BINS],
+            laps: 0,
+            lap_time: None,
+            current_laps: HashMap::new(),
+        }
+    }
+
+    /// Adds the position of a car to its current lap, and learns the lap when it's
+    /// completed if it's a valid regular lap driven on track from start to end.
+    /// Returns true when a lap was learned.
+    pub fn car_update(&mut self, car_update: &ACCDRealtimeCarUpdate) -> bool {
+        let car_index = car_update.car_index as u16;
+        let current = self.current_laps.entry(car_index).or_default();
+
+        let mut learned = false;
+        if current.laps != car_update.laps {
+            let completed = std::mem::take(current);
+            // Laps joined halfway are fine, the lap time so far is known anyway.
+            let consecutive = completed.laps + 1 == car_update.laps;
+            if consecutive && !completed.dirty && is_clean(&car_update.last_lap) {
+                if let Some(lap_time_ms) = car_update.last_lap.lap_time_ms {
+                    learned = self.learn_lap(&completed.samples, lap_time_ms);
+                }
+            }
+        }
+
+        let current = self.current_laps.entry(car_index).or_default();
+        current.laps = car_update.laps;
+        match (&car_update.car_location, car_update.current_lap.lap_time_ms) {
+            (CarLocationEnum::Track, Some(lap_time_ms)) => {
+                current
+                    .samples
+                    .push((car_update.spline_position, lap_time_ms));
+            }
+            _ => current.dirty = true,
+        }
+
+        learned
+    }
+
+    /// Folds a completed lap into the model.
+    fn learn_lap(&mut self, samples: &[(f32, i32)], lap_time_ms: i32) -> bool {
+        if samples.len() < MIN_LAP_SAMPLES || lap_time_ms <= 0 {
+            return false;
+        }
+
+        for (spline_position, time_ms) in samples {
+            if !(0.0..1.0).contains(spline_position) || *time_ms > lap_time_ms {
+                continue;
+            }
+            let offset = *time_ms as f64 / lap_time_ms as f64 - *spline_position as f64;
+            if (offset - self.offset_at(*spline_position as f64)).abs() > MAX_OFFSET_ERROR {
+                continue;
+            }
+            let bin = &mut self.bins[bin_index(*spline_position)];
+            bin.samples = (bin.samples + 1).min(MAX_BIN_SAMPLES);
+            bin.offset += (offset - bin.offset) / bin.samples as f64;
+        }
+
+        self.laps += 1;
+        let lap_time = SessionTime::from_millis(lap_time_ms as f64);
+        self.lap_time = Some(match self.lap_time {
+            Some(mean) => {
+                let weight = self.laps.min(MAX_BIN_SAMPLES) as f64;
+                mean + SessionTime::from_millis((lap_time - mean).as_millis() / weight)
+            }
+            None => lap_time,
+        });
+
+        true
+    }
+
+    /// Share of the slices already learned, between 0 and 1.
+    pub fn coverage(&self) -> f32 {
+        let known = self.bins.iter().filter(|bin| bin.samples > 0).count();
+        known as f32 / BINS as f32
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.coverage() >= COMPLETE_COVERAGE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.laps == 0
+    }
+
+    /// Share of the lap time needed to get from the start line to a spline position,
+    /// between 0 and 1.
+    pub fn lap_fraction(&self, spline_position: f32) -> f64 {
+        let spline_position = (spline_position as f64).clamp(0.0, 1.0);
+        let fraction = spline_position + self.offset_at(spline_position);
+        fraction.clamp(0.0, 1.0)
+    }
+
+    /// Offset interpolated between the nearest learned slices, zero at the start line
+    /// where every lap starts and ends.
+    fn offset_at(&self, spline_position: f64) -> f64 {
+        let position = spline_position * BINS as f64;
+        let index = (position as usize).min(BINS - 1);
+
+        let before = (0..=index)
+            .rev()
+            .find(|i| self.bins[*i].samples > 0)
+            .map_or((0.0, 0.0), |i| (i as f64 + 0.5, self.bins[i].offset));
+        let after = (index + 1..BINS)
+            .find(|i| self.bins[*i].samples > 0)
+            .map_or((BINS as f64, 0.0), |i| {
+                (i as f64 + 0.5, self.bins[i].offset)
+            });
+
+        if after.0 <= before.0 {
+            return before.1;
+        }
+        let t = ((position - before.0) / (after.0 - before.0)).clamp(0.0, 1.0);
+        before.1 + (after.1 - before.1) * t
+    }
+
+    /// Time the car behind needs to get where the car ahead is, at the pace of `lap_time`.
+    /// Laps between the two cars count in full.
+    pub fn gap(
+        &self,
+        ahead: &ACCDRealtimeCarUpdate,
+        behind: &ACCDRealtimeCarUpdate,
+        lap_time: SessionTime,
+    ) -> SessionTime {
+        let laps = (ahead.laps - behind.laps) as f64;
+        let fraction = laps + self.lap_fraction(ahead.spline_position)
+            - self.lap_fraction(behind.spline_position);
+        SessionTime::from_millis(fraction * lap_time.as_millis())
+    }
+
+    /// File of a track in `dir`.
+    pub fn file_path(dir: &Path, track_id: i32) -> PathBuf {
+        dir.join(format!("spline_{}.json", track_id))
+    }
+
+    /// Reads the model saved for a track, `None` if there's none yet.
+    pub fn load(dir: &Path, track_id: i32) -> io::Result<Option<ACCDSplineModel>> {
+        let path = ACCDSplineModel::file_path(dir, track_id);
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, msg.to_string());
+        let value: Value = serde_json::from_str(&source).map_err(|e| invalid(&e.to_string()))?;
+
+        let mut model = ACCDSplineModel::new(
+            value["track_id"].as_i64().unwrap_or(track_id as i64) as i32,
+            value["track_name"].as_str().unwrap_or(""),
+        );
+        model.laps = value["laps"].as_u64().unwrap_or(0) as u32;
+        model.lap_time = value["lap_time_ms"].as_f64().map(SessionTime::from_millis);
+
+        let bins = value["bins"]
+            .as_array()
+            .filter(|bins| bins.len() == BINS)
+            .ok_or_else(|| invalid("expected bins"))?;
+        for (bin, saved) in model.bins.iter_mut().zip(bins) {
+            if let Some([offset, samples]) = saved.as_array().map(|v| v.as_slice()) {
+                *bin = Bin {
+                    offset: offset.as_f64().ok_or_else(|| invalid("invalid offset"))?,
+                    samples: samples.as_u64().ok_or_else(|| invalid("invalid samples"))? as u32,
+                };
+            }
+        }
+
+        Ok(Some(model))
+    }
+
+    /// Writes the model to its file in `dir`, creating the directory if needed.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let bins: Vec<Value> = self
+            .bins
+            .iter()
+            .map(|bin| match bin.samples {
+                0 => Value::Null,
+                samples => json!([bin.offset, samples]),
+            })
+            .collect();
+        let value = json!({
+            "track_id": self.track_id,
+            "track_name": self.track_name,
+            "laps": self.laps,
+            "lap_time_ms": self.lap_time.map(|lap_time| lap_time.as_millis()),
+            "bins": bins,
+        });
+
+        fs::create_dir_all(dir)?;
+        fs::write(
+            ACCDSplineModel::file_path(dir, self.track_id),
+            value.to_string(),
+        )
+    }
+}
+
+/// Laps that represent the racing pace.
+fn is_clean(lap: &ACCDLapInfo) -> bool {
+    matches!(lap.lap_type, LapType::Regular) && !lap.is_invalid && lap.lap_time_ms.is_some()
+}
+
+fn bin_index(spline_position: f32) -> usize {
+    ((spline_position * BINS as f32) as usize).min(BINS - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A lap at constant speed, with the samples ACC sends around the start line
+    /// that pair the spline position of one lap with the lap time of the other.
+    fn lap_samples(lap_time_ms: i32) -> Vec<(f32, i32)> {
+        let mut samples = vec![(0.998, 20), (0.002, lap_time_ms - 20)];
+        for i in 1..50 {
+            let spline_position = i as f32 / 50.0;
+            samples.push((
+                spline_position,
+                (spline_position * lap_time_ms as f32) as i32,
+            ));
+        }
+        samples
+    }
+
+    #[test]
+    fn samples_across_the_start_line_are_ignored() {
+        let mut model = ACCDSplineModel::new(1, "monza");
+        assert!(model.learn_lap(&lap_samples(100_000), 100_000));
+
+        assert_eq!(model.bins[0].samples, 0);
+        assert_eq!(model.bins[BINS - 1].samples, 0);
+        assert!(model.lap_fraction(0.999) > 0.99);
+        assert!((model.lap_fraction(0.5) - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn laps_with_few_samples_are_not_learned() {
+        let mut model = ACCDSplineModel::new(1, "monza");
+        assert!(!model.learn_lap(&lap_samples(100_000)[..10], 100_000));
+        assert!(model.is_empty());
+    }
+}
@@ -1,12 +1,13 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 use byteorder::{NativeEndian, ReadBytesExt};
 
 use crate::accd_utils::read_string;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ACCDTrackData {
     pub track_name: String,
     track_id: i32,
@@ -16,40 +17,45 @@ pub struct ACCDTrackData {
 }
 
 impl ACCDTrackData {
-    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> Self {
-        let track_name = read_string(cur);
-        let track_id = cur.read_i32::<NativeEndian>().unwrap();
-        let track_meters = cur.read_i32::<NativeEndian>().unwrap();
+    pub fn new(cur: &mut Cursor<&Vec<u8>>) -> io::Result<Self> {
+        let track_name = read_string(cur)?;
+        let track_id = cur.read_i32::<NativeEndian>()?;
+        let track_meters = cur.read_i32::<NativeEndian>()?;
         let mut camera_sets: HashMap<String, Vec<String>> = HashMap::new();
-        let camera_set_count = cur.read_u8().unwrap();
+        let camera_set_count = cur.read_u8()?;
 
         for _cam_set in 0..camera_set_count {
-            let cam_set_name = read_string(cur);
+            let cam_set_name = read_string(cur)?;
             camera_sets.insert(cam_set_name.clone(), Vec::new());
 
-            let camera_count = cur.read_u8().unwrap();
+            let camera_count = cur.read_u8()?;
             let camera_names: &mut Vec<String> = camera_sets.get_mut(&cam_set_name).unwrap();
 
             for _cam in 0..camera_count {
-                let camera_name = read_string(cur);
+                let camera_name = read_string(cur)?;
                 camera_names.push(camera_name);
             }
         }
 
         let mut hud_pages: Vec<String> = Vec::new();
-        let hud_pages_count = cur.read_u8().unwrap();
+        let hud_pages_count = cur.read_u8()?;
 
         for _i in 0..hud_pages_count {
-            hud_pages.push(read_string(cur));
+            hud_pages.push(read_string(cur)?);
         }
 
-        ACCDTrackData {
+        Ok(ACCDTrackData {
             track_name,
             track_id,
             track_meters,
             camera_sets,
             hud_pages,
-        }
+        })
+    }
+
+    /// ACC's id of the track, -1 before the track data arrives.
+    pub fn track_id(&self) -> i32 {
+        self.track_id
     }
 }
 
@@ -0,0 +1,512 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::accd_car_info::CarClass;
+use crate::accd_realtime_car_update::{ACCDRealtimeCarUpdate, CarLocationEnum};
+
+/// Directory the applications keep the learned track maps in.
+pub const DEFAULT_TRACK_MAPS_DIR: &str = "./track_maps";
+
+/// Slices of the lap the outline is made of.
+const BINS: usize = 1000;
+/// Positions averaged in a slice, later ones are ignored.
+const MAX_BIN_SAMPLES: u32 = 20;
+/// Share of slices with a position needed to close the outline.
+const COMPLETE_COVERAGE: f32 = 0.98;
+/// Longest hole of an incomplete outline drawn as a straight line.
+const MAX_BRIDGED_BINS: usize = 40;
+/// Space around the track in the rendered map, in pixels.
+const MARGIN: f64 = 24.0;
+const CAR_RADIUS: f64 = 9.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Bin {
+    x: f64,
+    y: f64,
+    samples: u32,
+}
+
+/// A car drawn on the map, at its world position.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDMapCar {
+    pub x: f32,
+    pub y: f32,
+    pub race_number: i32,
+    pub car_class: CarClass,
+    pub focused: bool,
+}
+
+/// Outline of a track learned from the world positions of the cars: every car update
+/// on track adds its position to the slice of the lap given by its spline position,
+/// after a few laps every slice has the average position of the racing line.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ACCDTrackMap {
+    pub track_id: i32,
+    pub track_name: String,
+    bins: Vec<Bin>,
+}
+
+impl Default for ACCDTrackMap {
+    fn default() -> Self {
+        ACCDTrackMap::new(-1, "")
+    }
+}
+
+/// Fill colour of the car dots.
+pub fn class_color(car_class: CarClass) -> &'static str {
+    match car_class {
+        CarClass::GT3 => "#e53935",
+        CarClass::GT4 => "#1e88e5",
+        CarClass::GT2 => "#8e24aa",
+        CarClass::Cup => "#fdd835",
+        CarClass::ST => "#43a047",
+        CarClass::CHL => "#fb8c00",
+        CarClass::TCX => "#00acc1",
+        CarClass::Unknown => "#9e9e9e",
+    }
+}
+
+impl ACCDTrackMap {
+    pub fn new(track_id: i32, track_name: &str) -> Self {
+        ACCDTrackMap {
+            track_id,
+            track_name: track_name.to_string(),
+            bins: vec![Bin::default(); BINS],
+        }
+    }
+
+    /// Adds the position of a car, cars in the pits don't follow the track.
+    pub fn car_update(&mut self, car_update: &ACCDRealtimeCarUpdate) {
+        if let CarLocationEnum::Track = car_update.car_location {
+            self.add_position(
+                car_update.spline_position,
+                car_update.world_pos_x,
+                car_update.world_pos_y,
+            );
+        }
+    }
+
+    pub fn add_position(&mut self, spline_position: f32, x: f32, y: f32) {
+        if !(0.0..=1.0).contains(&spline_position) {
+            return;
+        }
+
+        let bin = &mut self.bins[bin_index(spline_position)];
+        if bin.samples < MAX_BIN_SAMPLES {
+            bin.samples += 1;
+            bin.x += (x as f64 - bin.x) / bin.samples as f64;
+            bin.y += (y as f64 - bin.y) / bin.samples as f64;
+        }
+    }
+
+    /// Share of the lap with a known position, between 0 and 1.
+    pub fn coverage(&self) -> f32 {
+        let known = self.bins.iter().filter(|bin| bin.samples > 0).count();
+        known as f32 / BINS as f32
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.coverage() >= COMPLETE_COVERAGE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bins.iter().all(|bin| bin.samples == 0)
+    }
+
+    /// Known positions in lap order.
+    pub fn outline(&self) -> Vec<(f32, f32)> {
+        self.bins
+            .iter()
+            .filter(|bin| bin.samples > 0)
+            .map(|bin| (bin.x as f32, bin.y as f32))
+            .collect()
+    }
+
+    /// Position of the racing line at a spline position, if that part of the lap is known.
+    pub fn position_at(&self, spline_position: f32) -> Option<(f32, f32)> {
+        if !(0.0..=1.0).contains(&spline_position) {
+            return None;
+        }
+
+        let bin = &self.bins[bin_index(spline_position)];
+        if bin.samples > 0 {
+            Some((bin.x as f32, bin.y as f32))
+        } else {
+            None
+        }
+    }
+
+    /// File of a track in `dir`.
+    pub fn file_path(dir: &Path, track_id: i32) -> PathBuf {
+        dir.join(format!("track_{}.json", track_id))
+    }
+
+    /// Reads the map saved for a track, `None` if there's none yet.
+    pub fn load(dir: &Path, track_id: i32) -> io::Result<Option<ACCDTrackMap>> {
+        let path = ACCDTrackMap::file_path(dir, track_id);
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, msg.to_string());
+        let value: Value = serde_json::from_str(&source).map_err(|e| invalid(&e.to_string()))?;
+
+        let mut map = ACCDTrackMap::new(
+            value["track_id"].as_i64().unwrap_or(track_id as i64) as i32,
+            value["track_name"].as_str().unwrap_or(""),
+        );
+        let bins = value["bins"]
+            .as_array()
+            .filter(|bins| bins.len() == BINS)
+            .ok_or_else(|| invalid("expected bins"))?;
+        for (bin, saved) in map.bins.iter_mut().zip(bins) {
+            if let Some([x, y, samples]) = saved.as_array().map(|v| v.as_slice()) {
+                *bin = Bin {
+                    x: x.as_f64().ok_or_else(|| invalid("invalid x"))?,
+                    y: y.as_f64().ok_or_else(|| invalid("invalid y"))?,
+                    samples: samples.as_u64().ok_or_else(|| invalid("invalid samples"))? as u32,
+                };
+            }
+        }
+
+        Ok(Some(map))
+    }
+
+    /// Writes the map to its file in `dir`, creating the directory if needed.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let bins: Vec<Value> = self
+            .bins
+            .iter()
+            .map(|bin| match bin.samples {
+                0 => Value::Null,
+                samples => json!([bin.x, bin.y, samples]),
+            })
+            .collect();
+        let value = json!({
+            "track_id": self.track_id,
+            "track_name": self.track_name,
+            "bins": bins,
+        });
+
+        fs::create_dir_all(dir)?;
+        fs::write(
+            ACCDTrackMap::file_path(dir, self.track_id),
+            value.to_string(),
+        )
+    }
+
+    /// SVG image of the track with the cars, with a transparent background
+    /// so it can be used as an overlay.
+    pub fn to_svg(&self, cars: &[ACCDMapCar], width: u32, height: u32) -> String {
+        let mut points: Vec<(f32, f32)> = self.outline();
+        if points.len() < 2 {
+            points = cars.iter().map(|car| (car.x, car.y)).collect();
+        }
+        let project = Projection::new(&points, width as f64, height as f64);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = width,
+            h = height
+        );
+
+        let path = self.outline_path(&project);
+        if !path.is_empty() {
+            svg += &format!(
+                "<path d=\"{}\" fill=\"none\" stroke=\"#808080\" stroke-width=\"8\" \
+                 stroke-linecap=\"round\" stroke-linejoin=\"round\"/>\n",
+                path
+            );
+        }
+
+        // Focused car last, so it's drawn over the others.
+        let mut cars: Vec<&ACCDMapCar> = cars.iter().collect();
+        cars.sort_by_key(|car| car.focused);
+
+        svg += "<g font-family=\"sans-serif\" font-size=\"10\" font-weight=\"bold\" text-anchor=\"middle\">\n";
+        for car in cars {
+            let (x, y) = project.point(car.x, car.y);
+            let (stroke, stroke_width) = if car.focused {
+                ("#ffffff", 3)
+            } else {
+                ("#000000", 1)
+            };
+            svg += &format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                x,
+                y,
+                CAR_RADIUS,
+                class_color(car.car_class),
+                stroke,
+                stroke_width
+            );
+            svg += &format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"#000000\">{}</text>\n",
+                x,
+                y + 3.5,
+                car.race_number
+            );
+        }
+        svg += "</g>\n</svg>\n";
+
+        svg
+    }
+
+    /// PNG image of `to_svg`, race numbers need a font installed on the system.
+    #[cfg(feature = "png")]
+    pub fn to_png(&self, cars: &[ACCDMapCar], width: u32, height: u32) -> Result<Vec<u8>, String> {
+        use std::sync::{Arc, OnceLock};
+
+        use resvg::{tiny_skia, usvg};
+
+        // Loading the system fonts is slow, it's done once.
+        static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+        let fontdb = FONTS.get_or_init(|| {
+            let mut fontdb = usvg::fontdb::Database::new();
+            fontdb.load_system_fonts();
+            // The default sans-serif font is Arial, use another one when it's not installed.
+            let families: Vec<String> = fontdb
+                .faces()
+                .filter_map(|face| face.families.first())
+                .map(|(family, _)| family.clone())
+                .collect();
+            if !families.iter().any(|family| family == "Arial") {
+                let sans = families.iter().find(|family| family.contains("Sans"));
+                if let Some(family) = sans.or_else(|| families.first()) {
+                    fontdb.set_sans_serif_family(family.clone());
+                }
+            }
+            Arc::new(fontdb)
+        });
+
+        let options = usvg::Options {
+            fontdb: Arc::clone(fontdb),
+            ..usvg::Options::default()
+        };
+        let tree = usvg::Tree::from_str(&self.to_svg(cars, width, height), &options)
+            .map_err(|e| e.to_string())?;
+        let mut pixmap = tiny_skia::Pixmap::new(width, height)
+            .ok_or_else(|| format!("invalid image size {}x{}", width, height))?;
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+        pixmap.encode_png().map_err(|e| e.to_string())
+    }
+
+    /// Path data of the outline, broken where a long part of the lap is not known yet.
+    fn outline_path(&self, project: &Projection) -> String {
+        let complete = self.is_complete();
+        let mut path = String::new();
+        let mut gap: Option<usize> = None;
+
+        for bin in self.bins.iter() {
+            if bin.samples == 0 {
+                gap = gap.map(|gap| gap + 1);
+                continue;
+            }
+
+            let (x, y) = project.point(bin.x as f32, bin.y as f32);
+            let bridge = gap.is_some_and(|gap| complete || gap <= MAX_BRIDGED_BINS);
+            let command = if bridge { 'L' } else { 'M' };
+            path += &format!("{}{:.1},{:.1} ", command, x, y);
+            gap = Some(0);
+        }
+
+        if complete {
+            path += "Z";
+        }
+
+        path.trim_end().to_string()
+    }
+}
+
+fn bin_index(spline_position: f32) -> usize {
+    ((spline_position * BINS as f32) as usize).min(BINS - 1)
+}
+
+/// World to image coordinates: keeps the aspect ratio, centers the track
+/// and flips the y axis so that the map is seen from above.
+struct Projection {
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+    max_y: f64,
+    min_x: f64,
+}
+
+impl Projection {
+    fn new(points: &[(f32, f32)], width: f64, height: f64) -> Self {
+        let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+        let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+        for (x, y) in points {
+            min_x = min_x.min(*x as f64);
+            max_x = max_x.max(*x as f64);
+            min_y = min_y.min(*y as f64);
+            max_y = max_y.max(*y as f64);
+        }
+        if points.is_empty() {
+            return Projection {
+                scale: 1.0,
+                offset_x: width / 2.0,
+                offset_y: height / 2.0,
+                max_y: 0.0,
+                min_x: 0.0,
+            };
+        }
+
+        let span_x = (max_x - min_x).max(1.0);
+        let span_y = (max_y - min_y).max(1.0);
+        let scale = ((width - 2.0 * MARGIN) / span_x)
+            .min((height - 2.0 * MARGIN) / span_y)
+            .max(0.0);
+
+        Projection {
+            scale,
+            offset_x: (width - span_x * scale) / 2.0,
+            offset_y: (height - span_y * scale) / 2.0,
+            max_y,
+            min_x,
+        }
+    }
+
+    fn point(&self, x: f32, y: f32) -> (f64, f64) {
+        (
+            self.offset_x + (x as f64 - self.min_x) * self.scale,
+            self.offset_y + (self.max_y - y as f64) * self.scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// Position of the racing line of a round track, one lap per spline position.
+    fn circle(spline_position: f32) -> (f32, f32) {
+        let angle = spline_position * std::f32::consts::TAU;
+        (500.0 * angle.cos(), 500.0 * angle.sin())
+    }
+
+    /// Drives from `from` to `to`, adding one position in the middle of every slice.
+    fn drive(map: &mut ACCDTrackMap, from: f32, to: f32) {
+        let (from, to) = ((from * BINS as f32) as usize, (to * BINS as f32) as usize);
+        for bin in from..to {
+            let spline_position = (bin as f32 + 0.5) / BINS as f32;
+            let (x, y) = circle(spline_position);
+            map.add_position(spline_position, x, y);
+        }
+    }
+
+    fn car(race_number: i32, spline_position: f32, focused: bool) -> ACCDMapCar {
+        let (x, y) = circle(spline_position);
+        ACCDMapCar {
+            x,
+            y,
+            race_number,
+            car_class: CarClass::GT3,
+            focused,
+        }
+    }
+
+    #[test]
+    fn coverage_builds_up_over_a_lap() {
+        let mut map = ACCDTrackMap::new(1, "Monza");
+        assert!(map.is_empty());
+        assert_eq!(map.coverage(), 0.0);
+
+        drive(&mut map, 0.0, 0.5);
+        assert_eq!(map.coverage(), 0.5);
+        assert!(!map.is_complete());
+        assert_eq!(map.outline().len(), BINS / 2);
+
+        // Driving the same part again doesn't add to the coverage.
+        drive(&mut map, 0.0, 0.5);
+        assert_eq!(map.coverage(), 0.5);
+
+        drive(&mut map, 0.5, 0.97);
+        assert!(!map.is_complete());
+        drive(&mut map, 0.97, 0.98);
+        assert!(map.is_complete());
+        assert!(map.position_at(0.975).is_some());
+        assert!(map.position_at(0.99).is_none());
+        assert!(map.position_at(1.5).is_none());
+    }
+
+    #[test]
+    fn cars_in_the_pits_are_left_out() {
+        let mut map = ACCDTrackMap::new(1, "Monza");
+        let mut car_update = ACCDRealtimeCarUpdate {
+            spline_position: 0.25,
+            world_pos_x: 10.0,
+            world_pos_y: 20.0,
+            car_location: CarLocationEnum::Pitlane,
+            ..ACCDRealtimeCarUpdate::default()
+        };
+        map.car_update(&car_update);
+        assert!(map.is_empty());
+
+        car_update.car_location = CarLocationEnum::Track;
+        map.car_update(&car_update);
+        assert_eq!(map.position_at(0.25), Some((10.0, 20.0)));
+
+        // Out of the lap.
+        map.add_position(-0.1, 0.0, 0.0);
+        map.add_position(1.1, 0.0, 0.0);
+        assert_eq!(map.outline().len(), 1);
+    }
+
+    #[test]
+    fn saved_maps_load_the_same() {
+        let dir = env::temp_dir().join(format!("accd_track_map_{}", std::process::id()));
+        let mut map = ACCDTrackMap::new(7, "Spa");
+        drive(&mut map, 0.1, 0.6);
+        map.add_position(0.3, 1.0 / 3.0, -2.5);
+
+        assert!(ACCDTrackMap::load(&dir, 7).unwrap().is_none());
+        map.save(&dir).unwrap();
+        let loaded = ACCDTrackMap::load(&dir, 7).unwrap().unwrap();
+        assert_eq!((loaded.track_id, loaded.track_name.as_str()), (7, "Spa"));
+        // serde_json may parse the last digit of a float one bit off.
+        for (loaded, bin) in loaded.bins.iter().zip(map.bins.iter()) {
+            assert_eq!(loaded.samples, bin.samples);
+            assert!((loaded.x - bin.x).abs() < 1e-9 && (loaded.y - bin.y).abs() < 1e-9);
+        }
+
+        fs::write(ACCDTrackMap::file_path(&dir, 8), "{\"bins\": []}").unwrap();
+        assert!(ACCDTrackMap::load(&dir, 8).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn svg_has_the_outline_and_the_cars() {
+        let mut map = ACCDTrackMap::new(1, "Monza");
+        drive(&mut map, 0.0, 1.0);
+        let cars = vec![car(7, 0.25, true), car(12, 0.5, false)];
+
+        let svg = map.to_svg(&cars, 400, 300);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"400\" height=\"300\""));
+        assert_eq!(svg.matches("<path").count(), 1);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        // The focused car is drawn last, with a white outline.
+        let (car_12, car_7) = (
+            svg.find(">12</text>").unwrap(),
+            svg.find(">7</text>").unwrap(),
+        );
+        assert!(car_12 < car_7);
+        assert_eq!(svg.matches("stroke=\"#ffffff\"").count(), 1);
+
+        // Without an outline only the cars are drawn.
+        let svg = ACCDTrackMap::default().to_svg(&cars, 400, 300);
+        assert_eq!(svg.matches("<path").count(), 0);
+        assert_eq!(svg.matches("<circle").count(), 2);
+    }
+}
@@ -1,12 +1,29 @@
 pub mod accd_broadcasting_event;
 pub mod accd_car_info;
+pub mod accd_conditions;
 pub mod accd_config;
+pub mod accd_config_loader;
+pub mod accd_connection_manager;
 pub mod accd_driver_info;
+pub mod accd_entry_list_sync;
 mod accd_enums;
+pub mod accd_export;
+pub mod accd_grid;
 pub mod accd_lap_info;
+pub mod accd_pace;
+pub mod accd_pit_strategy;
+pub mod accd_position_history;
 pub mod accd_protocol;
 pub mod accd_realtime_car_update;
 pub mod accd_realtime_update;
+pub mod accd_recording;
 pub mod accd_registration_result;
+pub mod accd_report;
+pub mod accd_session_state;
+pub mod accd_session_time;
+pub mod accd_session_tracker;
+pub mod accd_speed_trap;
+pub mod accd_spline_model;
 pub mod accd_track_data;
+pub mod accd_track_map;
 pub mod accd_utils;
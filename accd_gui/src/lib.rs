@@ -13,7 +13,6 @@ use std::cell::RefCell;
 use std::cell::RefMut;
 use std::collections::HashMap;
 use std::error::Error;
-use std::net::UdpSocket;
 use std::rc::Rc;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
@@ -24,6 +23,7 @@ use std::time::Duration;
 use accd2::accd_broadcasting_event::{ACCDBroadcastingEvent, BroadcastingCarEventType};
 use accd2::accd_car_info::ACCDCarInfo;
 use accd2::accd_config::ACCDConfig;
+use accd2::accd_config_loader::{ACCDConfigLoader, USAGE};
 use accd2::accd_driver_info::ACCDDriverInfo;
 use accd2::accd_protocol::ACCDProtocol;
 use accd2::accd_protocol::ListenResult;
@@ -31,7 +31,6 @@ use accd2::accd_realtime_car_update::ACCDRealtimeCarUpdate;
 use accd2::accd_realtime_update::ACCDRealtimeUpdate;
 use accd2::accd_registration_result::ACCDRegistrationResult;
 use accd2::accd_track_data::ACCDTrackData;
-use accd2::accd_utils::parse_config_file;
 
 mod replay_panel;
 use crate::replay_panel::ReplayPanel;
@@ -528,9 +527,16 @@ impl MainApp {
                 Car data: {}\r\n",
                 &new_button.broadcast_evt.event_type,
                 &new_button.broadcast_evt.event_msg,
-                &new_button.broadcast_evt.event_time_ms,
-                &new_button.broadcast_evt.event_car_id,
-                &new_button.broadcast_evt.event_car_data
+                &new_button.broadcast_evt.event_time,
+                new_button
+                    .broadcast_evt
+                    .event_car_id
+                    .map_or("-".to_string(), |car_id| car_id.to_string()),
+                new_button
+                    .broadcast_evt
+                    .event_car_data
+                    .as_ref()
+                    .map_or("-".to_string(), |car_info| car_info.to_string())
             ));
 
         let row = buttons.len() as u32;
@@ -547,7 +553,7 @@ impl MainApp {
             move |evt, _evt_data, handle| match evt {
                 nwg::Event::OnButtonClick => {
                     if handle == new_button_handle {
-                        let start_time = (event_data.event.event_time_ms as f32)
+                        let start_time = (event_data.event.event_time.as_millis() as f32)
                             - event_data.replay_seconds_back;
                         let duration = event_data.replay_duration;
                         let car_index = event_data.event.event_car_id;
@@ -570,15 +576,35 @@ impl MainApp {
 
     fn init(&self) {
         let c_trtx = self.txrx.clone();
-        *self.accdp.lock().unwrap() = ACCDProtocol::new(parse_config_file(
-            "./accd_core/config/default.cfg".to_string(),
-        ));
+        let loader = match ACCDConfigLoader::from_env() {
+            Ok(loader) => loader,
+            Err(e) => {
+                nwg::error_message("ACC Director LITE", &format!("{}\n\n{}", e, USAGE));
+                nwg::stop_thread_dispatch();
+                return;
+            }
+        };
+        let config = match loader.load() {
+            Ok(config) => config,
+            Err(e) => {
+                nwg::error_message("ACC Director LITE", &e.to_string());
+                nwg::stop_thread_dispatch();
+                return;
+            }
+        };
+        if loader.print_config {
+            print!("{}", config.to_toml(true));
+            nwg::stop_thread_dispatch();
+            return;
+        }
+        *self.accdp.lock().unwrap() = ACCDProtocol::new(config);
         let c_accdp = Arc::clone(&self.accdp);
 
-        let bind_addr = c_accdp.lock().unwrap().config.bind_addr;
-        c_accdp.lock().unwrap().socket = Some(RefCell::new(UdpSocket::bind(bind_addr).unwrap()));
-
-        c_accdp.lock().unwrap().request_connection();
+        if let Err(e) = c_accdp.lock().unwrap().connect() {
+            nwg::error_message("ACC Director LITE", &e.to_string());
+            nwg::stop_thread_dispatch();
+            return;
+        }
 
         let track_notice = self.track_panel.track_notice.sender();
         let track_data = Arc::clone(&self.track_panel.track_data);
@@ -46,21 +46,21 @@ impl RealtimeUpdatePanel {
             Wetness: {}\r\n\
             ---\r\n\
             Best Session Lap: {}\r\n\
-            Best lap car: {}\r\n\
-            Best lap driver: {}\r\n\
+            Best lap car: {:?}\r\n\
+            Best lap driver: {:?}\r\n\
             ---\r\n\
             Session type: {:?}\r\n\
-            Session time: {:?}\r\n\
-            Session end time: {:?}\r\n\
-            Session remaining time: {:?}\r\n\
-            ({:?})\r\n\
+            Session time: {}\r\n\
+            Session end time: {}\r\n\
+            Session remaining time: {}\r\n\
+            ({})\r\n\
             ---\r\n\
             Replay playing {}\r\n\
-            Replay sesison time: {}\r\n\
-            Replay remaining time: {}",
+            Replay sesison time: {:?}\r\n\
+            Replay remaining time: {:?}",
             realtime_data.ambient_temp,
             realtime_data.track_temp,
-            realtime_data.time_of_day,
+            realtime_data.time_of_day.to_duration().unwrap_or_default(),
             realtime_data.phase,
             realtime_data.current_hud_page,
             realtime_data.active_camera,
@@ -0,0 +1,385 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use accd_core::accd_config_loader::{ACCDConfigLoader, USAGE};
+use accd_core::accd_protocol::{ACCDProtocol, ListenResult};
+use accd_core::accd_utils::write_string;
+
+// Message types of the broadcasting protocol the relay needs to look at.
+const REGISTRATION_RESULT: u8 = 1;
+const REGISTER_COMMAND_APPLICATION: u8 = 1;
+const UNREGISTER_COMMAND_APPLICATION: u8 = 9;
+const REQUEST_ENTRY_LIST: u8 = 10;
+const REQUEST_TRACK_DATA: u8 = 11;
+/// Change HUD page, change focus, instant replay, play and save manual replay highlight.
+const CONTROL_COMMANDS: [u8; 5] = [49, 50, 51, 52, 60];
+/// Read only flag of a registration result: type, connection id, connection success, read only.
+const READ_ONLY_OFFSET: usize = 6;
+
+struct Client {
+    addr: SocketAddr,
+    /// Registered with the wrong command password, it only gets the datagrams like in ACC.
+    read_only: bool,
+}
+
+struct Relay {
+    /// Endpoints from the command line, they get every datagram even without registering
+    /// and can send commands.
+    static_clients: Vec<SocketAddr>,
+    /// Broadcast clients that registered with the relay.
+    clients: Vec<Client>,
+    /// Registration result received from ACC, sent as is to the clients that register later.
+    registration: Option<Vec<u8>>,
+    connection_id: i32,
+}
+
+impl Relay {
+    fn destinations(&self) -> Vec<SocketAddr> {
+        let mut destinations = self.static_clients.clone();
+        for client in self.clients.iter() {
+            if !destinations.contains(&client.addr) {
+                destinations.push(client.addr);
+            }
+        }
+        destinations
+    }
+
+    fn is_registered(&self, addr: SocketAddr) -> bool {
+        self.static_clients.contains(&addr) || self.clients.iter().any(|client| client.addr == addr)
+    }
+
+    /// Only clients known to the relay can send commands, with the command password.
+    fn can_command(&self, addr: SocketAddr) -> bool {
+        self.static_clients.contains(&addr)
+            || self
+                .clients
+                .iter()
+                .any(|client| client.addr == addr && !client.read_only)
+    }
+
+    /// Prepares a request of a client for ACC, `false` when it mustn't be forwarded.
+    /// Every request starts with the connection id, ACC knows only the one of the relay.
+    fn upstream_request(&self, msg: &mut [u8], from: SocketAddr) -> bool {
+        let allowed = match msg.first() {
+            // Read only clients can ask for the entry list and the track data, like in ACC.
+            Some(&REQUEST_ENTRY_LIST) | Some(&REQUEST_TRACK_DATA) => self.is_registered(from),
+            Some(msg_type) if CONTROL_COMMANDS.contains(msg_type) => self.can_command(from),
+            _ => false,
+        };
+        if !allowed || msg.len() < 5 {
+            return false;
+        }
+
+        msg[1..5].copy_from_slice(&self.connection_id.to_ne_bytes());
+        true
+    }
+}
+
+fn main() {
+    let loader = match ACCDConfigLoader::from_env() {
+        Ok(loader) => loader,
+        Err(e) => {
+            println!("ERROR: {}\n{}", e, USAGE);
+            process::exit(1);
+        }
+    };
+
+    let config = match loader.load() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if loader.print_config {
+        print!("{}", config.to_toml(true));
+        return;
+    }
+
+    // Arguments left by the config loader: [listen_addr] [downstream_addr ...].
+    let listen_addr = loader
+        .args
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "0.0.0.0:9100".to_string());
+    let mut static_clients = Vec::new();
+    for arg in loader.args.iter().skip(1) {
+        match arg.parse::<SocketAddr>() {
+            Ok(addr) => static_clients.push(addr),
+            Err(e) => {
+                println!("ERROR({}): {}", arg, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let connection_psw = config.connection_psw.clone();
+    let command_psw = config.command_psw.clone();
+    let destination_addr = config.destination_addr;
+
+    let mut accdp = ACCDProtocol::new(config);
+    if let Err(e) = accdp.connect() {
+        println!("ERROR({}): {}", accdp.config.bind_addr, e);
+        process::exit(1);
+    }
+
+    // Commands are sent on a clone of the protocol socket, so they don't wait for the listener.
+    let upstream = match accdp.socket.as_ref().unwrap().borrow().try_clone() {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let downstream = match UdpSocket::bind(&listen_addr) {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!("ERROR({}): {}", listen_addr, e);
+            process::exit(1);
+        }
+    };
+    let downstream_tx = match downstream.try_clone() {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    };
+
+    println!("Relaying {} on {}", destination_addr, listen_addr);
+
+    let relay = Arc::new(Mutex::new(Relay {
+        static_clients,
+        clients: Vec::new(),
+        registration: None,
+        connection_id: -1,
+    }));
+
+    // ACC -> downstream clients.
+    let c_relay = Arc::clone(&relay);
+    thread::spawn(move || loop {
+        let msg = match accdp.recv_datagram() {
+            Some(msg) => msg,
+            None => continue,
+        };
+
+        if let ListenResult::RegistrationResult(reg_result) = accdp.process_datagram(&msg) {
+            println!("{}", reg_result);
+            let mut relay = c_relay.lock().unwrap();
+            relay.registration = Some(msg.clone());
+            relay.connection_id = reg_result.connection_id;
+        }
+        // Clients get the datagrams as they are, the results held back by the protocol don't matter.
+        while accdp.next_pending().is_some() {}
+
+        let destinations = c_relay.lock().unwrap().destinations();
+        for destination in destinations {
+            if let Err(e) = downstream_tx.send_to(&msg, destination) {
+                println!("ERROR({}): {}", destination, e);
+            }
+        }
+    });
+
+    // Downstream clients -> ACC.
+    let mut buffer = [0; 2048];
+    loop {
+        let (len, from) = match downstream.recv_from(&mut buffer) {
+            Ok(result) => result,
+            Err(e) => {
+                println!("ERROR: {}", e);
+                continue;
+            }
+        };
+        let msg = &mut buffer[..len];
+        if msg.is_empty() {
+            continue;
+        }
+
+        let mut relay = relay.lock().unwrap();
+        match msg[0] {
+            REGISTER_COMMAND_APPLICATION => {
+                let (password, command_password) = match register_passwords(msg) {
+                    Some(passwords) => passwords,
+                    None => continue,
+                };
+                if password != connection_psw {
+                    println!("Refused registration from {}, wrong password", from);
+                    send(&downstream, &registration_refused("Wrong password"), from);
+                    continue;
+                }
+
+                let read_only = command_password != command_psw;
+                relay.clients.retain(|client| client.addr != from);
+                relay.clients.push(Client {
+                    addr: from,
+                    read_only,
+                });
+                println!(
+                    "Client {} registered{}",
+                    from,
+                    if read_only { ", read only" } else { "" }
+                );
+
+                // Clients that register before ACC answered get the result with everyone else.
+                if let Some(registration) = &relay.registration {
+                    let mut registration = registration.clone();
+                    if read_only && registration.len() > READ_ONLY_OFFSET {
+                        registration[READ_ONLY_OFFSET] = 1;
+                    }
+                    send(&downstream, &registration, from);
+                }
+            }
+            UNREGISTER_COMMAND_APPLICATION => {
+                // The relay stays registered with ACC for the other clients.
+                relay.clients.retain(|client| client.addr != from);
+                println!("Client {} unregistered", from);
+            }
+            // Anyone can send datagrams to the relay, only the clients it knows reach ACC.
+            _ => {
+                if relay.upstream_request(msg, from) {
+                    send(&upstream, msg, destination_addr);
+                }
+            }
+        }
+    }
+}
+
+fn send(socket: &UdpSocket, msg: &[u8], destination: SocketAddr) {
+    if let Err(e) = socket.send_to(msg, destination) {
+        println!("ERROR({}): {}", destination, e);
+    }
+}
+
+/// Connection and command passwords of a register request: type, protocol version,
+/// display name, connection password, update interval, command password.
+fn register_passwords(msg: &[u8]) -> Option<(String, String)> {
+    let mut offset = 2;
+    let mut read_string = |skip: usize| {
+        offset += skip;
+        let len_bytes = msg.get(offset..offset + 2)?;
+        let len = u16::from_ne_bytes([len_bytes[0], len_bytes[1]]) as usize;
+        let bytes = msg.get(offset + 2..offset + 2 + len)?;
+        offset += 2 + len;
+        String::from_utf8(bytes.to_vec()).ok()
+    };
+
+    let _display_name = read_string(0)?;
+    let password = read_string(0)?;
+    // The update interval is an i32.
+    let command_password = read_string(4)?;
+    Some((password, command_password))
+}
+
+fn registration_refused(err_msg: &str) -> Vec<u8> {
+    let mut buffer = vec![REGISTRATION_RESULT];
+    buffer.extend_from_slice(&(-1i32).to_ne_bytes());
+    buffer.push(0); // connection success
+    buffer.push(1); // read only
+    write_string(&mut buffer, &err_msg.to_string());
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register_request(password: &str, command_password: &str) -> Vec<u8> {
+        let mut msg = vec![REGISTER_COMMAND_APPLICATION, 4];
+        write_string(&mut msg, &"Relay client".to_string());
+        write_string(&mut msg, &password.to_string());
+        msg.extend_from_slice(&250i32.to_ne_bytes());
+        write_string(&mut msg, &command_password.to_string());
+        msg
+    }
+
+    fn relay() -> Relay {
+        Relay {
+            static_clients: vec!["127.0.0.1:3000".parse().unwrap()],
+            clients: vec![
+                Client {
+                    addr: "127.0.0.1:3001".parse().unwrap(),
+                    read_only: false,
+                },
+                Client {
+                    addr: "127.0.0.1:3002".parse().unwrap(),
+                    read_only: true,
+                },
+            ],
+            registration: None,
+            connection_id: 7,
+        }
+    }
+
+    #[test]
+    fn passwords_of_register_requests() {
+        assert_eq!(
+            register_passwords(&register_request("asd", "cmd")),
+            Some(("asd".to_string(), "cmd".to_string()))
+        );
+        assert_eq!(
+            register_passwords(&register_request("asd", "")),
+            Some(("asd".to_string(), String::new()))
+        );
+
+        let msg = register_request("asd", "cmd");
+        for len in 0..msg.len() {
+            assert_eq!(register_passwords(&msg[..len]), None, "{} bytes", len);
+        }
+
+        let mut msg = register_request("as", "cmd");
+        let password = msg.len() - 2 - 3 - 4 - 2;
+        msg[password] = 0xff;
+        assert_eq!(register_passwords(&msg), None);
+    }
+
+    #[test]
+    fn requests_get_the_connection_id_of_the_relay() {
+        let relay = relay();
+        let client = "127.0.0.1:3001".parse().unwrap();
+
+        let mut msg = vec![50];
+        msg.extend_from_slice(&99i32.to_ne_bytes());
+        msg.extend_from_slice(&[1, 2, 3]);
+        assert!(relay.upstream_request(&mut msg, client));
+        assert_eq!(&msg[1..5], &7i32.to_ne_bytes());
+        assert_eq!(&msg[5..], &[1, 2, 3]);
+
+        // Too short to carry a connection id.
+        assert!(!relay.upstream_request(&mut [50, 0, 0, 0], client));
+    }
+
+    #[test]
+    fn read_only_clients_only_request_data() {
+        let relay = relay();
+        let request = |msg_type: u8, from: &str| {
+            let mut msg = vec![msg_type, 0, 0, 0, 0];
+            relay.upstream_request(&mut msg, from.parse().unwrap())
+        };
+
+        for client in &["127.0.0.1:3000", "127.0.0.1:3001"] {
+            for msg_type in [REQUEST_ENTRY_LIST, REQUEST_TRACK_DATA]
+                .iter()
+                .chain(CONTROL_COMMANDS.iter())
+            {
+                assert!(request(*msg_type, client), "{} from {}", msg_type, client);
+            }
+            // Not a request ACC knows.
+            assert!(!request(3, client));
+        }
+
+        let read_only = "127.0.0.1:3002";
+        assert!(request(REQUEST_ENTRY_LIST, read_only));
+        assert!(request(REQUEST_TRACK_DATA, read_only));
+        for msg_type in CONTROL_COMMANDS.iter() {
+            assert!(!request(*msg_type, read_only), "{}", msg_type);
+        }
+
+        let stranger = "127.0.0.1:3003";
+        assert!(!request(REQUEST_ENTRY_LIST, stranger));
+        assert!(!request(50, stranger));
+    }
+}
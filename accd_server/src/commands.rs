@@ -0,0 +1,531 @@
+use std::convert::TryFrom;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use tiny_http::{Request, StatusCode};
+
+use accd_core::accd_connection_manager::{ACCDConnection, ACCDConnectionManager};
+
+use crate::http_api::{error, respond_same_origin};
+
+/// Command bodies are a few params, anything bigger is refused.
+pub const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Permission {
+    Focus,
+    Camera,
+    Hud,
+    Replay,
+    Highlight,
+}
+
+impl Permission {
+    fn from_name(name: &str) -> Option<Permission> {
+        match name {
+            "focus" => Some(Permission::Focus),
+            "camera" => Some(Permission::Camera),
+            "hud" => Some(Permission::Hud),
+            "replay" => Some(Permission::Replay),
+            "highlight" => Some(Permission::Highlight),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Operator {
+    pub name: String,
+    token: String,
+    pub permissions: Vec<Permission>,
+}
+
+/// Tokens of `operators.example.json`, they are public so they must not work.
+const PLACEHOLDER_TOKEN_PREFIX: &str = "change-me";
+
+/// Reads the operators from a JSON file:
+/// `[{"name": "director", "token": "secret", "permissions": ["focus", "camera"]}]`.
+/// Without the file every command request is refused. A file that isn't a list of
+/// operators, or an operator still using the token of the example file, is an error,
+/// the server must not start with it.
+pub fn load_operators(file_path: &str) -> Result<Vec<Operator>, String> {
+    let data = match fs::read_to_string(file_path) {
+        Ok(data) => data,
+        Err(e) => {
+            println!("ERROR({}): {}, remote commands are disabled", file_path, e);
+            return Ok(Vec::new());
+        }
+    };
+
+    let operators = match serde_json::from_str::<Value>(&data) {
+        Ok(Value::Array(operators)) => operators,
+        Ok(_) => return Err(format!("{}: expected a list of operators", file_path)),
+        Err(e) => return Err(format!("{}: {}", file_path, e)),
+    };
+
+    let mut result = Vec::new();
+    for operator in operators {
+        let name = operator["name"].as_str().unwrap_or("");
+        let token = operator["token"].as_str().unwrap_or("");
+        if name.is_empty() || token.is_empty() {
+            println!("ERROR({}): operator without name or token", file_path);
+            continue;
+        }
+        if token.starts_with(PLACEHOLDER_TOKEN_PREFIX) {
+            return Err(format!(
+                "{}: operator {} has the token of the example file, set a secret token",
+                file_path, name
+            ));
+        }
+
+        let mut permissions = Vec::new();
+        for permission in operator["permissions"].as_array().unwrap_or(&Vec::new()) {
+            match permission.as_str().and_then(Permission::from_name) {
+                Some(permission) => permissions.push(permission),
+                None => println!(
+                    "ERROR({}): unknown permission {} for operator {}",
+                    file_path, permission, name
+                ),
+            }
+        }
+
+        result.push(Operator {
+            name: name.to_string(),
+            token: token.to_string(),
+            permissions,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Append only log of every command request, one line per request.
+pub struct AuditLog {
+    file: Mutex<Option<File>>,
+}
+
+impl AuditLog {
+    pub fn open(file_path: &str) -> Self {
+        let file = match OpenOptions::new().create(true).append(true).open(file_path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                println!("ERROR({}): {}, audit log goes to stdout only", file_path, e);
+                None
+            }
+        };
+
+        AuditLog {
+            file: Mutex::new(file),
+        }
+    }
+
+    pub fn record(&self, operator: &str, command: &str, params: &Value, result: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let line = format!(
+            "{} | {} | {} | {} | {}",
+            timestamp, operator, command, params, result
+        );
+        println!("AUDIT: {}", line);
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            if let Err(e) = writeln!(file, "{}", line) {
+                println!("ERROR: {}", e);
+            }
+        }
+    }
+}
+
+pub struct CommandContext {
+    pub connections: ACCDConnectionManager,
+    pub operators: Vec<Operator>,
+    pub audit_log: AuditLog,
+}
+
+/// Handles `POST /command/<name>` requests, authenticated with `Authorization: Bearer <token>`.
+/// The command goes to the connection named by the `connection` param, or to the first one.
+pub fn handle(mut request: Request, command: &str, ctx: &CommandContext) {
+    let authorization = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .map(|h| h.value.as_str());
+    let operator = authenticate(&ctx.operators, authorization);
+    let operator = match operator {
+        Some(operator) => operator,
+        None => {
+            ctx.audit_log
+                .record("-", command, &Value::Null, "unauthorized");
+            respond_same_origin(request, StatusCode(401), &error("unauthorized"));
+            return;
+        }
+    };
+
+    let permission = match Permission::from_name(command) {
+        Some(permission) => permission,
+        None => {
+            ctx.audit_log
+                .record(&operator.name, command, &Value::Null, "unknown command");
+            respond_same_origin(request, StatusCode(404), &error("unknown command"));
+            return;
+        }
+    };
+
+    let mut body = String::new();
+    let read = request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body);
+    if let Err(e) = read {
+        ctx.audit_log
+            .record(&operator.name, command, &Value::Null, "bad request");
+        respond_same_origin(request, StatusCode(400), &error(&e.to_string()));
+        return;
+    }
+    if body.len() as u64 > MAX_BODY_BYTES {
+        ctx.audit_log
+            .record(&operator.name, command, &Value::Null, "body too large");
+        respond_same_origin(request, StatusCode(413), &error("body too large"));
+        return;
+    }
+    let params = if body.trim().is_empty() {
+        json!({})
+    } else {
+        match serde_json::from_str::<Value>(&body) {
+            Ok(params) => params,
+            Err(e) => {
+                ctx.audit_log
+                    .record(&operator.name, command, &Value::Null, "bad request");
+                respond_same_origin(request, StatusCode(400), &error(&e.to_string()));
+                return;
+            }
+        }
+    };
+
+    if !operator.permissions.contains(&permission) {
+        ctx.audit_log
+            .record(&operator.name, command, &params, "forbidden");
+        respond_same_origin(request, StatusCode(403), &error("forbidden"));
+        return;
+    }
+
+    let connection = match params["connection"].as_str() {
+        Some(id) => ctx.connections.get(id),
+        None => ctx.connections.first(),
+    };
+    let connection = match connection {
+        Some(connection) => connection,
+        None => {
+            ctx.audit_log
+                .record(&operator.name, command, &params, "unknown connection");
+            respond_same_origin(request, StatusCode(404), &error("unknown connection"));
+            return;
+        }
+    };
+
+    match run(permission, &params, connection) {
+        Ok(()) => {
+            ctx.audit_log.record(&operator.name, command, &params, "ok");
+            respond_same_origin(request, StatusCode(200), &json!({ "result": "ok" }));
+        }
+        Err(e) => {
+            ctx.audit_log.record(&operator.name, command, &params, &e);
+            respond_same_origin(request, StatusCode(400), &error(&e));
+        }
+    }
+}
+
+/// The operator whose token is in the `Authorization: Bearer <token>` header.
+fn authenticate<'a>(
+    operators: &'a [Operator],
+    authorization: Option<&str>,
+) -> Option<&'a Operator> {
+    let token = authorization?.strip_prefix("Bearer ")?.trim();
+
+    // Every token is compared in full, so the time taken doesn't tell how close a guess is.
+    operators.iter().fold(None, |found, op| {
+        if constant_time_eq(&op.token, token) {
+            Some(op)
+        } else {
+            found
+        }
+    })
+}
+
+fn run(permission: Permission, params: &Value, connection: &ACCDConnection) -> Result<(), String> {
+    match permission {
+        Permission::Focus => {
+            let car_index = match (car_index_param(params)?, int_param(params, "race_number")?) {
+                (Some(car_index), _) => car_index,
+                (None, Some(race_number)) => connection
+                    .state
+                    .lock()
+                    .unwrap()
+                    .car_by_race_number(race_number)
+                    .map(|car_info| car_info.car_index)
+                    .ok_or(format!("no car with race number #{}", race_number))?,
+                (None, None) => return Err("car_index or race_number required".to_string()),
+            };
+
+            connection.accdp.lock().unwrap().set_focus(
+                Some(car_index),
+                str_param(params, "camera_set"),
+                str_param(params, "camera"),
+            );
+        }
+        Permission::Camera => {
+            let camera_set = str_param(params, "camera_set");
+            let camera = str_param(params, "camera");
+            if camera_set.is_empty() || camera.is_empty() {
+                return Err("camera_set and camera required".to_string());
+            }
+
+            connection
+                .accdp
+                .lock()
+                .unwrap()
+                .set_camera(camera_set, camera);
+        }
+        Permission::Hud => {
+            let hud_page = str_param(params, "hud_page");
+            if hud_page.is_empty() {
+                return Err("hud_page required".to_string());
+            }
+
+            connection.accdp.lock().unwrap().request_hud_page(hud_page);
+        }
+        Permission::Replay => {
+            let seconds = match &params["seconds"] {
+                Value::Null => 10.0,
+                seconds => seconds
+                    .as_f64()
+                    .filter(|seconds| *seconds > 0.0 && *seconds <= 3600.0)
+                    .ok_or("seconds must be a number between 0 and 3600")?
+                    as f32,
+            };
+
+            let realtime_update = connection.state.lock().unwrap().realtime_update.clone();
+            let start_time = realtime_update.session_time.as_millis() as f32;
+            let car_index = car_index_param(params)?.or(realtime_update.focused_car_index);
+
+            connection.accdp.lock().unwrap().request_instant_replay(
+                start_time - (seconds * 1000.0),
+                seconds * 1000.0,
+                car_index,
+                realtime_update.active_camera_set,
+                realtime_update.active_camera,
+            );
+        }
+        Permission::Highlight => {
+            connection
+                .accdp
+                .lock()
+                .unwrap()
+                .save_manual_replay_highlight();
+        }
+    }
+
+    Ok(())
+}
+
+fn str_param(params: &Value, key: &str) -> String {
+    params[key].as_str().unwrap_or("").to_string()
+}
+
+/// `car_index` param, an error when it's there but not a valid car index.
+fn car_index_param(params: &Value) -> Result<Option<u16>, String> {
+    match &params["car_index"] {
+        Value::Null => Ok(None),
+        car_index => car_index
+            .as_u64()
+            .and_then(|car_index| u16::try_from(car_index).ok())
+            .map(Some)
+            .ok_or_else(|| format!("car_index must be between 0 and {}", u16::MAX)),
+    }
+}
+
+/// Integer param, an error when it's there but not an `i32`.
+fn int_param(params: &Value, key: &str) -> Result<Option<i32>, String> {
+    match &params[key] {
+        Value::Null => Ok(None),
+        value => value
+            .as_i64()
+            .and_then(|value| i32::try_from(value).ok())
+            .map(Some)
+            .ok_or_else(|| format!("{} must be an integer", key)),
+    }
+}
+
+/// Compares two strings in a time that depends only on their lengths.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_api::tests::{post_command, start_server};
+
+    const OPERATORS: &str = r#"[
+        {"name": "director", "token": "director-token", "permissions": ["focus", "replay"]},
+        {"name": "viewer", "token": "viewer-token", "permissions": ["hud", "fly"]},
+        {"name": "nobody", "permissions": ["focus"]}
+    ]"#;
+
+    fn operators_file(name: &str, data: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("accd_commands_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, data).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn operators_are_loaded_with_their_permissions() {
+        let operators = load_operators(&operators_file("operators.json", OPERATORS)).unwrap();
+
+        // The operator without a token is skipped, unknown permissions are left out.
+        assert_eq!(operators.len(), 2);
+        assert_eq!(operators[0].name, "director");
+        assert_eq!(
+            operators[0].permissions,
+            vec![Permission::Focus, Permission::Replay]
+        );
+        assert_eq!(operators[1].permissions, vec![Permission::Hud]);
+    }
+
+    #[test]
+    fn bad_operator_files_are_errors() {
+        let placeholder = r#"[{"name": "director", "token": "change-me-director"}]"#;
+        for (name, data) in [
+            ("placeholder.json", placeholder),
+            ("malformed.json", r#"[{"name": "director""#),
+            ("object.json", r#"{"name": "director", "token": "secret"}"#),
+        ]
+        .iter()
+        {
+            assert!(
+                load_operators(&operators_file(name, data)).is_err(),
+                "{}",
+                name
+            );
+        }
+
+        // Without the file the server runs with commands disabled.
+        assert!(load_operators("/nonexistent/operators.json")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn operators_authenticate_with_a_bearer_token() {
+        let operators = load_operators(&operators_file("auth.json", OPERATORS)).unwrap();
+        let name = |authorization| authenticate(&operators, authorization).map(|op| &op.name);
+
+        assert_eq!(name(Some("Bearer director-token")).unwrap(), "director");
+        assert_eq!(name(Some("Bearer viewer-token ")).unwrap(), "viewer");
+        assert!(name(Some("Bearer viewer-tokem")).is_none());
+        assert!(name(Some("Bearer viewer")).is_none());
+        assert!(name(Some("Bearer ")).is_none());
+        assert!(name(Some("director-token")).is_none());
+        assert!(name(Some("Basic director-token")).is_none());
+        assert!(name(None).is_none());
+    }
+
+    #[test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq("", ""));
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+        assert!(!constant_time_eq("secret", ""));
+    }
+
+    #[test]
+    fn param_ranges() {
+        let params = json!({
+            "zero": 0,
+            "last": 65535,
+            "negative": -1,
+            "too_big": 65536,
+            "text": "3",
+            "fraction": 1.5,
+            "big": 2_147_483_648_i64,
+        });
+        let car_index = |key: &str| car_index_param(&json!({ "car_index": params[key] }));
+
+        assert_eq!(car_index("zero"), Ok(Some(0)));
+        assert_eq!(car_index("last"), Ok(Some(u16::MAX)));
+        assert_eq!(car_index("missing"), Ok(None));
+        for key in &["negative", "too_big", "text", "fraction"] {
+            assert!(car_index(key).is_err(), "{}", key);
+        }
+
+        assert_eq!(int_param(&params, "negative"), Ok(Some(-1)));
+        assert_eq!(int_param(&params, "missing"), Ok(None));
+        for key in &["big", "text", "fraction"] {
+            assert!(int_param(&params, key).is_err(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn commands_need_a_token_and_the_permission() {
+        let (addr, audit_log) = start_server(OPERATORS);
+
+        assert_eq!(post_command(&addr, "focus", "wrong-token", "{}"), 401);
+        assert_eq!(post_command(&addr, "teleport", "director-token", "{}"), 404);
+        assert_eq!(post_command(&addr, "camera", "director-token", "{}"), 403);
+        assert_eq!(post_command(&addr, "focus", "viewer-token", "{}"), 403);
+        assert_eq!(
+            post_command(&addr, "focus", "director-token", r#"{"car_index": 3}"#),
+            200
+        );
+
+        // Replays are between 0 and 3600 seconds long.
+        for (body, status) in [
+            (r#"{"seconds": 0}"#, 400),
+            (r#"{"seconds": 3601}"#, 400),
+            (r#"{"seconds": "10"}"#, 400),
+            (r#"{"seconds": 3600}"#, 200),
+            (r#"{"car_index": -1}"#, 400),
+            ("{}", 200),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                post_command(&addr, "replay", "director-token", body),
+                *status,
+                "{}",
+                body
+            );
+        }
+
+        let audit_log = fs::read_to_string(audit_log).unwrap();
+        let results: Vec<_> = audit_log
+            .lines()
+            .map(|line| line.rsplit(" | ").next().unwrap())
+            .collect();
+        assert_eq!(
+            results[..5],
+            [
+                "unauthorized",
+                "unknown command",
+                "forbidden",
+                "forbidden",
+                "ok"
+            ]
+        );
+        assert!(audit_log.contains("| director | teleport |"));
+    }
+}
@@ -0,0 +1,480 @@
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tiny_http::{Method, Request, Response, Server, StatusCode};
+
+use accd_core::accd_pace::DEFAULT_ROLLING_LAPS;
+
+use crate::commands::{self, CommandContext};
+use crate::websocket::{header, WebSocketHub};
+
+pub fn serve(server: &Server, hub: Arc<WebSocketHub>, ctx: CommandContext) {
+    for request in server.incoming_requests() {
+        let path = request.url().split('?').next().unwrap_or("").to_string();
+
+        if *request.method() == Method::Options {
+            // CORS preflight, browsers send it before command requests with an Authorization header.
+            // Commands are not allowed from other origins, a page opened by an operator
+            // mustn't be able to drive the broadcast.
+            if path.starts_with("/command/") {
+                if let Err(e) = request.respond(Response::empty(StatusCode(204))) {
+                    println!("ERROR: {}", e);
+                }
+                continue;
+            }
+            let response = Response::empty(StatusCode(204))
+                .with_header(header("Access-Control-Allow-Origin", "*"))
+                .with_header(header("Access-Control-Allow-Methods", "GET"))
+                .with_header(header(
+                    "Access-Control-Allow-Headers",
+                    "Authorization, Content-Type",
+                ));
+            if let Err(e) = request.respond(response) {
+                println!("ERROR: {}", e);
+            }
+            continue;
+        }
+
+        if *request.method() == Method::Post {
+            match path.strip_prefix("/command/") {
+                Some(command) => commands::handle(request, command, &ctx),
+                None => respond_same_origin(request, StatusCode(404), &error("not found")),
+            }
+            continue;
+        }
+
+        if *request.method() != Method::Get {
+            respond(request, StatusCode(405), &error("method not allowed"));
+            continue;
+        }
+
+        let connection_id = query_param(request.url(), "connection");
+
+        if path == "/ws" {
+            match &connection_id {
+                Some(id) if ctx.connections.get(id).is_none() => {
+                    respond(request, StatusCode(404), &error("unknown connection"));
+                }
+                _ => hub.accept(request, connection_id),
+            }
+            continue;
+        }
+
+        if path == "/connections" {
+            let connections: Vec<Value> = ctx
+                .connections
+                .connections()
+                .iter()
+                .map(|connection| {
+                    let accdp = connection.accdp.lock().unwrap();
+                    json!({
+                        "id": connection.id,
+                        "destination_address": accdp.config.destination_addr.to_string(),
+                        "connected": accdp.is_connected(),
+                    })
+                })
+                .collect();
+            respond(request, StatusCode(200), &json!(connections));
+            continue;
+        }
+
+        let connection = match &connection_id {
+            Some(id) => ctx.connections.get(id),
+            None => ctx.connections.first(),
+        };
+        let connection = match connection {
+            Some(connection) => connection,
+            None => {
+                respond(request, StatusCode(404), &error("unknown connection"));
+                continue;
+            }
+        };
+
+        if let Some(format) = path.strip_prefix("/track/map.") {
+            let size = |name, default| {
+                query_param(request.url(), name)
+                    .and_then(|value| value.parse::<u32>().ok())
+                    .filter(|size| *size > 0 && *size <= 4096)
+                    .unwrap_or(default)
+            };
+            let (width, height) = (size("width", 800), size("height", 600));
+            let state = connection.state.lock().unwrap();
+            let cars = state.map_cars();
+
+            match format {
+                "svg" => {
+                    let svg = state.track_map.to_svg(&cars, width, height);
+                    drop(state);
+                    respond_raw(request, svg.into_bytes(), "image/svg+xml");
+                }
+                #[cfg(feature = "png")]
+                "png" => {
+                    let png = state.track_map.to_png(&cars, width, height);
+                    drop(state);
+                    match png {
+                        Ok(png) => respond_raw(request, png, "image/png"),
+                        Err(e) => respond(request, StatusCode(500), &error(&e)),
+                    }
+                }
+                _ => {
+                    drop(state);
+                    respond(request, StatusCode(404), &error("not found"));
+                }
+            }
+            continue;
+        }
+
+        if let Some(format) = path.strip_prefix("/report.") {
+            let session = query_param(request.url(), "session");
+            let state = connection.state.lock().unwrap();
+            // Archived session by index, the current one without it.
+            let archive = match session.as_deref().map(str::parse::<usize>) {
+                Some(Ok(index)) => state.archived_sessions.get(index).cloned(),
+                Some(Err(_)) => None,
+                None => Some(state.session_archive()),
+            };
+            let report = archive.map(|archive| state.report(&archive));
+            drop(state);
+
+            match (report, format) {
+                (None, _) => respond(request, StatusCode(404), &error("unknown session")),
+                (Some(report), "md") => {
+                    respond_raw(request, report.to_markdown().into_bytes(), "text/markdown")
+                }
+                (Some(report), "html") => {
+                    respond_raw(request, report.to_html().into_bytes(), "text/html")
+                }
+                (Some(_), _) => respond(request, StatusCode(404), &error("not found")),
+            }
+            continue;
+        }
+
+        let body = {
+            let state = connection.state.lock().unwrap();
+            match path.as_str() {
+                "/session" => Some(json!({
+                    "registration_result": state.registration_result,
+                    "track_name": state.track_data.track_name,
+                    "realtime_update": state.realtime_update,
+                    "remaining": state.remaining(),
+                })),
+                "/entries" => Some(json!(state.entry_list_cars)),
+                "/standings" => Some(json!(state.standings())),
+                "/track" => Some(json!(state.track_data)),
+                "/track/map" => Some(json!({
+                    "track_id": state.track_map.track_id,
+                    "coverage": state.track_map.coverage(),
+                    "complete": state.track_map.is_complete(),
+                    "outline": state.track_map.outline(),
+                    "cars": state.map_cars(),
+                })),
+                "/events" => Some(json!(state.broadcasting_events)),
+                "/laps" => Some(json!(state.laps)),
+                "/positions" => {
+                    let history = &state.position_history;
+                    Some(json!({
+                        "lap_chart": history.lap_chart(),
+                        "gap_chart": history.gap_chart(),
+                        "gains": history.gains(),
+                        "start_positions": history.start_positions,
+                        "lap_samples": history.lap_samples,
+                        "timed_samples": history.timed_samples,
+                    }))
+                }
+                "/pace" => {
+                    let rolling_laps = query_param(request.url(), "laps")
+                        .and_then(|laps| laps.parse().ok())
+                        .unwrap_or(DEFAULT_ROLLING_LAPS);
+                    let cars: Vec<Value> = state
+                        .standings()
+                        .iter()
+                        .map(|standing| {
+                            let car_index = standing.car_info.car_index;
+                            json!({
+                                "race_number": standing.car_info.race_number,
+                                "pace": state.pace(car_index, rolling_laps),
+                                "same_strategy": state.pace_comparisons(car_index, rolling_laps),
+                            })
+                        })
+                        .collect();
+                    Some(json!(cars))
+                }
+                "/grid" => Some(json!({
+                    "grid": state.grid,
+                    "position_changes": state.position_changes(),
+                })),
+                "/strategy" => Some(json!({
+                    "pit_loss": state.pit_loss,
+                    "cars": state.strategy(),
+                    "pit_cycles": state.pit_cycles,
+                })),
+                "/conditions" => {
+                    let history = &state.condition_history;
+                    Some(json!({
+                        "current": history.current(),
+                        "samples": history.samples,
+                        "events": history.events,
+                        "periods": history.periods(),
+                        "correlation": history.correlation(),
+                    }))
+                }
+                "/speed_traps" => {
+                    let stats = &state.speed_stats;
+                    let entry_list_cars = &state.entry_list_cars;
+                    let traps: Vec<_> = stats
+                        .traps
+                        .iter()
+                        .enumerate()
+                        .map(|(trap, spline_position)| {
+                            json!({
+                                "spline_position": spline_position,
+                                "classes": stats.class_trap_bests(entry_list_cars, trap),
+                            })
+                        })
+                        .collect();
+                    Some(json!({
+                        "cars": stats.cars(entry_list_cars),
+                        "classes": stats.class_top_speeds(entry_list_cars),
+                        "traps": traps,
+                        "records": stats.records,
+                    }))
+                }
+                "/sessions" => Some(json!(state.archived_sessions)),
+                _ => None,
+            }
+        };
+
+        match body {
+            Some(body) => respond(request, StatusCode(200), &body),
+            None => respond(request, StatusCode(404), &error("not found")),
+        }
+    }
+}
+
+/// Value of `name` in the query string of `url`, without percent decoding.
+pub fn query_param(url: &str, name: &str) -> Option<String> {
+    url.split_once('?')?
+        .1
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+pub fn respond(request: Request, status: StatusCode, body: &Value) {
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+        .with_header(header("Access-Control-Allow-Origin", "*"));
+
+    if let Err(e) = request.respond(response) {
+        println!("ERROR: {}", e);
+    }
+}
+
+/// Like `respond`, without CORS headers: pages of other origins can't read the response.
+pub fn respond_same_origin(request: Request, status: StatusCode, body: &Value) {
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"));
+
+    if let Err(e) = request.respond(response) {
+        println!("ERROR: {}", e);
+    }
+}
+
+/// Non JSON responses, e.g. the track map images and the reports.
+fn respond_raw(request: Request, body: Vec<u8>, content_type: &str) {
+    let response = Response::from_data(body)
+        .with_header(header("Content-Type", content_type))
+        .with_header(header("Access-Control-Allow-Origin", "*"));
+
+    if let Err(e) = request.respond(response) {
+        println!("ERROR: {}", e);
+    }
+}
+
+pub fn error(msg: &str) -> Value {
+    json!({ "error": msg })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use tungstenite::Message;
+
+    use accd_core::accd_config::ACCDConfig;
+    use accd_core::accd_connection_manager::ACCDConnectionManager;
+
+    use crate::commands::{load_operators, AuditLog, MAX_BODY_BYTES};
+
+    /// Serves on a free port with one connection, `main`, and the operators of `operators_json`.
+    /// Returns the server address and the path of its audit log.
+    pub(crate) fn start_server(operators_json: &str) -> (String, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("accd_server_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        static SERVERS: AtomicUsize = AtomicUsize::new(0);
+        let server_id = SERVERS.fetch_add(1, Ordering::Relaxed);
+        let operators_path = dir.join(format!("operators_{}.json", server_id));
+        std::fs::write(&operators_path, operators_json).unwrap();
+        let operators = load_operators(operators_path.to_str().unwrap()).unwrap();
+        let audit_log_path = dir.join(format!("audit_{}.log", server_id));
+
+        let mut connections = ACCDConnectionManager::new();
+        let config = ACCDConfig {
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            destination_addr: "127.0.0.1:9".parse().unwrap(),
+            ..ACCDConfig::default()
+        };
+        connections.add("main", config).unwrap();
+
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap().to_string();
+        let ctx = CommandContext {
+            connections,
+            operators,
+            audit_log: AuditLog::open(audit_log_path.to_str().unwrap()),
+        };
+        thread::spawn(move || serve(&server, Arc::new(WebSocketHub::new()), ctx));
+        (addr, audit_log_path)
+    }
+
+    /// Status code, headers and body of the response to a raw request.
+    fn send(addr: &str, request: &[u8]) -> (u16, String, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        // The PNG map isn't UTF-8, only its status and headers are checked.
+        let response = String::from_utf8_lossy(&response);
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, head.to_string(), body.to_string())
+    }
+
+    fn get(addr: &str, url: &str) -> (u16, String, String) {
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n",
+            url
+        );
+        send(addr, request.as_bytes())
+    }
+
+    pub(crate) fn post_command(addr: &str, command: &str, token: &str, body: &str) -> u16 {
+        let request = format!(
+            "POST /command/{} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\
+             Authorization: Bearer {}\r\nContent-Length: {}\r\n\r\n{}",
+            command,
+            token,
+            body.len(),
+            body
+        );
+        send(addr, request.as_bytes()).0
+    }
+
+    #[test]
+    fn query_params() {
+        let url = "/pace?connection=main&laps=5&laps=7&flag";
+
+        assert_eq!(query_param(url, "connection").as_deref(), Some("main"));
+        assert_eq!(query_param(url, "laps").as_deref(), Some("5"));
+        assert_eq!(query_param(url, "flag"), None);
+        assert_eq!(query_param(url, "nection"), None);
+        assert_eq!(
+            query_param("/pace?connection=", "connection").as_deref(),
+            Some("")
+        );
+        assert_eq!(query_param("/pace", "connection"), None);
+    }
+
+    #[test]
+    fn track_map_formats() {
+        let (addr, _) = start_server("[]");
+
+        let (status, head, body) = get(&addr, "/track/map.svg?width=200&height=100");
+        assert_eq!(status, 200);
+        assert!(head.contains("image/svg+xml"));
+        assert!(body.starts_with("<svg"));
+
+        #[cfg(feature = "png")]
+        assert_eq!(get(&addr, "/track/map.png").0, 200);
+        #[cfg(not(feature = "png"))]
+        assert_eq!(get(&addr, "/track/map.png").0, 404);
+
+        assert_eq!(get(&addr, "/track/map.gif").0, 404);
+        assert_eq!(get(&addr, "/track/map.svg?connection=other").0, 404);
+    }
+
+    #[test]
+    fn report_formats_and_sessions() {
+        let (addr, _) = start_server("[]");
+
+        let (status, head, _) = get(&addr, "/report.md");
+        assert_eq!(status, 200);
+        assert!(head.contains("text/markdown"));
+        let (status, head, _) = get(&addr, "/report.html?connection=main");
+        assert_eq!(status, 200);
+        assert!(head.contains("text/html"));
+
+        let (status, _, body) = get(&addr, "/report.txt");
+        assert_eq!(status, 404);
+        assert!(body.contains("not found"));
+        // Nothing archived yet, and an index that isn't a number.
+        for url in &["/report.md?session=0", "/report.md?session=last"] {
+            let (status, _, body) = get(&addr, url);
+            assert_eq!(status, 404);
+            assert!(body.contains("unknown session"));
+        }
+    }
+
+    #[test]
+    fn websocket_clients_are_pinged_and_can_close() {
+        let (addr, _) = start_server("[]");
+        let stream = TcpStream::connect(&addr).unwrap();
+        let (mut websocket, _) = tungstenite::client(format!("ws://{}/ws", addr), stream).unwrap();
+
+        // Read by the server after its next ping.
+        websocket.send(Message::Ping(b"ping".to_vec())).unwrap();
+        let mut pinged = false;
+        loop {
+            match websocket.read().unwrap() {
+                Message::Ping(_) => pinged = true,
+                Message::Pong(data) => {
+                    assert_eq!(data, b"ping");
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert!(pinged);
+
+        websocket.close(None).unwrap();
+        loop {
+            match websocket.read() {
+                Ok(_) => {}
+                Err(tungstenite::Error::ConnectionClosed) => break,
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn command_bodies_are_capped() {
+        let (addr, _) =
+            start_server(r#"[{"name": "viewer", "token": "viewer-token", "permissions": []}]"#);
+        let limit = MAX_BODY_BYTES as usize;
+
+        // Within the limit the body is parsed, the operator lacks the permission.
+        let body = format!("{{}}{}", " ".repeat(limit - 2));
+        assert_eq!(post_command(&addr, "focus", "viewer-token", &body), 403);
+        let body = format!("{{}}{}", " ".repeat(limit - 1));
+        assert_eq!(post_command(&addr, "focus", "viewer-token", &body), 413);
+    }
+}
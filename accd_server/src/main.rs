@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use tiny_http::Server;
+
+use accd_core::accd_config_loader::{ACCDConfigLoader, USAGE};
+use accd_core::accd_connection_manager::ACCDConnectionManager;
+use accd_core::accd_report::DEFAULT_REPORTS_DIR;
+use accd_core::accd_session_time::SessionTime;
+use accd_core::accd_track_map::DEFAULT_TRACK_MAPS_DIR;
+
+mod commands;
+use crate::commands::{load_operators, AuditLog, CommandContext};
+
+mod http_api;
+mod websocket;
+use crate::websocket::WebSocketHub;
+
+fn main() {
+    let loader = match ACCDConfigLoader::from_env() {
+        Ok(loader) => loader,
+        Err(e) => {
+            println!("ERROR: {}\n{}", e, USAGE);
+            process::exit(1);
+        }
+    };
+
+    // Arguments left by the config loader: [http_addr] [operators.json] [audit log].
+    let mut args = loader.args.iter().cloned();
+    let http_addr = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let operators_path = args
+        .next()
+        .unwrap_or_else(|| "./accd_server/config/operators.json".to_string());
+    let audit_log_path = args
+        .next()
+        .unwrap_or_else(|| "./accd_server/audit.log".to_string());
+
+    let connections = match loader.load_connections() {
+        Ok(connections) => connections,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if loader.print_config {
+        for (id, config) in connections.iter() {
+            println!("# Connection {}", id);
+            print!("{}", config.to_toml(true));
+        }
+        return;
+    }
+
+    let operators = match load_operators(&operators_path) {
+        Ok(operators) => operators,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let mut manager = ACCDConnectionManager::new();
+    manager.track_maps_dir = Some(PathBuf::from(DEFAULT_TRACK_MAPS_DIR));
+    manager.reports_dir = Some(PathBuf::from(DEFAULT_REPORTS_DIR));
+    manager.position_sample_interval = Some(SessionTime::from_secs(10.0));
+    for (id, config) in connections {
+        println!("Connecting {} to {}", id, config.destination_addr);
+        if let Err(e) = manager.add(&id, config) {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    }
+
+    let server = match Server::http(&http_addr) {
+        Ok(server) => server,
+        Err(e) => {
+            println!("ERROR({}): {}", http_addr, e);
+            process::exit(1);
+        }
+    };
+    println!("Serving on http://{}", http_addr);
+
+    let hub = Arc::new(WebSocketHub::new());
+    let events = manager.subscribe();
+    let c_hub = Arc::clone(&hub);
+    thread::spawn(move || {
+        for event in events {
+            if let Some(delta) = websocket::delta(&event) {
+                c_hub.broadcast(&event.connection_id, &delta);
+            }
+        }
+    });
+
+    let ctx = CommandContext {
+        connections: manager,
+        operators,
+        audit_log: AuditLog::open(&audit_log_path),
+    };
+
+    // Ctrl-C stops serving, dropping the connections archives their sessions.
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM].iter() {
+        if let Err(e) = signal_hook::flag::register(*signal, Arc::clone(&stop)) {
+            println!("ERROR: {}", e);
+        }
+    }
+    let server = Arc::new(server);
+    let c_server = Arc::clone(&server);
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(100));
+        }
+        c_server.unblock();
+    });
+
+    http_api::serve(&server, hub, ctx);
+    println!("Stopped");
+}
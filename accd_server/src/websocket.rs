@@ -0,0 +1,167 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tiny_http::{Header, ReadWrite, Request, Response, StatusCode};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use accd_core::accd_connection_manager::ACCDConnectionEvent;
+use accd_core::accd_protocol::ListenResult;
+
+struct Client {
+    /// Only messages of this connection are sent to the client, all of them when `None`.
+    connection_id: Option<String>,
+    sender: SyncSender<String>,
+}
+
+/// The client is pinged this often, its frames are read until the pong arrives.
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Messages waiting for a client, a client that falls this far behind is dropped.
+const CLIENT_QUEUE: usize = 1024;
+
+/// Keeps the connected WebSocket clients, every client gets its own writer thread.
+#[derive(Default)]
+pub struct WebSocketHub {
+    clients: Mutex<Vec<Client>>,
+}
+
+impl WebSocketHub {
+    pub fn new() -> Self {
+        WebSocketHub::default()
+    }
+
+    pub fn broadcast(&self, connection_id: &str, message: &Value) {
+        let text = message.to_string();
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|client| match &client.connection_id {
+                Some(id) if id != connection_id => true,
+                _ => client.sender.try_send(text.clone()).is_ok(),
+            });
+    }
+
+    pub fn accept(&self, request: Request, connection_id: Option<String>) {
+        let key = match request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Sec-WebSocket-Key"))
+        {
+            Some(header) => derive_accept_key(header.value.as_bytes()),
+            None => {
+                let _ = request.respond(Response::empty(StatusCode(400)));
+                return;
+            }
+        };
+
+        let response = Response::empty(StatusCode(101))
+            .with_header(header("Upgrade", "websocket"))
+            .with_header(header("Connection", "Upgrade"))
+            .with_header(header("Sec-WebSocket-Accept", &key));
+        let stream = request.upgrade("websocket", response);
+
+        let (tx, rx) = mpsc::sync_channel::<String>(CLIENT_QUEUE);
+        self.clients.lock().unwrap().push(Client {
+            connection_id,
+            sender: tx,
+        });
+
+        thread::spawn(move || {
+            let websocket = WebSocket::from_raw_socket(stream, Role::Server, None);
+            // Returning drops the receiver, the next broadcast forgets the client.
+            match serve_client(websocket, rx) {
+                Ok(()) => println!("WebSocket client closed"),
+                Err(e) => println!("WebSocket client dropped: {}", e),
+            }
+        });
+    }
+}
+
+/// Sends the queued messages to the client. The upgraded stream can't be split, so
+/// the client is only read after a ping: reading until its pong answers its pings
+/// and notices when it closes.
+#[allow(clippy::result_large_err)]
+fn serve_client(
+    mut websocket: WebSocket<Box<dyn ReadWrite + Send>>,
+    rx: Receiver<String>,
+) -> tungstenite::Result<()> {
+    let mut next_ping = Instant::now() + PING_INTERVAL;
+    loop {
+        match rx.recv_timeout(next_ping.saturating_duration_since(Instant::now())) {
+            Ok(text) => {
+                websocket.write(Message::text(text))?;
+                for text in rx.try_iter() {
+                    websocket.write(Message::text(text))?;
+                }
+                websocket.flush()?;
+                if Instant::now() < next_ping {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        websocket.send(Message::Ping(Vec::new()))?;
+        loop {
+            match websocket.read()? {
+                Message::Pong(_) => break,
+                Message::Close(_) => {
+                    // Sends the close reply queued by `read`.
+                    return match websocket.flush() {
+                        Err(tungstenite::Error::ConnectionClosed) => Ok(()),
+                        result => result,
+                    };
+                }
+                // Pings are answered by the next read or flush, text from clients is ignored.
+                _ => {}
+            }
+        }
+        websocket.flush()?;
+        next_ping = Instant::now() + PING_INTERVAL;
+    }
+}
+
+/// The message pushed to the WebSocket clients for an event, car updates and
+/// broadcasting events are sent one by one so clients only get what changed.
+pub fn delta(event: &ACCDConnectionEvent) -> Option<Value> {
+    let (msg_type, data) = match &event.listen_result {
+        ListenResult::RegistrationResult(reg_result) => {
+            ("registration_result", serde_json::to_value(reg_result))
+        }
+        ListenResult::RealTimeUpdate(rtu) => ("realtime_update", serde_json::to_value(rtu)),
+        ListenResult::RealTimeCarUpdate(car_update) => {
+            ("realtime_car_update", serde_json::to_value(car_update))
+        }
+        ListenResult::EntryList(entry_list_cars) => {
+            ("entry_list", serde_json::to_value(entry_list_cars))
+        }
+        ListenResult::EntryListCar(car_info) => ("entry_list_car", serde_json::to_value(car_info)),
+        ListenResult::TrackData(trk_data) => ("track_data", serde_json::to_value(trk_data)),
+        ListenResult::BroadcastingEvent(event) => {
+            ("broadcasting_event", serde_json::to_value(event))
+        }
+        ListenResult::Session(event) => ("session", serde_json::to_value(event)),
+        ListenResult::Grid(event) => ("grid", serde_json::to_value(event)),
+        ListenResult::Pit(event) => ("pit", serde_json::to_value(event)),
+        ListenResult::Conditions(event) => ("conditions", serde_json::to_value(event)),
+        ListenResult::SpeedTrap(event) => ("speed_trap", serde_json::to_value(event)),
+        ListenResult::EntryListSync(event) => ("entry_list_sync", serde_json::to_value(event)),
+        ListenResult::Error => return None,
+    };
+
+    Some(json!({
+        "connection": event.connection_id,
+        "type": msg_type,
+        "data": data.ok()?,
+    }))
+}
+
+pub fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
@@ -0,0 +1,281 @@
+use std::sync::{Arc, Mutex};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use accd_core::accd_broadcasting_event::{ACCDBroadcastingEvent, BroadcastingCarEventType};
+use accd_core::accd_protocol::ACCDProtocol;
+use accd_core::accd_session_state::ACCDSessionState;
+use accd_core::accd_track_data::ACCDTrackData;
+
+pub const REPLAY_SECONDS: [f32; 2] = [10.0, 30.0];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AppTabs {
+    Connection = 0,
+    Leaderboard = 1,
+    Cameras = 2,
+    Huds = 3,
+    Replay = 4,
+    RealtimeUpdate = 5,
+    Track = 6,
+    Events = 7,
+}
+
+impl AppTabs {
+    pub const ALL: [AppTabs; 8] = [
+        AppTabs::Connection,
+        AppTabs::Leaderboard,
+        AppTabs::Cameras,
+        AppTabs::Huds,
+        AppTabs::Replay,
+        AppTabs::RealtimeUpdate,
+        AppTabs::Track,
+        AppTabs::Events,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            AppTabs::Connection => "Connection",
+            AppTabs::Leaderboard => "Leaderboard",
+            AppTabs::Cameras => "Cameras",
+            AppTabs::Huds => "Huds",
+            AppTabs::Replay => "Replay",
+            AppTabs::RealtimeUpdate => "Realtime Update",
+            AppTabs::Track => "Track",
+            AppTabs::Events => "Events",
+        }
+    }
+
+    fn next(self) -> AppTabs {
+        AppTabs::ALL[(self as usize + 1) % AppTabs::ALL.len()]
+    }
+
+    fn previous(self) -> AppTabs {
+        AppTabs::ALL[(self as usize + AppTabs::ALL.len() - 1) % AppTabs::ALL.len()]
+    }
+}
+
+pub struct App {
+    pub tab: AppTabs,
+    pub selected_car: Option<u16>,
+    pub selected_camera_set: usize,
+    pub selected_camera: usize,
+    pub selected_hud: usize,
+    pub selected_replay: usize,
+    pub selected_event: usize,
+    pub accdp: Arc<Mutex<ACCDProtocol>>,
+    pub state: Arc<Mutex<ACCDSessionState>>,
+}
+
+/// Camera sets sorted by name, so that they keep the same order between frames.
+pub fn camera_sets(track_data: &ACCDTrackData) -> Vec<(String, Vec<String>)> {
+    let mut camera_sets: Vec<(String, Vec<String>)> = track_data
+        .camera_sets
+        .iter()
+        .map(|(camera_set, cameras)| (camera_set.clone(), cameras.clone()))
+        .collect();
+    camera_sets.sort();
+    camera_sets
+}
+
+impl App {
+    pub fn new(accdp: Arc<Mutex<ACCDProtocol>>, state: Arc<Mutex<ACCDSessionState>>) -> Self {
+        App {
+            tab: AppTabs::Connection,
+            selected_car: None,
+            selected_camera_set: 0,
+            selected_camera: 0,
+            selected_hud: 0,
+            selected_replay: 0,
+            selected_event: 0,
+            accdp,
+            state,
+        }
+    }
+
+    /// Handles a key press, returns `true` when the application should quit.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let state = Arc::clone(&self.state);
+        let state = state.lock().unwrap();
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Tab => self.tab = self.tab.next(),
+            KeyCode::BackTab => self.tab = self.tab.previous(),
+            KeyCode::Char(c @ '1'..='8') => {
+                self.tab = AppTabs::ALL[c.to_digit(10).unwrap() as usize - 1];
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(&state, -1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(&state, 1),
+            KeyCode::Left | KeyCode::Char('h') if self.tab == AppTabs::Cameras => {
+                self.move_camera_set(&state, -1, false);
+            }
+            KeyCode::Right | KeyCode::Char('l') if self.tab == AppTabs::Cameras => {
+                self.move_camera_set(&state, 1, false);
+            }
+            KeyCode::Enter => self.activate(&state),
+            KeyCode::Char('n') => {
+                self.move_car_selection(&state, 1);
+                self.focus_selected_car();
+            }
+            KeyCode::Char('p') => {
+                self.move_car_selection(&state, -1);
+                self.focus_selected_car();
+            }
+            KeyCode::Char('f') => self.focus_selected_car(),
+            KeyCode::Char(']') => self.move_camera_set(&state, 1, true),
+            KeyCode::Char('[') => self.move_camera_set(&state, -1, true),
+            KeyCode::Char('r') => self.replay(&state, REPLAY_SECONDS[0]),
+            KeyCode::Char('R') => self.replay(&state, REPLAY_SECONDS[1]),
+            _ => {}
+        }
+
+        false
+    }
+
+    fn move_selection(&mut self, state: &ACCDSessionState, step: i32) {
+        match self.tab {
+            AppTabs::Leaderboard => self.move_car_selection(state, step),
+            AppTabs::Cameras => {
+                let cameras_len = camera_sets(&state.track_data)
+                    .get(self.selected_camera_set)
+                    .map_or(0, |(_, cameras)| cameras.len());
+                self.selected_camera = step_index(self.selected_camera, cameras_len, step);
+            }
+            AppTabs::Huds => {
+                self.selected_hud =
+                    step_index(self.selected_hud, state.track_data.hud_pages.len(), step);
+            }
+            AppTabs::Replay => {
+                self.selected_replay = step_index(self.selected_replay, REPLAY_SECONDS.len(), step);
+            }
+            AppTabs::Events => {
+                self.selected_event =
+                    step_index(self.selected_event, state.broadcasting_events.len(), step);
+            }
+            _ => {}
+        }
+    }
+
+    fn move_car_selection(&mut self, state: &ACCDSessionState, step: i32) {
+        let standings = state.standings();
+        let current = self
+            .selected_car
+            .and_then(|car_index| {
+                standings
+                    .iter()
+                    .position(|standing| standing.car_info.car_index == car_index)
+            })
+            .unwrap_or(0);
+
+        let next = match self.selected_car {
+            Some(_) => step_index(current, standings.len(), step),
+            None => 0,
+        };
+        self.selected_car = standings
+            .get(next)
+            .map(|standing| standing.car_info.car_index);
+    }
+
+    fn move_camera_set(&mut self, state: &ACCDSessionState, step: i32, apply: bool) {
+        let camera_sets = camera_sets(&state.track_data);
+        self.selected_camera_set = step_index(self.selected_camera_set, camera_sets.len(), step);
+        self.selected_camera = 0;
+
+        if apply {
+            if let Some((camera_set, cameras)) = camera_sets.get(self.selected_camera_set) {
+                if let Some(camera) = cameras.first() {
+                    self.accdp
+                        .lock()
+                        .unwrap()
+                        .set_camera(camera_set.clone(), camera.clone());
+                }
+            }
+        }
+    }
+
+    fn activate(&mut self, state: &ACCDSessionState) {
+        match self.tab {
+            AppTabs::Leaderboard => self.focus_selected_car(),
+            AppTabs::Cameras => {
+                let camera_sets = camera_sets(&state.track_data);
+                if let Some((camera_set, cameras)) = camera_sets.get(self.selected_camera_set) {
+                    if let Some(camera) = cameras.get(self.selected_camera) {
+                        self.accdp
+                            .lock()
+                            .unwrap()
+                            .set_camera(camera_set.clone(), camera.clone());
+                    }
+                }
+            }
+            AppTabs::Huds => {
+                if let Some(hud_page) = state.track_data.hud_pages.get(self.selected_hud) {
+                    self.accdp
+                        .lock()
+                        .unwrap()
+                        .request_hud_page(hud_page.clone());
+                }
+            }
+            AppTabs::Replay => self.replay(state, REPLAY_SECONDS[self.selected_replay]),
+            AppTabs::Events => {
+                // Newest events are listed first.
+                if let Some(broadcasting_event) = state
+                    .broadcasting_events
+                    .iter()
+                    .rev()
+                    .nth(self.selected_event)
+                {
+                    self.replay_event(broadcasting_event);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn focus_selected_car(&self) {
+        if let Some(car_index) = self.selected_car {
+            self.accdp
+                .lock()
+                .unwrap()
+                .set_focus(Some(car_index), "".to_string(), "".to_string());
+        }
+    }
+
+    fn replay(&self, state: &ACCDSessionState, duration: f32) {
+        let realtime_update = &state.realtime_update;
+        let start_time = realtime_update.session_time.as_millis() as f32;
+
+        self.accdp.lock().unwrap().request_instant_replay(
+            start_time - (duration * 1000.0),
+            duration * 1000.0,
+            realtime_update.focused_car_index,
+            realtime_update.active_camera_set.clone(),
+            realtime_update.active_camera.clone(),
+        );
+    }
+
+    fn replay_event(&self, broadcasting_event: &ACCDBroadcastingEvent) {
+        let (replay_seconds_back, replay_duration) = match broadcasting_event.event_type {
+            BroadcastingCarEventType::PenaltyCommMsg => (10.0, 6.0),
+            _ => (10.0, 10.0),
+        };
+
+        self.accdp.lock().unwrap().request_instant_replay(
+            (broadcasting_event.event_time.as_millis() as f32) - replay_seconds_back * 1000.0,
+            replay_duration * 1000.0,
+            broadcasting_event.event_car_id,
+            "".to_string(),
+            "".to_string(),
+        );
+    }
+}
+
+fn step_index(index: usize, len: usize, step: i32) -> usize {
+    if len == 0 {
+        return 0;
+    }
+
+    let index = index.min(len - 1) as i32 + step;
+    index.rem_euclid(len as i32) as usize
+}
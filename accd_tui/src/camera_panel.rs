@@ -0,0 +1,46 @@
+use ratatui::layout::Rect;
+use ratatui::widgets::{List, ListItem, ListState};
+use ratatui::Frame;
+
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::{camera_sets, App};
+use crate::ui::{highlight_style, panel_block, split_area};
+
+pub fn draw(frame: &mut Frame, area: Rect, app: &App, state: &ACCDSessionState) {
+    let camera_sets = camera_sets(&state.track_data);
+    let realtime_data = &state.realtime_update;
+    let [sets_area, cameras_area] = split_area(area, 30);
+
+    let sets = List::new(camera_sets.iter().map(|(camera_set, _)| {
+        if *camera_set == realtime_data.active_camera_set {
+            ListItem::new(format!("* {}", camera_set))
+        } else {
+            ListItem::new(format!("  {}", camera_set))
+        }
+    }))
+    .highlight_style(highlight_style())
+    .block(panel_block("Camera sets"));
+
+    let mut sets_state = ListState::default().with_selected(Some(app.selected_camera_set));
+    frame.render_stateful_widget(sets, sets_area, &mut sets_state);
+
+    let (camera_set, cameras) = match camera_sets.get(app.selected_camera_set) {
+        Some((camera_set, cameras)) => (camera_set.clone(), cameras.clone()),
+        None => ("".to_string(), Vec::new()),
+    };
+
+    let title = format!("Cameras {}", camera_set);
+    let cameras = List::new(cameras.iter().map(|camera| {
+        if camera_set == realtime_data.active_camera_set && *camera == realtime_data.active_camera {
+            ListItem::new(format!("* {}", camera))
+        } else {
+            ListItem::new(format!("  {}", camera))
+        }
+    }))
+    .highlight_style(highlight_style())
+    .block(panel_block(&title));
+
+    let mut cameras_state = ListState::default().with_selected(Some(app.selected_camera));
+    frame.render_stateful_widget(cameras, cameras_area, &mut cameras_state);
+}
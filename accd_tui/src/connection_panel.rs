@@ -0,0 +1,28 @@
+use ratatui::layout::Rect;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::App;
+use crate::ui::panel_block;
+
+pub fn draw(frame: &mut Frame, area: Rect, app: &App, state: &ACCDSessionState) {
+    let reg_result = &state.registration_result;
+    let destination_addr = app.accdp.lock().unwrap().config.destination_addr;
+
+    let text = format!(
+        "Addr: {}\n\
+        Id: {}\n\
+        Connected: {}\n\
+        Read only: {}\n\
+        Msg: {}",
+        destination_addr,
+        reg_result.connection_id,
+        reg_result.connection_success > 0,
+        reg_result.is_read_only > 0,
+        reg_result.err_msg.trim_matches(char::from(0))
+    );
+
+    frame.render_widget(Paragraph::new(text).block(panel_block("Connection")), area);
+}
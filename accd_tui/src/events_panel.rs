@@ -0,0 +1,26 @@
+use ratatui::layout::Rect;
+use ratatui::widgets::{List, ListState};
+use ratatui::Frame;
+
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::App;
+use crate::ui::{highlight_style, panel_block};
+
+pub fn draw(frame: &mut Frame, area: Rect, app: &App, state: &ACCDSessionState) {
+    let events = List::new(state.broadcasting_events.iter().rev().map(|event| {
+        let car = match &event.event_car_data {
+            Some(car_info) => format!("#{} {}", car_info.race_number, car_info.team_name),
+            None => "-".to_string(),
+        };
+        format!(
+            "{} | {:?} | {} | {}",
+            event.event_time, event.event_type, car, event.event_msg
+        )
+    }))
+    .highlight_style(highlight_style())
+    .block(panel_block("Events (enter to replay)"));
+
+    let mut events_state = ListState::default().with_selected(Some(app.selected_event));
+    frame.render_stateful_widget(events, area, &mut events_state);
+}
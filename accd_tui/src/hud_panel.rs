@@ -0,0 +1,25 @@
+use ratatui::layout::Rect;
+use ratatui::widgets::{List, ListItem, ListState};
+use ratatui::Frame;
+
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::App;
+use crate::ui::{highlight_style, panel_block};
+
+pub fn draw(frame: &mut Frame, area: Rect, app: &App, state: &ACCDSessionState) {
+    let current_hud_page = &state.realtime_update.current_hud_page;
+
+    let huds = List::new(state.track_data.hud_pages.iter().map(|hud_page| {
+        if hud_page == current_hud_page {
+            ListItem::new(format!("* {}", hud_page))
+        } else {
+            ListItem::new(format!("  {}", hud_page))
+        }
+    }))
+    .highlight_style(highlight_style())
+    .block(panel_block("Huds"));
+
+    let mut huds_state = ListState::default().with_selected(Some(app.selected_hud));
+    frame.render_stateful_widget(huds, area, &mut huds_state);
+}
@@ -0,0 +1,89 @@
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Row, Table, TableState};
+use ratatui::Frame;
+
+use accd_core::accd_realtime_car_update::CarLocationEnum;
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::App;
+use crate::ui::{fmt_gap, fmt_lap_time, highlight_style, panel_block};
+
+pub fn draw(frame: &mut Frame, area: Rect, app: &App, state: &ACCDSessionState) {
+    let standings = state.standings();
+    let focused_car_index = state.realtime_update.focused_car_index;
+
+    let rows = standings.iter().map(|standing| {
+        let car_info = &standing.car_info;
+        let driver_name = match car_info.drivers.get(car_info.current_driver_index as usize) {
+            Some(driver) => format!("{} {}", driver.first_name, driver.last_name),
+            None => "".to_string(),
+        };
+
+        let cells = match &standing.car_update {
+            Some(car_update) => vec![
+                format!("P{}", car_update.position),
+                format!("#{}", car_info.race_number),
+                car_info.team_name.clone(),
+                driver_name,
+                car_update.laps.to_string(),
+                fmt_gap(standing.gap_to_leader),
+                fmt_gap(standing.gap_ahead),
+                fmt_lap_time(car_update.last_lap.lap_time_ms),
+                fmt_lap_time(car_update.best_session_lap.lap_time_ms),
+                format!("{:+.3}", car_update.delta as f32 / 1000.0),
+                match car_update.car_location {
+                    CarLocationEnum::Track => "".to_string(),
+                    ref location => format!("{:?}", location),
+                },
+            ],
+            None => vec![
+                "-".to_string(),
+                format!("#{}", car_info.race_number),
+                car_info.team_name.clone(),
+                driver_name,
+            ],
+        };
+
+        let row = Row::new(cells);
+        if focused_car_index == Some(car_info.car_index) {
+            row.style(Style::default().fg(Color::Cyan))
+        } else {
+            row
+        }
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Length(5),
+            Constraint::Percentage(30),
+            Constraint::Percentage(25),
+            Constraint::Length(5),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Pos", "#", "Team", "Driver", "Laps", "Gap", "Int", "Last", "Best", "Delta", "",
+        ])
+        .style(Style::default().fg(Color::Yellow)),
+    )
+    .row_highlight_style(highlight_style())
+    .block(panel_block("Leaderboard"));
+
+    let selected = app.selected_car.and_then(|car_index| {
+        standings
+            .iter()
+            .position(|standing| standing.car_info.car_index == car_index)
+    });
+    let mut table_state = TableState::default().with_selected(selected);
+
+    frame.render_stateful_widget(table, area, &mut table_state);
+}
@@ -0,0 +1,106 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyEventKind};
+
+use accd_core::accd_config_loader::{ACCDConfigLoader, USAGE};
+use accd_core::accd_protocol::ACCDProtocol;
+use accd_core::accd_session_state::ACCDSessionState;
+use accd_core::accd_track_map::DEFAULT_TRACK_MAPS_DIR;
+
+mod app;
+use crate::app::App;
+
+mod ui;
+
+mod camera_panel;
+mod connection_panel;
+mod events_panel;
+mod hud_panel;
+mod leaderboard_panel;
+mod realtime_update_panel;
+mod replay_panel;
+mod track_panel;
+
+fn main() {
+    let loader = match ACCDConfigLoader::from_env() {
+        Ok(loader) => loader,
+        Err(e) => {
+            println!("ERROR: {}\n{}", e, USAGE);
+            process::exit(1);
+        }
+    };
+    if let Some(arg) = loader.args.first() {
+        println!("ERROR: unexpected argument {}\n{}", arg, USAGE);
+        process::exit(1);
+    }
+
+    let config = match loader.load() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if loader.print_config {
+        print!("{}", config.to_toml(true));
+        return;
+    }
+
+    let mut accdp = ACCDProtocol::new(config);
+    if let Err(e) = accdp.connect() {
+        println!("ERROR({}): {}", accdp.config.bind_addr, e);
+        process::exit(1);
+    }
+
+    let mut state = ACCDSessionState::new();
+    state.race_laps = accdp.config.race_laps;
+    state.track_maps_dir = Some(PathBuf::from(DEFAULT_TRACK_MAPS_DIR));
+
+    let accdp = Arc::new(Mutex::new(accdp));
+    let state = Arc::new(Mutex::new(state));
+
+    let c_accdp = Arc::clone(&accdp);
+    let c_state = Arc::clone(&state);
+    thread::spawn(move || loop {
+        // The protocol stays free for the UI while waiting for ACC.
+        let listen_result = ACCDProtocol::listen_shared(&c_accdp);
+        c_state.lock().unwrap().update(&listen_result);
+    });
+
+    let mut app = App::new(Arc::clone(&accdp), state);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app);
+    ratatui::restore();
+
+    accdp.lock().unwrap().disconnect();
+    app.state.lock().unwrap().end_session();
+
+    if let Err(e) = result {
+        println!("Application error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(terminal: &mut ratatui::DefaultTerminal, app: &mut App) -> Result<(), Box<dyn Error>> {
+    loop {
+        {
+            // Drawn under the lock, copying the state would copy every archived session.
+            let state = app.state.lock().unwrap();
+            terminal.draw(|frame| ui::draw(frame, app, &state))?;
+        }
+
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && app.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
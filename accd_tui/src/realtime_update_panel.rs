@@ -0,0 +1,75 @@
+use ratatui::layout::Rect;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::App;
+use crate::ui::{fmt_duration, fmt_lap_time, fmt_optional, fmt_remaining, panel_block, split_area};
+
+pub fn draw(frame: &mut Frame, area: Rect, _app: &App, state: &ACCDSessionState) {
+    let realtime_data = &state.realtime_update;
+    let [session_area, conditions_area] = split_area(area, 50);
+
+    let session = format!(
+        "Session type: {:?}\n\
+        Phase: {:?}\n\
+        Session time: {}\n\
+        Session end time: {}\n\
+        Session remaining time: {}\n\
+        Remaining: {}\n\
+        ---\n\
+        Best session lap: {}\n\
+        Best lap car: {}\n\
+        Best lap driver: {}\n\
+        ---\n\
+        Hud page: {}\n\
+        Camera: {} | {}\n\
+        Focused car: {}\n\
+        ---\n\
+        Replay playing: {}\n\
+        Replay session time: {}\n\
+        Replay remaining time: {}",
+        realtime_data.session_type,
+        realtime_data.phase,
+        realtime_data.session_time,
+        realtime_data.session_end_time,
+        realtime_data.session_remaining_time,
+        fmt_remaining(&state.remaining()),
+        fmt_lap_time(realtime_data.best_session_lap.lap_time_ms),
+        fmt_optional(realtime_data.bestlap_car_index),
+        fmt_optional(realtime_data.bestlap_driver_index),
+        realtime_data.current_hud_page,
+        realtime_data.active_camera_set,
+        realtime_data.active_camera,
+        fmt_optional(realtime_data.focused_car_index),
+        realtime_data.is_replay_playing,
+        fmt_optional(realtime_data.replay_session_time),
+        fmt_optional(realtime_data.replay_remaining_time)
+    );
+
+    let conditions = format!(
+        "Time of day: {}\n\
+        Ambient temp: {}\n\
+        Track temp: {}\n\
+        ---\n\
+        Rain level: {}\n\
+        Clouds: {}\n\
+        Wetness: {}",
+        fmt_duration(realtime_data.time_of_day.to_duration().unwrap_or_default()),
+        realtime_data.ambient_temp,
+        realtime_data.track_temp,
+        realtime_data.rain_level,
+        realtime_data.clouds,
+        realtime_data.wetness
+    );
+
+    frame.render_widget(
+        Paragraph::new(session).block(panel_block("Realtime Update")),
+        session_area,
+    );
+    frame.render_widget(
+        Paragraph::new(conditions).block(panel_block("Conditions")),
+        conditions_area,
+    );
+}
@@ -0,0 +1,38 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::widgets::{List, ListState, Paragraph};
+use ratatui::Frame;
+
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::{App, REPLAY_SECONDS};
+use crate::ui::{fmt_optional, highlight_style, panel_block};
+
+pub fn draw(frame: &mut Frame, area: Rect, app: &App, state: &ACCDSessionState) {
+    let [buttons_area, status_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(5)]).areas(area);
+
+    let buttons = List::new(
+        REPLAY_SECONDS
+            .iter()
+            .map(|seconds| format!("Replay last {}s", seconds)),
+    )
+    .highlight_style(highlight_style())
+    .block(panel_block("Replay"));
+
+    let mut buttons_state = ListState::default().with_selected(Some(app.selected_replay));
+    frame.render_stateful_widget(buttons, buttons_area, &mut buttons_state);
+
+    let realtime_data = &state.realtime_update;
+    let status = format!(
+        "Replay playing: {}\n\
+        Replay session time: {}\n\
+        Replay remaining time: {}",
+        realtime_data.is_replay_playing,
+        fmt_optional(realtime_data.replay_session_time),
+        fmt_optional(realtime_data.replay_remaining_time)
+    );
+    frame.render_widget(
+        Paragraph::new(status).block(panel_block("Status")),
+        status_area,
+    );
+}
@@ -0,0 +1,93 @@
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::text::Span;
+use ratatui::widgets::canvas::{Canvas, Points};
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+
+use accd_core::accd_car_info::CarClass;
+use accd_core::accd_session_state::ACCDSessionState;
+
+use crate::app::App;
+use crate::ui::{panel_block, split_area};
+
+pub fn draw(frame: &mut Frame, area: Rect, _app: &App, state: &ACCDSessionState) {
+    let [info_area, map_area] = split_area(area, 30);
+
+    let track_data = &state.track_data;
+    let text = format!(
+        "{}\n({}m)\n\n{} camera sets | {} hud pages\n\nMap: {:.0}% of the lap known",
+        track_data.track_name,
+        track_data.track_meters,
+        track_data.camera_sets.len(),
+        track_data.hud_pages.len(),
+        state.track_map.coverage() * 100.0
+    );
+    frame.render_widget(Paragraph::new(text).block(panel_block("Track")), info_area);
+
+    let outline: Vec<(f64, f64)> = state
+        .track_map
+        .outline()
+        .iter()
+        .map(|(x, y)| (*x as f64, *y as f64))
+        .collect();
+    let cars = state.map_cars();
+
+    // Bounds of the outline, of the cars until the outline is known.
+    let mut points: Vec<(f64, f64)> = outline.clone();
+    if points.len() < 2 {
+        points = cars
+            .iter()
+            .map(|car| (car.x as f64, car.y as f64))
+            .collect();
+    }
+    let bounds = |values: Vec<f64>| {
+        let min = values.iter().cloned().fold(f64::MAX, f64::min);
+        let max = values.iter().cloned().fold(f64::MIN, f64::max);
+        if min > max {
+            [0.0, 1.0]
+        } else {
+            [min - 10.0, max + 10.0]
+        }
+    };
+    let x_bounds = bounds(points.iter().map(|(x, _)| *x).collect());
+    let y_bounds = bounds(points.iter().map(|(_, y)| *y).collect());
+
+    let canvas = Canvas::default()
+        .block(panel_block("Map"))
+        .x_bounds(x_bounds)
+        .y_bounds(y_bounds)
+        .paint(|ctx| {
+            ctx.draw(&Points {
+                coords: &outline,
+                color: Color::DarkGray,
+            });
+            ctx.layer();
+            for car in cars.iter() {
+                let color = if car.focused {
+                    Color::White
+                } else {
+                    class_color(car.car_class)
+                };
+                ctx.print(
+                    car.x as f64,
+                    car.y as f64,
+                    Span::styled(car.race_number.to_string(), color),
+                );
+            }
+        });
+    frame.render_widget(canvas, map_area);
+}
+
+fn class_color(car_class: CarClass) -> Color {
+    match car_class {
+        CarClass::GT3 => Color::Red,
+        CarClass::GT4 => Color::Blue,
+        CarClass::GT2 => Color::Magenta,
+        CarClass::Cup => Color::Yellow,
+        CarClass::ST => Color::Green,
+        CarClass::CHL => Color::LightRed,
+        CarClass::TCX => Color::Cyan,
+        CarClass::Unknown => Color::Gray,
+    }
+}
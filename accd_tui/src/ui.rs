@@ -0,0 +1,111 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Paragraph, Tabs};
+use ratatui::Frame;
+
+use accd_core::accd_session_state::{ACCDRemaining, ACCDSessionState};
+use accd_core::accd_session_time::SessionTime;
+
+use crate::app::{App, AppTabs};
+use crate::{
+    camera_panel, connection_panel, events_panel, hud_panel, leaderboard_panel,
+    realtime_update_panel, replay_panel, track_panel,
+};
+
+const HELP: &str = " q quit | tab/1-8 switch tab | ↑↓ select | enter apply | n/p focus next/prev car | [ ] camera set | r/R replay 10s/30s";
+
+pub fn draw(frame: &mut Frame, app: &App, state: &ACCDSessionState) {
+    let [tabs_area, body_area, help_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let tabs = Tabs::new(AppTabs::ALL.iter().map(|tab| tab.title()))
+        .select(app.tab as usize)
+        .highlight_style(highlight_style())
+        .block(Block::bordered().title(" ACC Director LITE "));
+    frame.render_widget(tabs, tabs_area);
+
+    match app.tab {
+        AppTabs::Connection => connection_panel::draw(frame, body_area, app, state),
+        AppTabs::Leaderboard => leaderboard_panel::draw(frame, body_area, app, state),
+        AppTabs::Cameras => camera_panel::draw(frame, body_area, app, state),
+        AppTabs::Huds => hud_panel::draw(frame, body_area, app, state),
+        AppTabs::Replay => replay_panel::draw(frame, body_area, app, state),
+        AppTabs::RealtimeUpdate => realtime_update_panel::draw(frame, body_area, app, state),
+        AppTabs::Track => track_panel::draw(frame, body_area, app, state),
+        AppTabs::Events => events_panel::draw(frame, body_area, app, state),
+    }
+
+    frame.render_widget(
+        Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)),
+        help_area,
+    );
+}
+
+pub fn panel_block(title: &str) -> Block<'_> {
+    Block::bordered().title(format!(" {} ", title))
+}
+
+pub fn highlight_style() -> Style {
+    Style::default()
+        .fg(Color::Black)
+        .bg(Color::Yellow)
+        .add_modifier(Modifier::BOLD)
+}
+
+pub fn split_area(area: Rect, left: u16) -> [Rect; 2] {
+    Layout::horizontal([Constraint::Percentage(left), Constraint::Min(0)]).areas(area)
+}
+
+/// Formats lap and split times, `None` for laps that are not done yet.
+pub fn fmt_lap_time(lap_time_ms: Option<i32>) -> String {
+    match lap_time_ms {
+        Some(lap_time_ms) if lap_time_ms > 0 => {
+            SessionTime::from_millis(lap_time_ms as f64).to_string()
+        }
+        _ => "--:--.---".to_string(),
+    }
+}
+
+/// Formats gaps between cars, seconds under a minute.
+pub fn fmt_gap(gap: Option<SessionTime>) -> String {
+    match gap {
+        Some(gap) if gap.as_secs().abs() < 60.0 => format!("{:+.3}", gap.as_secs()),
+        Some(gap) if gap.is_negative() => gap.to_string(),
+        Some(gap) => format!("+{}", gap),
+        None => "-".to_string(),
+    }
+}
+
+pub fn fmt_optional<T: Display>(value: Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+pub fn fmt_remaining(remaining: &ACCDRemaining) -> String {
+    match remaining {
+        ACCDRemaining::Time(time) => time.to_string(),
+        ACCDRemaining::Laps(0) => "finished".to_string(),
+        ACCDRemaining::Laps(1) => "last lap".to_string(),
+        ACCDRemaining::Laps(laps) => format!("{} laps", laps),
+        ACCDRemaining::Unknown => "-".to_string(),
+    }
+}
+
+pub fn fmt_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}